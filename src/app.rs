@@ -1,14 +1,38 @@
+use crate::batch_rename::{self, BatchItem, RuleEdit};
+use crate::columns::{ColumnLayout, ListColumn};
+use crate::dialog::{
+    prepare_confirmation, BatchRenameDialog, ConfirmAction, ConfirmDialog, ConflictDialog, Dialog,
+};
 use crate::fs_utils::{
-    copy_item, delete_item, move_item, open_file, read_dir, rename_item,
-    setup_applications_directory, DirEntry, PreviewContent, generate_thumbnail,
+    create_from_template, create_item, delete_item, list_templates, open_file, open_terminal,
+    read_dir, reload_entries, rename_item, setup_applications_directory, ConflictChoice, DirEntry, PreviewContent, generate_thumbnail,
+    load_preview,
+};
+use crate::keybindings::{self, Keymap};
+use crate::index::{IndexStatus, SearchIndex};
+use crate::jobs::{JobEvent, JobId, JobManager};
+use crate::location::{self, Completion, PathInput};
+use crate::journal::{self, FileOperation, Journal, JournalEntry, JournalFailure};
+use crate::open_with;
+use crate::pane::Pane;
+use crate::search::{MatchMode, Search, SearchEvent, SearchId, TextPreview};
+use crate::tab::{Tab, TabId};
+use crate::trash::{
+    delete_from_trash, empty_trash, list_trash, restore_item, trash_item, TrashEntry,
 };
+use crate::ui::item_layout::{self, ItemLayout};
 use crate::ui::view::view;
+use crate::ui::{file_grid, rubber_band, search_results, top_bar};
+use crate::watcher::{self, DirectoryChange};
 use dirs;
 use iced::executor;
-use iced::{Application, Command, Element, Theme};
-use iced::widget::image;
-use std::collections::HashSet;
-use std::path::PathBuf;
+use iced::keyboard::{self, Key, Modifiers};
+use iced::{event, Application, Command, Element, Event, Subscription, Theme};
+use iced::keyboard::key::Named;
+use iced::widget::{image, text_input};
+use iced::widget::scrollable::{self, AbsoluteOffset, RelativeOffset};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -32,6 +56,22 @@ pub enum GroupCriteria {
     MimeType,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewMode {
+    Grid,
+    List,
+    Columns,
+}
+
+// Arrow key directions for moving the selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ClipboardAction {
     Copy,
@@ -40,55 +80,158 @@ pub enum ClipboardAction {
 
 #[derive(Debug)]
 pub struct FileManager {
-    pub current_path: PathBuf,
-    pub entries: Vec<DirEntry>,
-    pub error: Option<String>,
-    pub selected_path: Option<PathBuf>,
-    history: Vec<PathBuf>,
-    history_index: usize,
+    pub panes: Vec<Pane>,
+    pub active_pane: usize,
+    next_tab_id: TabId,
+    pub keyboard_modifiers: Modifiers,
     pub show_hidden_files: bool,
-    pub sort_criteria: SortCriteria,
-    pub sort_order: SortOrder,
-    pub group_criteria: GroupCriteria,
-    pub collapsed_groups: HashSet<String>,
-    pub clipboard_item: Option<(PathBuf, ClipboardAction)>,
-    pub renaming_path: Option<PathBuf>,
-    pub rename_input_value: String,
+    pub clipboard: Option<(Vec<PathBuf>, ClipboardAction)>,
     pub preview_content: Option<PreviewContent>,
     pub show_details_panel: bool,
-    pub last_click_time: Option<Instant>,
-    pub last_clicked_path: Option<PathBuf>,
+    pub list_columns: ColumnLayout,
+    // Text of the location field while it is being edited (Ctrl+L)
+    pub path_input: Option<PathInput>,
+    keymap: Keymap,
+    // Apps that can open the file whose context menu was opened last
+    pub open_with_apps: Option<(PathBuf, Vec<open_with::App>)>,
+    // Folders in the folder whose breadcrumb menu was opened last
+    pub subfolders: Option<(PathBuf, Vec<PathBuf>)>,
+    // Files of the templates folder, offered under "New Document"
+    pub templates: Vec<PathBuf>,
+    // How the search field matches names, for every tab
+    pub search_mode: MatchMode,
+    // Typing in the folder view filters the items instead of jumping to one
+    pub type_to_filter: bool,
+    // Index of the home folder answering name searches, while indexing is on
+    pub search_index: Option<SearchIndex>,
+    pub trash_entries: Vec<TrashEntry>,
+    pub dialog: Option<Dialog>,
+    pub journal: Journal,
+    pub jobs: JobManager,
+    pub show_jobs_panel: bool,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     Navigate(PathBuf),
-    LoadEntries(Result<Vec<DirEntry>, String>),
+    LoadEntries(TabId, Result<Vec<DirEntry>, String>),
+    DirectoryChanged(PathBuf, DirectoryChange),
+    EntriesReloaded(PathBuf, Vec<(PathBuf, Option<DirEntry>)>),
     GoUp,
     GoBack,
     GoForward,
     ToggleHiddenFiles,
+    NewTab,
+    OpenInNewTab(PathBuf),
+    SelectTab(TabId),
+    NextTab,
+    PreviousTab,
+    CloseTab(TabId),
+    CloseActiveTab,
+    GridScrolled(TabId, AbsoluteOffset),
+    ToggleSplitView,
+    InPane(usize, Box<Message>),
+    CopyToOtherPane,
+    MoveToOtherPane,
     ItemClicked(PathBuf),
-    DeleteItem(PathBuf),
-    ItemDeleted(Result<(), String>),
-    CopyItem(PathBuf),
-    CutItem(PathBuf),
+    ModifiersChanged(Modifiers),
+    RubberBandSelect(Vec<PathBuf>),
+    SelectAll,
+    InvertSelection,
+    TrashSelection,
+    DeleteSelectionPermanently,
+    ItemsDeleted(Vec<FileOperation>, Result<(), String>),
+    CopySelection,
+    CutSelection,
     Paste,
-    ItemPasted(Result<(), String>),
+    PasteIntoTab(TabId),
+    JobUpdated(JobId, JobEvent),
+    PauseJob(JobId),
+    ResumeJob(JobId),
+    CancelJob(JobId),
+    ClearFinishedJobs,
+    ToggleJobsPanel,
+    ConflictApplyToAllToggled(bool),
+    ResolveConflict(ConflictChoice),
     StartRename(PathBuf),
     RenameInputChanged(String),
     ConfirmRename,
     CancelRename,
-    ItemRenamed(Result<(), String>),
+    ItemRenamed(Result<Vec<FileOperation>, String>),
+    BatchRenameSelection,
+    BatchRenameLoaded(Result<Vec<BatchItem>, String>),
+    BatchRenameEdited(RuleEdit),
     SetSortCriteria(SortCriteria),
     ToggleSortOrder,
     SetGroupCriteria(GroupCriteria),
     ToggleGroupCollapse(String),
+    SetViewMode(ViewMode),
+    KeyPressed(Key, Modifiers),
+    MoveSelection(Direction),
+    ItemsLocated(Direction, ItemLayout),
+    ItemLocated(PathBuf, ItemLayout),
+    ToggleTypeToFilter(bool),
+    OpenSelection,
+    RenameSelection,
+    FocusPathBar,
+    PathInputChanged(String),
+    CompletePathInput,
+    // The completion of the typed text
+    PathInputCompleted(String, Option<Completion>),
+    SubmitPathInput,
+    EscapePressed,
+    ItemContextMenu(PathBuf),
+    OpenWithAppsLoaded(PathBuf, Vec<open_with::App>),
+    SubfoldersMenu(PathBuf),
+    SubfoldersLoaded(PathBuf, Vec<PathBuf>),
+    OpenWith(PathBuf),
+    PasteIntoFolder(PathBuf),
+    ShowProperties,
+    CopyPath,
+    BackgroundContextMenu,
+    TemplatesLoaded(Vec<PathBuf>),
+    NewFolder,
+    NewFile,
+    NewDocument(PathBuf),
+    ItemCreated(Result<PathBuf, String>),
+    CreatedItemLoaded(PathBuf, Vec<(PathBuf, Option<DirEntry>)>),
+    OpenTerminalHere,
+    TerminalOpened(Result<(), String>),
+    FocusSearch,
+    SearchInputChanged(String),
+    FilterInputChanged(String),
+    SetSearchMode(MatchMode),
+    SearchUpdated(TabId, SearchId, SearchEvent),
+    ClearSearch,
+    OpenSearchResult(PathBuf),
+    RevealItem(PathBuf),
+    ToggleSearchIndex(bool),
+    IndexStatusChanged(IndexStatus),
+    OpenLineMatch(PathBuf, usize),
+    TextPreviewLoaded(TabId, SearchId, Result<TextPreview, String>),
+    ParentColumnsLoaded(TabId, Vec<(PathBuf, Vec<DirEntry>)>),
+    ResizeColumn(ListColumn, f32),
+    MoveColumn(ListColumn, f32),
     FileOpenResult(Result<(), String>),
-    LoadPreview(Result<PreviewContent, String>),
+    LoadPreview(PathBuf, Result<PreviewContent, String>),
     SetupApplicationsResult(Result<(), String>),
     ToggleDetailsPanel,
     ThumbnailLoaded(PathBuf, Option<image::Handle>),
+    ShowTrash,
+    TrashLoaded(Result<Vec<TrashEntry>, String>),
+    RestoreFromTrash(PathBuf),
+    TrashItemRestored(Result<PathBuf, String>),
+    DeleteFromTrash(PathBuf),
+    EmptyTrash,
+    TrashUpdated(Result<(), String>),
+    ShowConfirmation(Result<ConfirmDialog, String>),
+    DialogConfirmed,
+    DialogCancelled,
+    Undo,
+    Redo,
+    UndoFinished(Result<JournalEntry, JournalFailure>),
+    RedoFinished(Result<JournalEntry, JournalFailure>),
+    OperationsFingerprinted(Result<JournalEntry, String>),
 }
 
 impl Application for FileManager {
@@ -99,41 +242,48 @@ impl Application for FileManager {
 
     fn new(_flags: ()) -> (Self, Command<Message>) {
         let initial_path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
-        let initial_sort_criteria = SortCriteria::Name;
-        let initial_sort_order = SortOrder::Ascending;
-        let initial_group_criteria = GroupCriteria::None;
+        let initial_tab = Tab::new(
+            0,
+            initial_path,
+            SortCriteria::Name,
+            SortOrder::Ascending,
+            GroupCriteria::None,
+            ViewMode::Grid,
+        );
         let initial_state = FileManager {
-            current_path: initial_path.clone(),
-            entries: vec![],
-            error: None,
-            selected_path: None,
-            history: vec![initial_path.clone()],
-            history_index: 0,
+            panes: vec![Pane::new(initial_tab)],
+            active_pane: 0,
+            next_tab_id: 1,
+            keyboard_modifiers: Modifiers::default(),
             show_hidden_files: false,
-            sort_criteria: initial_sort_criteria,
-            sort_order: initial_sort_order,
-            group_criteria: initial_group_criteria,
-            collapsed_groups: HashSet::new(),
-            clipboard_item: None,
-            renaming_path: None,
-            rename_input_value: String::new(),
+            clipboard: None,
             preview_content: None,
             show_details_panel: true,
-            last_click_time: None,
-            last_clicked_path: None,
+            list_columns: ColumnLayout::default(),
+            path_input: None,
+            keymap: keybindings::load(),
+            open_with_apps: None,
+            subfolders: None,
+            templates: vec![],
+            search_mode: MatchMode::Substring,
+            type_to_filter: false,
+            search_index: None,
+            trash_entries: vec![],
+            dialog: None,
+            journal: Journal::default(),
+            jobs: JobManager::default(),
+            show_jobs_panel: false,
         };
 
+        let mut initial_state = initial_state;
+        let start_index = if SearchIndex::is_enabled() {
+            initial_state.start_index()
+        } else {
+            Command::none()
+        };
         let initial_commands = Command::batch([
-            Command::perform(
-                read_dir(
-                    initial_path,
-                    false,
-                    initial_sort_criteria,
-                    initial_sort_order,
-                    initial_group_criteria,
-                ),
-                Message::LoadEntries,
-            ),
+            initial_state.load_tab(initial_state.tab()),
+            start_index,
             Command::perform(
                 setup_applications_directory(),
                 Message::SetupApplicationsResult,
@@ -144,21 +294,24 @@ impl Application for FileManager {
     }
 
     fn title(&self) -> String {
-        format!("File Manager - {}", self.current_path.display())
+        let tab = self.tab();
+        if tab.showing_trash {
+            return "File Manager - Trash".to_string();
+        }
+        format!("File Manager - {}", tab.current_path.display())
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
         match &message {
             Message::Navigate(_)
-            | Message::LoadEntries(_)
             | Message::GoUp
             | Message::GoBack
             | Message::GoForward
             | Message::ToggleHiddenFiles
-            | Message::DeleteItem(_)
-            | Message::ItemDeleted(_)
+            | Message::TrashSelection
+            | Message::DeleteSelectionPermanently
+            | Message::ItemsDeleted(..)
             | Message::Paste
-            | Message::ItemPasted(_)
             | Message::ConfirmRename
             | Message::CancelRename
             | Message::ItemRenamed(_)
@@ -167,11 +320,10 @@ impl Application for FileManager {
             | Message::SetGroupCriteria(_) => {
                 self.preview_content = None;
             }
-            Message::ItemClicked(path) => if self.selected_path.as_ref() != Some(path) {},
             _ => {}
         }
 
-        if self.renaming_path.is_some() {
+        if self.tab().renaming_path.is_some() {
             match message {
                 Message::Navigate(_)
                 | Message::GoUp
@@ -181,9 +333,9 @@ impl Application for FileManager {
                 | Message::SetSortCriteria(_)
                 | Message::ToggleSortOrder
                 | Message::SetGroupCriteria(_)
-                | Message::DeleteItem(_) => {
-                    self.renaming_path = None;
-                    self.rename_input_value.clear();
+                | Message::TrashSelection
+                | Message::DeleteSelectionPermanently => {
+                    self.tab_mut().cancel_rename();
                 }
                 _ => {}
             }
@@ -193,24 +345,13 @@ impl Application for FileManager {
             Message::Navigate(path) => {
                 if path.is_dir() {
                     let target_path = path.canonicalize().unwrap_or(path);
-                    if target_path != self.current_path {
-                        self.current_path = target_path.clone();
-                        self.error = None;
-                        self.selected_path = None;
+                    self.path_input = None;
+                    let tab = self.tab_mut();
+                    if target_path != tab.current_path || tab.showing_trash {
+                        tab.open(target_path.clone());
+                        tab.update_history(target_path);
                         self.preview_content = None;
-                        self.renaming_path = None;
-                        self.rename_input_value.clear();
-                        self.update_history(target_path.clone());
-                        Command::perform(
-                            read_dir(
-                                target_path,
-                                self.show_hidden_files,
-                                self.sort_criteria,
-                                self.sort_order,
-                                self.group_criteria,
-                            ),
-                            Message::LoadEntries,
-                        )
+                        self.load_tab(self.tab())
                     } else {
                         Command::none()
                     }
@@ -218,44 +359,74 @@ impl Application for FileManager {
                     Command::perform(open_file(path), Message::FileOpenResult)
                 }
             }
-            Message::LoadEntries(result) => {
+            Message::LoadEntries(tab_id, result) => {
+                let is_active = self.tab().id == tab_id;
+                let Some(tab) = self.tab_by_id_mut(tab_id) else {
+                    return Command::none();
+                };
                 match result {
                     Ok(entries) => {
-                        self.entries = entries;
-                        self.error = None;
+                        tab.entries = entries;
+                        tab.error = None;
                     }
                     Err(e) => {
-                        self.error = Some(e);
-                        self.entries = vec![];
+                        tab.error = Some(e);
+                        tab.entries = vec![];
+                    }
+                }
+                // Items that are still there stay selected (and in rename) after a reload
+                let existing: HashSet<&PathBuf> = tab.entries.iter().map(|e| &e.path).collect();
+                tab.selection.retain(&existing);
+                if tab
+                    .renaming_path
+                    .as_ref()
+                    .is_none_or(|path| !existing.contains(path))
+                {
+                    tab.cancel_rename();
+                }
+                if is_active && tab.selection.is_empty() {
+                    self.preview_content = None;
+                }
+                Command::none()
+            }
+            Message::DirectoryChanged(dir, change) => {
+                let watching: Vec<&Tab> = self
+                    .all_tabs()
+                    .filter(|tab| !tab.showing_trash && tab.current_path == dir)
+                    .collect();
+                if watching.is_empty() {
+                    return Command::none();
+                }
+                match change {
+                    DirectoryChange::Items(paths) => Command::perform(
+                        reload_entries(paths, self.show_hidden_files),
+                        move |reloaded| Message::EntriesReloaded(dir, reloaded),
+                    ),
+                    DirectoryChange::Everything => {
+                        Command::batch(watching.into_iter().map(|tab| self.load_tab(tab)))
+                    }
+                }
+            }
+            Message::EntriesReloaded(dir, reloaded) => {
+                for tab in self.all_tabs_mut() {
+                    if !tab.showing_trash && tab.current_path == dir {
+                        tab.apply_reloaded_entries(&reloaded);
                     }
                 }
-                self.selected_path = None;
-                self.preview_content = None;
-                self.renaming_path = None;
-                self.rename_input_value.clear();
                 Command::none()
             }
             Message::GoUp => {
-                if let Some(parent) = self.current_path.parent() {
+                if self.tab().showing_trash {
+                    return self.leave_trash();
+                }
+                let tab = self.tab_mut();
+                if let Some(parent) = tab.current_path.parent() {
                     let parent_path = parent.to_path_buf();
-                    if parent_path != self.current_path {
-                        self.current_path = parent_path.clone();
-                        self.error = None;
-                        self.selected_path = None;
+                    if parent_path != tab.current_path {
+                        tab.open(parent_path.clone());
+                        tab.update_history(parent_path);
                         self.preview_content = None;
-                        self.renaming_path = None;
-                        self.rename_input_value.clear();
-                        self.update_history(parent_path.clone());
-                        Command::perform(
-                            read_dir(
-                                parent_path,
-                                self.show_hidden_files,
-                                self.sort_criteria,
-                                self.sort_order,
-                                self.group_criteria,
-                            ),
-                            Message::LoadEntries,
-                        )
+                        self.load_tab(self.tab())
                     } else {
                         Command::none()
                     }
@@ -264,211 +435,359 @@ impl Application for FileManager {
                 }
             }
             Message::GoBack => {
-                if self.can_go_back() {
-                    self.history_index -= 1;
-                    let path = self.history[self.history_index].clone();
-                    self.current_path = path.clone();
-                    self.error = None;
-                    self.selected_path = None;
-                    self.preview_content = None;
-                    self.renaming_path = None;
-                    self.rename_input_value.clear();
-                    Command::perform(
-                        read_dir(
-                            path,
-                            self.show_hidden_files,
-                            self.sort_criteria,
-                            self.sort_order,
-                            self.group_criteria,
-                        ),
-                        Message::LoadEntries,
-                    )
-                } else {
-                    Command::none()
+                if self.tab().showing_trash {
+                    return self.leave_trash();
+                }
+                let tab = self.tab_mut();
+                match tab.history_back() {
+                    Some(path) => {
+                        tab.open(path);
+                        self.preview_content = None;
+                        self.load_tab(self.tab())
+                    }
+                    None => Command::none(),
                 }
             }
             Message::GoForward => {
-                if self.can_go_forward() {
-                    self.history_index += 1;
-                    let path = self.history[self.history_index].clone();
-                    self.current_path = path.clone();
-                    self.error = None;
-                    self.selected_path = None;
-                    self.preview_content = None;
-                    self.renaming_path = None;
-                    self.rename_input_value.clear();
-                    Command::perform(
-                        read_dir(
-                            path,
-                            self.show_hidden_files,
-                            self.sort_criteria,
-                            self.sort_order,
-                            self.group_criteria,
-                        ),
-                        Message::LoadEntries,
-                    )
-                } else {
-                    Command::none()
+                let tab = self.tab_mut();
+                match tab.history_forward() {
+                    Some(path) => {
+                        tab.open(path);
+                        self.preview_content = None;
+                        self.load_tab(self.tab())
+                    }
+                    None => Command::none(),
                 }
             }
             Message::ToggleHiddenFiles => {
                 self.show_hidden_files = !self.show_hidden_files;
                 self.preview_content = None;
-                self.renaming_path = None;
-                self.rename_input_value.clear();
-                Command::perform(
-                    read_dir(
-                        self.current_path.clone(),
-                        self.show_hidden_files,
-                        self.sort_criteria,
-                        self.sort_order,
-                        self.group_criteria,
-                    ),
-                    Message::LoadEntries,
+                self.tab_mut().cancel_rename();
+                // Searches start over to take in (or leave out) hidden items
+                let searches: Vec<(TabId, String)> = self
+                    .all_tabs()
+                    .filter_map(|tab| Some((tab.id, tab.search.as_ref()?.query.clone())))
+                    .collect();
+                let restarted: Vec<Command<Message>> = searches
+                    .into_iter()
+                    .map(|(tab_id, query)| self.start_search(tab_id, query))
+                    .collect();
+                Command::batch(
+                    self.all_tabs()
+                        .filter(|tab| !tab.showing_trash)
+                        .map(|tab| self.load_tab(tab))
+                        .chain(restarted),
                 )
             }
+            Message::NewTab => {
+                let path = self.tab().current_path.clone();
+                self.open_tab(path)
+            }
+            Message::OpenInNewTab(path) => {
+                if path.is_dir() {
+                    self.open_tab(path.canonicalize().unwrap_or(path))
+                } else {
+                    Command::none()
+                }
+            }
+            Message::SelectTab(tab_id) => {
+                let pane = self.pane_mut();
+                match pane.tabs.iter().position(|t| t.id == tab_id) {
+                    Some(index) if pane.select_tab(index) => self.show_active_tab(),
+                    _ => Command::none(),
+                }
+            }
+            Message::NextTab => {
+                if self.pane_mut().select_next_tab() {
+                    return self.show_active_tab();
+                }
+                Command::none()
+            }
+            Message::PreviousTab => {
+                if self.pane_mut().select_previous_tab() {
+                    return self.show_active_tab();
+                }
+                Command::none()
+            }
+            Message::CloseTab(tab_id) => {
+                if self.pane_mut().close_tab(tab_id) {
+                    return self.show_active_tab();
+                }
+                Command::none()
+            }
+            Message::CloseActiveTab => self.update(Message::CloseTab(self.tab().id)),
+            Message::GridScrolled(tab_id, offset) => {
+                if let Some(tab) = self.tab_by_id_mut(tab_id) {
+                    tab.scroll_offset = offset;
+                }
+                Command::none()
+            }
+            Message::ToggleSplitView => {
+                if self.panes.len() > 1 {
+                    // Keeps the pane that was being worked in
+                    let pane = self.panes.swap_remove(self.active_pane);
+                    self.panes = vec![pane];
+                    self.active_pane = 0;
+                    return Command::none();
+                }
+                let current = self.tab();
+                let tab = Tab::new(
+                    self.next_tab_id,
+                    current.current_path.clone(),
+                    current.sort_criteria,
+                    current.sort_order,
+                    current.group_criteria,
+                    current.view_mode,
+                );
+                self.next_tab_id += 1;
+                let load = self.load_tab(&tab);
+                self.panes.push(Pane::new(tab));
+                load
+            }
+            Message::InPane(index, message) => {
+                if index >= self.panes.len() {
+                    return Command::none();
+                }
+                // Working in a pane makes it the active one; scrolling alone does not
+                if index != self.active_pane && !matches!(*message, Message::GridScrolled(..)) {
+                    self.active_pane = index;
+                    self.preview_content = None;
+                    self.path_input = None;
+                }
+                self.update(*message)
+            }
+            Message::CopyToOtherPane => self.transfer_to_other_pane(ClipboardAction::Copy),
+            Message::MoveToOtherPane => self.transfer_to_other_pane(ClipboardAction::Cut),
             Message::ItemClicked(path) => {
-                let is_double_click = self.last_clicked_path.as_ref() == Some(&path) &&
-                                      self.last_click_time.map_or(false, |t| t.elapsed() < Duration::from_millis(500));
+                let modifiers = self.keyboard_modifiers;
+                let tab = self.tab_mut();
+                if tab.showing_trash {
+                    tab.selection.select_only(path);
+                    return Command::none();
+                }
+                if modifiers.shift() {
+                    let order = tab.visible_paths();
+                    tab.selection.select_range(path, &order, modifiers.command());
+                    return Command::none();
+                }
+                if modifiers.command() {
+                    tab.selection.toggle(path);
+                    return self.load_lead_thumbnail();
+                }
+                if tab.view_mode == ViewMode::Columns {
+                    if let Some(command) = self.column_item_clicked(&path) {
+                        return command;
+                    }
+                }
+                let tab = self.tab_mut();
+
+                let is_double_click = tab.last_clicked_path.as_ref() == Some(&path)
+                    && tab
+                        .last_click_time
+                        .is_some_and(|t| t.elapsed() < Duration::from_millis(500));
 
-                self.selected_path = Some(path.clone());
-                self.last_click_time = Some(Instant::now());
-                self.last_clicked_path = Some(path.clone());
+                tab.selection.select_only(path.clone());
+                tab.last_click_time = Some(Instant::now());
+                tab.last_clicked_path = Some(path.clone());
 
                 if is_double_click {
                     return Command::perform(async move { path }, Message::Navigate);
                 }
 
-                if let Some(entry) = self.entries.iter().find(|e| e.path == *self.selected_path.as_ref().unwrap()) {
-                    if entry.mime_group.as_deref() == Some("Images") && entry.thumbnail.is_none() {
-                         let p = entry.path.clone();
-                         return Command::perform(load_thumbnail_async(p.clone()), move |handle| {
-                             Message::ThumbnailLoaded(p, handle)
-                         });
-                    }
+                Command::batch([self.load_lead_thumbnail(), self.load_column_preview()])
+            }
+            Message::ModifiersChanged(modifiers) => {
+                self.keyboard_modifiers = modifiers;
+                Command::none()
+            }
+            Message::RubberBandSelect(paths) => {
+                let extend = self.keyboard_modifiers.command();
+                let tab = self.tab_mut();
+                if !tab.showing_trash {
+                    tab.selection.set(paths, extend);
                 }
                 Command::none()
             }
-            Message::DeleteItem(path) => {
-                println!("Delete requested for: {}", path.display());
+            Message::SelectAll => {
+                let tab = self.tab_mut();
+                if !tab.showing_trash {
+                    let all = tab.visible_paths();
+                    tab.selection.select_all(&all);
+                }
+                Command::none()
+            }
+            Message::InvertSelection => {
+                let tab = self.tab_mut();
+                if !tab.showing_trash {
+                    let all = tab.visible_paths();
+                    tab.selection.invert(&all);
+                }
+                Command::none()
+            }
+            Message::TrashSelection => {
+                let paths = self.selected_paths();
+                if paths.is_empty() {
+                    return Command::none();
+                }
+                println!("Trash requested for {} item(s)", paths.len());
                 self.preview_content = None;
-                self.renaming_path = None;
-                self.rename_input_value.clear();
-                Command::perform(delete_item(path), Message::ItemDeleted)
+                Command::perform(
+                    async move {
+                        let mut operations = vec![];
+                        for path in paths {
+                            match trash_item(path).await {
+                                Ok(item) => operations.push(FileOperation::Trash(item)),
+                                Err(e) => return (operations, Err(e)),
+                            }
+                        }
+                        (operations, Ok(()))
+                    },
+                    |(operations, result)| Message::ItemsDeleted(operations, result),
+                )
             }
-            Message::ItemDeleted(result) => {
-                let command = match result {
-                    Ok(_) => {
-                        self.error = None;
-                        Command::perform(
-                            read_dir(
-                                self.current_path.clone(),
-                                self.show_hidden_files,
-                                self.sort_criteria,
-                                self.sort_order,
-                                self.group_criteria,
-                            ),
-                            Message::LoadEntries,
-                        )
-                    }
-                    Err(e) => {
-                        self.error = Some(format!("Failed to delete item: {}", e));
-                        Command::none()
-                    }
-                };
-                self.selected_path = None;
+            Message::DeleteSelectionPermanently => {
+                let paths = self.selected_paths();
+                if paths.is_empty() {
+                    return Command::none();
+                }
+                println!("Permanent delete requested for {} item(s)", paths.len());
                 self.preview_content = None;
-                command
+                Command::perform(
+                    prepare_confirmation(ConfirmAction::DeletePermanently(paths)),
+                    Message::ShowConfirmation,
+                )
             }
-            Message::CopyItem(path) => {
-                println!("Copy requested for: {}", path.display());
-                self.clipboard_item = Some((path, ClipboardAction::Copy));
-                self.error = None;
-                Command::none()
-            }
-            Message::CutItem(path) => {
-                println!("Cut requested for: {}", path.display());
-                self.clipboard_item = Some((path, ClipboardAction::Cut));
-                self.error = None;
-                Command::none()
-            }
-            Message::Paste => {
-                if let Some((source_path, action)) = self.clipboard_item.clone() {
-                    let destination_dir = self.current_path.clone();
-                    println!(
-                        "Paste requested: {:?} {} to {}",
-                        action,
-                        source_path.display(),
-                        destination_dir.display()
-                    );
-
-                    let command = match action {
-                        ClipboardAction::Copy => Command::perform(
-                            copy_item(source_path, destination_dir),
-                            Message::ItemPasted,
-                        ),
-                        ClipboardAction::Cut => Command::perform(
-                            move_item(source_path, destination_dir),
-                            Message::ItemPasted,
-                        ),
-                    };
-                    if action == ClipboardAction::Copy {}
-                    command
-                } else {
-                    self.error = Some("Clipboard is empty.".to_string());
-                    Command::none()
+            Message::ItemsDeleted(operations, result) => {
+                // Items trashed before a failure can still be undone
+                let record = self.record_operations(operations);
+                let tab = self.tab_mut();
+                match result {
+                    Ok(()) => tab.error = None,
+                    Err(e) => tab.error = Some(format!("Failed to delete item: {}", e)),
                 }
+                tab.selection.clear();
+                self.preview_content = None;
+                Command::batch([record, self.refresh_view()])
             }
-            Message::ItemPasted(result) => {
-                let command = match result {
-                    Ok(_) => {
-                        self.error = None;
-                        if let Some((_, ClipboardAction::Cut)) = self.clipboard_item {
-                            self.clipboard_item = None;
+            Message::CopySelection => {
+                let paths = self.selected_paths();
+                if !paths.is_empty() {
+                    println!("Copy requested for {} item(s)", paths.len());
+                    self.clipboard = Some((paths, ClipboardAction::Copy));
+                    self.tab_mut().error = None;
+                }
+                Command::none()
+            }
+            Message::CutSelection => {
+                let paths = self.selected_paths();
+                if !paths.is_empty() {
+                    println!("Cut requested for {} item(s)", paths.len());
+                    self.clipboard = Some((paths, ClipboardAction::Cut));
+                    self.tab_mut().error = None;
+                }
+                Command::none()
+            }
+            Message::Paste => self.paste_into(self.tab().id),
+            Message::PasteIntoTab(tab_id) => self.paste_into(tab_id),
+            Message::JobUpdated(id, event) => {
+                let outcome = self.jobs.apply(id, event);
+                if matches!(&self.dialog, Some(Dialog::Conflict(c)) if c.job_id == id)
+                    && self.jobs.next_conflict().is_none_or(|(job_id, _)| job_id != id)
+                {
+                    // The job finished while its question was still open
+                    self.dialog = None;
+                }
+                self.open_next_conflict();
+                let Some((operations, result)) = outcome else {
+                    return Command::none();
+                };
+                // Whatever completed before a failure or cancel can still be undone
+                let record = self.record_operations(operations);
+                match result {
+                    Ok(()) => {
+                        let job_sources = self
+                            .jobs
+                            .jobs()
+                            .iter()
+                            .find(|job| job.id == id)
+                            .map(|job| &job.sources);
+                        if let Some((sources, ClipboardAction::Cut)) = &self.clipboard {
+                            if Some(sources) == job_sources {
+                                self.clipboard = None;
+                            }
                         }
-                        Command::perform(
-                            read_dir(
-                                self.current_path.clone(),
-                                self.show_hidden_files,
-                                self.sort_criteria,
-                                self.sort_order,
-                                self.group_criteria,
-                            ),
-                            Message::LoadEntries,
-                        )
                     }
-                    Err(e) => {
-                        self.error = Some(format!("Failed to paste item: {}", e));
-                        Command::none()
-                    }
-                };
-                self.selected_path = None;
-                self.preview_content = None;
-                command
+                    Err(e) => self.tab_mut().error = Some(format!("Failed to paste item: {}", e)),
+                }
+                Command::batch([record, self.refresh_view(), self.start_jobs()])
+            }
+            Message::PauseJob(id) => {
+                self.jobs.pause(id);
+                Command::none()
+            }
+            Message::ResumeJob(id) => {
+                self.jobs.resume(id);
+                Command::none()
+            }
+            Message::CancelJob(id) => {
+                self.jobs.cancel(id);
+                if matches!(&self.dialog, Some(Dialog::Conflict(c)) if c.job_id == id) {
+                    self.dialog = None;
+                    self.open_next_conflict();
+                }
+                self.start_jobs()
+            }
+            Message::ConflictApplyToAllToggled(apply_to_all) => {
+                if let Some(Dialog::Conflict(dialog)) = &mut self.dialog {
+                    dialog.apply_to_all = apply_to_all;
+                }
+                Command::none()
+            }
+            Message::ResolveConflict(choice) => {
+                if let Some(Dialog::Conflict(dialog)) = self.dialog.take() {
+                    self.jobs.answer_conflict(dialog.job_id, choice, dialog.apply_to_all);
+                    self.open_next_conflict();
+                }
+                Command::none()
+            }
+            Message::ClearFinishedJobs => {
+                self.jobs.clear_finished();
+                Command::none()
+            }
+            Message::ToggleJobsPanel => {
+                self.show_jobs_panel = !self.show_jobs_panel;
+                Command::none()
             }
             Message::StartRename(path) => {
                 println!("Start rename requested for: {}", path.display());
+                let tab = self.tab_mut();
                 if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                    self.renaming_path = Some(path.clone());
-                    self.rename_input_value = file_name.to_string();
-                    self.error = None;
+                    tab.renaming_path = Some(path.clone());
+                    tab.rename_input_value = file_name.to_string();
+                    tab.error = None;
+                    let input_id = file_grid::rename_input_id();
+                    Command::batch([
+                        text_input::focus(input_id.clone()),
+                        text_input::select_all(input_id),
+                    ])
                 } else {
-                    self.error = Some("Cannot get file name to rename.".to_string());
-                    self.renaming_path = None;
-                    self.rename_input_value.clear();
+                    tab.error = Some("Cannot get file name to rename.".to_string());
+                    tab.cancel_rename();
+                    Command::none()
                 }
-                Command::none()
             }
             Message::RenameInputChanged(new_value) => {
-                if self.renaming_path.is_some() {
-                    self.rename_input_value = new_value;
+                let tab = self.tab_mut();
+                if tab.renaming_path.is_some() {
+                    tab.rename_input_value = new_value;
                 }
                 Command::none()
             }
             Message::ConfirmRename => {
-                if let Some(path_to_rename) = self.renaming_path.clone() {
-                    if !self.rename_input_value.is_empty()
-                        && self.rename_input_value
+                let tab = self.tab_mut();
+                if let Some(path_to_rename) = tab.renaming_path.clone() {
+                    if !tab.rename_input_value.is_empty()
+                        && tab.rename_input_value
                             != path_to_rename
                                 .file_name()
                                 .unwrap_or_default()
@@ -478,18 +797,24 @@ impl Application for FileManager {
                         println!(
                             "Confirm rename: {} to {}",
                             path_to_rename.display(),
-                            self.rename_input_value
+                            tab.rename_input_value
                         );
-                        let new_name = self.rename_input_value.clone();
-                        self.renaming_path = None;
-                        self.rename_input_value.clear();
+                        let new_name = tab.rename_input_value.clone();
+                        tab.cancel_rename();
                         Command::perform(
-                            rename_item(path_to_rename, new_name),
+                            async move {
+                                let source = path_to_rename.clone();
+                                rename_item(path_to_rename, new_name).await.map(|destination| {
+                                    vec![FileOperation::Rename {
+                                        source,
+                                        destination,
+                                    }]
+                                })
+                            },
                             Message::ItemRenamed,
                         )
                     } else {
-                        self.renaming_path = None;
-                        self.rename_input_value.clear();
+                        tab.cancel_rename();
                         Command::none()
                     }
                 } else {
@@ -498,111 +823,566 @@ impl Application for FileManager {
             }
             Message::CancelRename => {
                 println!("Cancel rename");
-                self.renaming_path = None;
-                self.rename_input_value.clear();
-                self.error = None;
+                let tab = self.tab_mut();
+                tab.cancel_rename();
+                tab.error = None;
                 self.preview_content = None;
                 Command::none()
             }
             Message::ItemRenamed(result) => {
                 let command = match result {
-                    Ok(_) => {
-                        self.error = None;
-                        Command::perform(
-                            read_dir(
-                                self.current_path.clone(),
-                                self.show_hidden_files,
-                                self.sort_criteria,
-                                self.sort_order,
-                                self.group_criteria,
-                            ),
-                            Message::LoadEntries,
-                        )
+                    Ok(operations) => {
+                        self.tab_mut().error = None;
+                        Command::batch([self.record_operations(operations), self.refresh_view()])
                     }
                     Err(e) => {
-                        self.error = Some(format!("Failed to rename item: {}", e));
+                        self.tab_mut().error = Some(format!("Failed to rename item: {}", e));
                         Command::none()
                     }
                 };
-                self.selected_path = None;
+                self.tab_mut().selection.clear();
                 self.preview_content = None;
                 command
             }
+            Message::BatchRenameSelection => {
+                let paths = self.selected_paths();
+                if paths.len() < 2 || self.tab().showing_trash {
+                    return Command::none();
+                }
+                self.tab_mut().cancel_rename();
+                Command::perform(batch_rename::load_items(paths), Message::BatchRenameLoaded)
+            }
+            Message::BatchRenameLoaded(result) => {
+                match result {
+                    Ok(items) => {
+                        self.dialog = Some(Dialog::BatchRename(BatchRenameDialog::new(items)))
+                    }
+                    Err(e) => self.tab_mut().error = Some(e),
+                }
+                Command::none()
+            }
+            Message::BatchRenameEdited(edit) => {
+                if let Some(Dialog::BatchRename(dialog)) = &mut self.dialog {
+                    dialog.rules.edit(edit);
+                    dialog.update_preview();
+                }
+                Command::none()
+            }
             Message::SetSortCriteria(criteria) => {
-                if self.sort_criteria != criteria {
-                    self.sort_criteria = criteria;
+                let tab = self.tab_mut();
+                if tab.sort_criteria != criteria {
+                    tab.sort_criteria = criteria;
+                    tab.cancel_rename();
                     self.preview_content = None;
-                    self.renaming_path = None;
-                    self.rename_input_value.clear();
-                    Command::perform(
-                        read_dir(
-                            self.current_path.clone(),
-                            self.show_hidden_files,
-                            self.sort_criteria,
-                            self.sort_order,
-                            self.group_criteria,
-                        ),
-                        Message::LoadEntries,
-                    )
+                    self.load_tab(self.tab())
                 } else {
                     self.update(Message::ToggleSortOrder)
                 }
             }
             Message::ToggleSortOrder => {
-                self.sort_order = match self.sort_order {
+                let tab = self.tab_mut();
+                tab.sort_order = match tab.sort_order {
                     SortOrder::Ascending => SortOrder::Descending,
                     SortOrder::Descending => SortOrder::Ascending,
                 };
+                tab.cancel_rename();
                 self.preview_content = None;
-                self.renaming_path = None;
-                self.rename_input_value.clear();
+                self.load_tab(self.tab())
+            }
+            Message::SetGroupCriteria(criteria) => {
+                let tab = self.tab_mut();
+                if tab.group_criteria != criteria {
+                    tab.group_criteria = criteria;
+                    tab.collapsed_groups.clear();
+                    tab.cancel_rename();
+                    self.preview_content = None;
+                    self.load_tab(self.tab())
+                } else {
+                    Command::none()
+                }
+            }
+            Message::ToggleGroupCollapse(group_id) => {
+                let collapsed_groups = &mut self.tab_mut().collapsed_groups;
+                if collapsed_groups.contains(&group_id) {
+                    collapsed_groups.remove(&group_id);
+                } else {
+                    collapsed_groups.insert(group_id);
+                }
+                Command::none()
+            }
+            Message::SetViewMode(view_mode) => {
+                let tab = self.tab_mut();
+                if tab.view_mode == view_mode {
+                    return Command::none();
+                }
+                tab.view_mode = view_mode;
+                if view_mode == ViewMode::Columns {
+                    Command::batch([self.load_parent_columns(self.tab()), self.load_column_preview()])
+                } else {
+                    Command::none()
+                }
+            }
+            Message::KeyPressed(key, modifiers) => match self.keymap.action(&key, modifiers) {
+                // An open dialog handles its own keys; shortcuts would act behind it
+                _ if self.dialog.is_some() => Command::none(),
+                // The path bar completes folder names with Tab
+                _ if key == Key::Named(Named::Tab)
+                    && modifiers.is_empty()
+                    && self.path_input.is_some() =>
+                {
+                    self.update(Message::CompletePathInput)
+                }
+                // While filtering, Backspace takes back the last typed character
+                _ if key == Key::Named(Named::Backspace)
+                    && modifiers.is_empty()
+                    && self.tab().type_ahead.is_filtering() =>
+                {
+                    self.tab_mut().type_ahead.pop();
+                    self.show_type_ahead_match()
+                }
+                Some(action) => self.update(action.message()),
+                None => self.type_ahead(&key, modifiers),
+            },
+            Message::ItemLocated(path, layout) => {
+                match layout
+                    .bounds(&path)
+                    .and_then(|bounds| layout.scroll_into_view(bounds))
+                {
+                    Some(offset) => scrollable::scroll_to(self.tab().scrollable_id(), offset),
+                    None => Command::none(),
+                }
+            }
+            Message::ToggleTypeToFilter(enabled) => {
+                self.type_to_filter = enabled;
+                for tab in self.all_tabs_mut() {
+                    tab.type_ahead.clear();
+                }
+                Command::none()
+            }
+            Message::MoveSelection(direction) => {
+                let tab = self.tab();
+                if tab.showing_trash || tab.renaming_path.is_some() || self.path_input.is_some() {
+                    return Command::none();
+                }
+                match tab.view_mode {
+                    ViewMode::Columns => self.move_in_columns(direction),
+                    ViewMode::Grid | ViewMode::List => {
+                        // Which item is above or below depends on the layout
+                        let items = tab
+                            .shown_entries()
+                            .map(|e| (rubber_band::item_id(&e.path).into(), e.path.clone()))
+                            .collect();
+                        item_layout::locate_items(tab.scrollable_id(), items, move |layout| {
+                            Message::ItemsLocated(direction, layout)
+                        })
+                    }
+                }
+            }
+            Message::ItemsLocated(direction, layout) => {
+                let tab = self.tab();
+                let order = tab.visible_paths();
+                let lead = tab
+                    .selection
+                    .lead()
+                    .and_then(|lead| Some((lead, layout.bounds(lead)?)));
+                let target = match (lead, direction) {
+                    (None, _) => order.first().cloned(),
+                    (Some((_, bounds)), Direction::Up | Direction::Down) => layout
+                        .vertical_neighbour(bounds, direction == Direction::Down)
+                        .cloned(),
+                    (Some((lead, _)), Direction::Left | Direction::Right)
+                        if tab.view_mode == ViewMode::Grid =>
+                    {
+                        neighbour_in_order(&order, lead, direction == Direction::Right)
+                    }
+                    _ => None,
+                };
+                let Some(target) = target else {
+                    return Command::none();
+                };
+                let scroll = layout
+                    .bounds(&target)
+                    .and_then(|bounds| layout.scroll_into_view(bounds))
+                    .map(|offset| scrollable::scroll_to(tab.scrollable_id(), offset));
+                let command = self.select_with_keyboard(target, &order);
+                Command::batch([command, scroll.unwrap_or_else(Command::none)])
+            }
+            Message::OpenSelection => {
+                let tab = self.tab();
+                if tab.showing_trash || tab.renaming_path.is_some() {
+                    return Command::none();
+                }
+                let selected = tab.selected_entries();
+                if let [entry] = selected.as_slice() {
+                    return self.update(Message::Navigate(entry.path.clone()));
+                }
+                // Several items: open the files, there is no single folder to enter
+                Command::batch(
+                    selected
+                        .into_iter()
+                        .filter(|entry| !entry.is_dir)
+                        .map(|entry| {
+                            Command::perform(open_file(entry.path.clone()), Message::FileOpenResult)
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            }
+            Message::RenameSelection => {
+                let tab = self.tab();
+                match tab.selection.single() {
+                    Some(path) if !tab.showing_trash && tab.renaming_path.is_none() => {
+                        self.update(Message::StartRename(path.clone()))
+                    }
+                    None if tab.selection.len() > 1 => self.update(Message::BatchRenameSelection),
+                    _ => Command::none(),
+                }
+            }
+            Message::FocusPathBar => {
+                if self.tab().showing_trash {
+                    return Command::none();
+                }
+                self.path_input = Some(PathInput::new(self.tab().current_path.display().to_string()));
+                let input_id = top_bar::path_input_id();
+                Command::batch([
+                    text_input::focus(input_id.clone()),
+                    text_input::select_all(input_id),
+                ])
+            }
+            Message::PathInputChanged(value) => {
+                if let Some(input) = &mut self.path_input {
+                    *input = PathInput::new(value);
+                }
+                Command::none()
+            }
+            Message::CompletePathInput => {
+                let Some(input) = &self.path_input else {
+                    return Command::none();
+                };
+                let typed = input.value.clone();
                 Command::perform(
-                    read_dir(
-                        self.current_path.clone(),
+                    location::complete(
+                        typed.clone(),
+                        self.tab().current_path.clone(),
                         self.show_hidden_files,
-                        self.sort_criteria,
-                        self.sort_order,
-                        self.group_criteria,
                     ),
-                    Message::LoadEntries,
+                    move |completion| Message::PathInputCompleted(typed, completion),
                 )
             }
-            Message::SetGroupCriteria(criteria) => {
-                if self.group_criteria != criteria {
-                    self.group_criteria = criteria;
-                    self.collapsed_groups.clear();
-                    self.preview_content = None;
-                    self.renaming_path = None;
-                    self.rename_input_value.clear();
-                    Command::perform(
-                        read_dir(
-                            self.current_path.clone(),
-                            self.show_hidden_files,
-                            self.sort_criteria,
-                            self.sort_order,
-                            self.group_criteria,
-                        ),
-                        Message::LoadEntries,
-                    )
+            Message::PathInputCompleted(typed, completion) => {
+                // Dropped if the path bar was closed or typed in meanwhile
+                let Some(input) = self.path_input.as_mut().filter(|input| input.value == typed)
+                else {
+                    return Command::none();
+                };
+                match completion {
+                    Some(completion) => {
+                        *input = PathInput {
+                            value: completion.value,
+                            error: None,
+                            candidates: completion.candidates,
+                        };
+                        text_input::move_cursor_to_end(top_bar::path_input_id())
+                    }
+                    None => {
+                        input.candidates.clear();
+                        input.error = Some("No folder to complete to".to_string());
+                        Command::none()
+                    }
+                }
+            }
+            Message::SubmitPathInput => {
+                let base = self.tab().current_path.clone();
+                let Some(input) = &mut self.path_input else {
+                    return Command::none();
+                };
+                match location::resolve(&input.value, &base) {
+                    Ok(path) if path.is_dir() => self.update(Message::Navigate(path)),
+                    // A file opens its folder with the file selected
+                    Ok(path) => self.update(Message::RevealItem(path)),
+                    Err(e) => {
+                        input.candidates.clear();
+                        input.error = Some(e);
+                        Command::none()
+                    }
+                }
+            }
+            Message::EscapePressed => {
+                if self.path_input.take().is_some() {
+                    Command::none()
+                } else if self.tab().renaming_path.is_some() {
+                    self.update(Message::CancelRename)
+                } else if self.tab().search.is_some() {
+                    self.update(Message::ClearSearch)
+                } else if self.tab().type_ahead.is_filtering() {
+                    self.tab_mut().type_ahead.clear();
+                    Command::none()
+                } else if !self.tab().filter_query.is_empty() {
+                    self.update(Message::FilterInputChanged(String::new()))
                 } else {
                     Command::none()
                 }
             }
-            Message::ToggleGroupCollapse(group_id) => {
-                if self.collapsed_groups.contains(&group_id) {
-                    self.collapsed_groups.remove(&group_id);
+            Message::ItemContextMenu(path) => {
+                let tab = self.tab_mut();
+                if tab.showing_trash {
+                    return Command::none();
+                }
+                // The menu acts on the selection, which a right-clicked item
+                // outside of it replaces
+                if !tab.selection.contains(&path) {
+                    tab.selection.select_only(path.clone());
+                }
+                self.open_with_apps = None;
+                let mut commands = vec![self.load_lead_thumbnail(), self.load_column_preview()];
+                if !path.is_dir() {
+                    commands.push(Command::perform(
+                        open_with::apps_for(path.clone()),
+                        move |apps| Message::OpenWithAppsLoaded(path, apps),
+                    ));
+                }
+                Command::batch(commands)
+            }
+            Message::OpenWithAppsLoaded(path, apps) => {
+                self.open_with_apps = Some((path, apps));
+                Command::none()
+            }
+            Message::SubfoldersMenu(path) => {
+                self.subfolders = None;
+                Command::perform(
+                    location::subfolders(path.clone(), self.show_hidden_files),
+                    move |folders| Message::SubfoldersLoaded(path, folders),
+                )
+            }
+            Message::SubfoldersLoaded(path, folders) => {
+                self.subfolders = Some((path, folders));
+                Command::none()
+            }
+            Message::OpenWith(desktop_file) => {
+                let files: Vec<PathBuf> = self
+                    .tab()
+                    .selected_entries()
+                    .into_iter()
+                    .filter(|entry| !entry.is_dir)
+                    .map(|entry| entry.path.clone())
+                    .collect();
+                if files.is_empty() {
+                    return Command::none();
+                }
+                Command::perform(
+                    open_with::launch(desktop_file, files),
+                    Message::FileOpenResult,
+                )
+            }
+            Message::PasteIntoFolder(folder) => self.paste_to(folder),
+            Message::ShowProperties => {
+                self.show_details_panel = true;
+                Command::none()
+            }
+            Message::CopyPath => {
+                let paths = self.selected_paths();
+                if paths.is_empty() {
+                    return Command::none();
+                }
+                let text: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+                iced::clipboard::write(text.join("\n"))
+            }
+            Message::BackgroundContextMenu => Command::perform(list_templates(), Message::TemplatesLoaded),
+            Message::TemplatesLoaded(templates) => {
+                self.templates = templates;
+                Command::none()
+            }
+            Message::NewFolder | Message::NewFile => {
+                let tab = self.tab();
+                if tab.showing_trash {
+                    return Command::none();
+                }
+                let is_dir = matches!(message, Message::NewFolder);
+                let name = if is_dir { "New Folder" } else { "New File" };
+                Command::perform(
+                    create_item(tab.current_path.clone(), name.to_string(), is_dir),
+                    Message::ItemCreated,
+                )
+            }
+            Message::NewDocument(template) => {
+                let tab = self.tab();
+                if tab.showing_trash {
+                    return Command::none();
+                }
+                Command::perform(
+                    create_from_template(tab.current_path.clone(), template),
+                    Message::ItemCreated,
+                )
+            }
+            Message::ItemCreated(result) => match result {
+                // Shown right away rather than with the next reload, so that
+                // it can be named at once
+                Ok(path) => Command::perform(
+                    reload_entries(vec![path.clone()], self.show_hidden_files),
+                    move |reloaded| Message::CreatedItemLoaded(path, reloaded),
+                ),
+                Err(e) => {
+                    self.tab_mut().error = Some(e);
+                    Command::none()
+                }
+            },
+            Message::CreatedItemLoaded(path, reloaded) => {
+                let Some(dir) = path.parent().map(Path::to_path_buf) else {
+                    return Command::none();
+                };
+                let shown = self.update(Message::EntriesReloaded(dir.clone(), reloaded));
+                let tab = self.tab_mut();
+                if tab.showing_trash
+                    || tab.current_path != dir
+                    || !tab.shown_entries().any(|e| e.path == path)
+                {
+                    return shown;
+                }
+                tab.error = None;
+                tab.selection.select_only(path.clone());
+                Command::batch([shown, self.update(Message::StartRename(path))])
+            }
+            Message::OpenTerminalHere => {
+                let tab = self.tab();
+                if tab.showing_trash {
+                    return Command::none();
+                }
+                Command::perform(
+                    open_terminal(tab.current_path.clone()),
+                    Message::TerminalOpened,
+                )
+            }
+            Message::TerminalOpened(result) => {
+                if let Err(e) = result {
+                    self.tab_mut().error = Some(e);
+                }
+                Command::none()
+            }
+            Message::FocusSearch => {
+                if self.tab().showing_trash {
+                    return Command::none();
+                }
+                let input_id = top_bar::search_input_id();
+                Command::batch([
+                    text_input::focus(input_id.clone()),
+                    text_input::select_all(input_id),
+                ])
+            }
+            Message::SearchInputChanged(query) => {
+                let tab_id = self.tab().id;
+                self.start_search(tab_id, query)
+            }
+            Message::FilterInputChanged(query) => {
+                self.tab_mut().set_filter(query);
+                Command::none()
+            }
+            Message::SetSearchMode(mode) => {
+                self.search_mode = mode;
+                match self.tab().search.as_ref().map(|search| search.query.clone()) {
+                    Some(query) => self.start_search(self.tab().id, query),
+                    None => Command::none(),
+                }
+            }
+            Message::SearchUpdated(tab_id, search_id, event) => {
+                // Results of a search that was replaced in the meantime are dropped
+                if let Some(search) = self
+                    .tab_by_id_mut(tab_id)
+                    .and_then(|tab| tab.search.as_mut())
+                    .filter(|search| search.id == search_id)
+                {
+                    search.apply(event);
+                }
+                Command::none()
+            }
+            Message::ClearSearch => {
+                self.tab_mut().search = None;
+                Command::none()
+            }
+            Message::OpenSearchResult(path) => self.update(Message::Navigate(path)),
+            Message::OpenLineMatch(path, line) => {
+                let tab = self.tab();
+                let Some(search) = &tab.search else {
+                    return Command::none();
+                };
+                let (tab_id, search_id) = (tab.id, search.id);
+                Command::perform(TextPreview::load(path, line), move |result| {
+                    Message::TextPreviewLoaded(tab_id, search_id, result)
+                })
+            }
+            Message::TextPreviewLoaded(tab_id, search_id, result) => {
+                let Some(search) = self
+                    .tab_by_id_mut(tab_id)
+                    .and_then(|tab| tab.search.as_mut())
+                    .filter(|search| search.id == search_id)
+                else {
+                    return Command::none();
+                };
+                let offset = result.as_ref().ok().map(search_results::preview_offset);
+                search.preview = Some(result);
+                match offset {
+                    Some(offset) => scrollable::scroll_to(search.preview_id(), offset),
+                    None => Command::none(),
+                }
+            }
+            Message::ToggleSearchIndex(enabled) => {
+                if enabled {
+                    self.start_index()
                 } else {
-                    self.collapsed_groups.insert(group_id);
+                    if let Some(index) = self.search_index.take() {
+                        index.discard();
+                    }
+                    Command::none()
+                }
+            }
+            Message::IndexStatusChanged(status) => {
+                if let IndexStatus::Failed(e) = &status {
+                    eprintln!("Search index failed: {}", e);
+                }
+                if let Some(index) = &mut self.search_index {
+                    index.set_status(status);
                 }
                 Command::none()
             }
+            Message::RevealItem(path) => {
+                let Some(parent) = path.parent() else {
+                    return Command::none();
+                };
+                let command = self.update(Message::Navigate(parent.to_path_buf()));
+                // Stays selected once the folder is loaded
+                self.tab_mut().selection.select_only(path);
+                command
+            }
+            Message::ParentColumnsLoaded(tab_id, columns) => {
+                let Some(tab) = self.tab_by_id_mut(tab_id) else {
+                    return Command::none();
+                };
+                // Ignore folders that were left in the meantime
+                let paths: Vec<&PathBuf> = columns.iter().map(|(path, _)| path).collect();
+                if paths.iter().copied().ne(tab.column_paths().iter()) {
+                    return Command::none();
+                }
+                tab.parent_columns = columns;
+                // Keeps the deepest column in sight
+                scrollable::snap_to(tab.scrollable_id(), RelativeOffset { x: 1.0, y: 0.0 })
+            }
+            Message::ResizeColumn(column, delta) => {
+                self.list_columns.resize(column, delta);
+                Command::none()
+            }
+            Message::MoveColumn(column, offset) => {
+                self.list_columns.move_by(column, offset);
+                Command::none()
+            }
             Message::FileOpenResult(result) => {
                 if let Err(e) = result {
-                    self.error = Some(format!("Failed to open file: {}", e));
+                    self.tab_mut().error = Some(format!("Failed to open file: {}", e));
                 }
                 Command::none()
             }
-            Message::LoadPreview(result) => {
+            Message::LoadPreview(path, result) => {
+                // Only the preview of the item that is still selected is kept
+                if self.tab().selection.single() != Some(&path) {
+                    return Command::none();
+                }
                 match result {
                     Ok(PreviewContent::Image(handle)) => {
                         self.preview_content = Some(PreviewContent::Image(handle));
@@ -630,47 +1410,667 @@ impl Application for FileManager {
                 Command::none()
             }
             Message::ThumbnailLoaded(path, handle) => {
-                if let Some(entry) = self.entries.iter_mut().find(|e| e.path == path) {
-                    entry.thumbnail = handle;
+                for tab in self.all_tabs_mut() {
+                    if let Some(entry) = tab.entries.iter_mut().find(|e| e.path == path) {
+                        entry.thumbnail = handle.clone();
+                    }
+                }
+                Command::none()
+            }
+            Message::ShowTrash => {
+                let tab = self.tab_mut();
+                tab.showing_trash = true;
+                tab.error = None;
+                tab.selection.clear();
+                tab.cancel_rename();
+                tab.search = None;
+                self.preview_content = None;
+                Command::perform(list_trash(), Message::TrashLoaded)
+            }
+            Message::TrashLoaded(result) => {
+                let error = match result {
+                    Ok(entries) => {
+                        self.trash_entries = entries;
+                        None
+                    }
+                    Err(e) => {
+                        self.trash_entries = vec![];
+                        Some(format!("Failed to read the trash: {}", e))
+                    }
+                };
+                let existing = self.trash_entries.iter().map(|e| &e.trashed_path).collect();
+                let trash_tabs = self.panes.iter_mut().flat_map(|pane| pane.tabs.iter_mut());
+                for tab in trash_tabs.filter(|tab| tab.showing_trash) {
+                    tab.error = error.clone();
+                    tab.selection.retain(&existing);
+                }
+                Command::none()
+            }
+            Message::RestoreFromTrash(trashed_path) => {
+                match self.find_trash_entry(&trashed_path) {
+                    Some(entry) => Command::perform(restore_item(entry), Message::TrashItemRestored),
+                    None => Command::none(),
+                }
+            }
+            Message::TrashItemRestored(result) => {
+                let tab = self.tab_mut();
+                match result {
+                    Ok(restored_path) => {
+                        println!("Restored to {}", restored_path.display());
+                        tab.error = None;
+                    }
+                    Err(e) => {
+                        tab.error = Some(format!("Failed to restore item: {}", e));
+                    }
+                }
+                tab.selection.clear();
+                Command::perform(list_trash(), Message::TrashLoaded)
+            }
+            Message::DeleteFromTrash(trashed_path) => {
+                match self.find_trash_entry(&trashed_path) {
+                    Some(entry) => Command::perform(
+                        prepare_confirmation(ConfirmAction::DeleteFromTrash(vec![entry])),
+                        Message::ShowConfirmation,
+                    ),
+                    None => Command::none(),
+                }
+            }
+            Message::EmptyTrash => Command::perform(
+                prepare_confirmation(ConfirmAction::EmptyTrash),
+                Message::ShowConfirmation,
+            ),
+            Message::TrashUpdated(result) => {
+                let tab = self.tab_mut();
+                if let Err(e) = result {
+                    tab.error = Some(e);
+                }
+                tab.selection.clear();
+                Command::perform(list_trash(), Message::TrashLoaded)
+            }
+            Message::ShowConfirmation(result) => {
+                match result {
+                    Ok(dialog) => self.dialog = Some(Dialog::Confirm(dialog)),
+                    Err(e) => self.tab_mut().error = Some(e),
+                }
+                Command::none()
+            }
+            Message::DialogConfirmed => match self.dialog.take() {
+                Some(Dialog::Confirm(dialog)) => self.run_confirmed_action(dialog.action),
+                Some(Dialog::BatchRename(dialog)) if dialog.ready().is_some() => {
+                    let planned = dialog.preview.unwrap_or_default();
+                    Command::perform(batch_rename::apply(planned), Message::ItemRenamed)
+                }
+                other => {
+                    self.dialog = other;
+                    Command::none()
+                }
+            },
+            Message::DialogCancelled => {
+                // A conflict question stays until it is answered or its job cancelled
+                if !matches!(self.dialog, Some(Dialog::Conflict(_))) {
+                    self.dialog = None;
+                }
+                Command::none()
+            }
+            Message::Undo => match self.journal.take_undo() {
+                Some(entry) => Command::perform(journal::undo(entry), Message::UndoFinished),
+                None => Command::none(),
+            },
+            Message::Redo => match self.journal.take_redo() {
+                Some(entry) => Command::perform(journal::redo(entry), Message::RedoFinished),
+                None => Command::none(),
+            },
+            Message::UndoFinished(result) => {
+                self.journal.finish();
+                match result {
+                    Ok(entry) => {
+                        self.tab_mut().error = None;
+                        self.journal.push_redo(entry);
+                    }
+                    // What was not undone can be tried again, what was can be redone
+                    Err(failure) => {
+                        self.journal.push_undo(failure.remaining);
+                        if let Some(entry) = failure.completed {
+                            self.journal.push_redo(entry);
+                        }
+                        self.tab_mut().error = Some(format!("Cannot undo: {}", failure.error));
+                    }
+                }
+                self.refresh_view()
+            }
+            Message::RedoFinished(result) => {
+                self.journal.finish();
+                match result {
+                    Ok(entry) => {
+                        self.tab_mut().error = None;
+                        self.journal.push_undo(entry);
+                    }
+                    Err(failure) => {
+                        self.journal.push_redo(failure.remaining);
+                        if let Some(entry) = failure.completed {
+                            self.journal.push_undo(entry);
+                        }
+                        self.tab_mut().error = Some(format!("Cannot redo: {}", failure.error));
+                    }
+                }
+                self.refresh_view()
+            }
+            Message::OperationsFingerprinted(result) => {
+                match result {
+                    Ok(entry) => self.journal.record(entry),
+                    Err(e) => {
+                        self.journal.recording_failed();
+                        eprintln!("Failed to record operations for undo: {}", e);
+                    }
                 }
                 Command::none()
             }
         }
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         view(self)
     }
 
-    fn subscription(&self) -> iced::Subscription<Message> {
-        iced::Subscription::none()
+    fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = vec![
+            // Shortcuts are looked up in the keymap loaded from the keybindings file
+            keyboard::on_key_press(|key, modifiers| Some(Message::KeyPressed(key, modifiers))),
+            // Modifier state is needed to tell plain, Ctrl- and Shift-clicks apart
+            event::listen_with(|event, _status| match event {
+                Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                    Some(Message::ModifiersChanged(modifiers))
+                }
+                // Text fields swallow Escape, but it still ends renaming or
+                // editing the location
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: Key::Named(Named::Escape),
+                    ..
+                }) => Some(Message::EscapePressed),
+                _ => None,
+            }),
+        ];
+        // One watch per open folder, however many tabs show it
+        let watched: HashSet<&PathBuf> = self
+            .all_tabs()
+            .filter(|tab| !tab.showing_trash)
+            .map(|tab| &tab.current_path)
+            .collect();
+        subscriptions.extend(watched.into_iter().map(|path| watcher::watch(path.clone())));
+        Subscription::batch(subscriptions)
     }
 }
 
 impl FileManager {
-    pub fn update_history(&mut self, new_path: PathBuf) {
-        self.renaming_path = None;
-        self.rename_input_value.clear();
+    // The pane that toolbar and keyboard actions apply to
+    pub fn pane(&self) -> &Pane {
+        &self.panes[self.active_pane]
+    }
+
+    pub fn pane_mut(&mut self) -> &mut Pane {
+        &mut self.panes[self.active_pane]
+    }
 
-        if self.history_index < self.history.len() - 1 {
-            self.history.truncate(self.history_index + 1);
+    // The tab shown in the active pane
+    pub fn tab(&self) -> &Tab {
+        self.pane().tab()
+    }
+
+    pub fn tab_mut(&mut self) -> &mut Tab {
+        self.pane_mut().tab_mut()
+    }
+
+    fn all_tabs(&self) -> impl Iterator<Item = &Tab> {
+        self.panes.iter().flat_map(|pane| pane.tabs.iter())
+    }
+
+    fn all_tabs_mut(&mut self) -> impl Iterator<Item = &mut Tab> {
+        self.panes.iter_mut().flat_map(|pane| pane.tabs.iter_mut())
+    }
+
+    fn tab_by_id_mut(&mut self, tab_id: TabId) -> Option<&mut Tab> {
+        self.all_tabs_mut().find(|tab| tab.id == tab_id)
+    }
+
+    // Opens `path` in a new tab next to the current one and switches to it
+    fn open_tab(&mut self, path: PathBuf) -> Command<Message> {
+        let current = self.tab();
+        let tab = Tab::new(
+            self.next_tab_id,
+            path,
+            current.sort_criteria,
+            current.sort_order,
+            current.group_criteria,
+            current.view_mode,
+        );
+        self.next_tab_id += 1;
+        let load = self.load_tab(&tab);
+        self.pane_mut().insert_tab(tab);
+        self.preview_content = None;
+        load
+    }
+
+    // Restores the scroll position after another tab was brought to the front
+    fn show_active_tab(&mut self) -> Command<Message> {
+        self.preview_content = None;
+        self.path_input = None;
+        let tab = self.tab();
+        scrollable::scroll_to(tab.scrollable_id(), tab.scroll_offset)
+    }
+
+    // F5/F6: copies or moves the selection to the folder shown in the other pane
+    fn transfer_to_other_pane(&mut self, action: ClipboardAction) -> Command<Message> {
+        if self.panes.len() < 2 || self.tab().showing_trash {
+            return Command::none();
         }
-        if self.history.last() != Some(&new_path) {
-            self.history.push(new_path);
+        let target = self.panes[1 - self.active_pane].tab();
+        if target.showing_trash {
+            return Command::none();
         }
-        self.history_index = self.history.len() - 1;
+        let destination_dir = target.current_path.clone();
+        let sources = self.selected_paths();
+        if sources.is_empty() {
+            return Command::none();
+        }
+        println!(
+            "{:?} {} item(s) to the other pane at {}",
+            action,
+            sources.len(),
+            destination_dir.display()
+        );
+        self.start_paste(sources, action, destination_dir)
     }
 
-    pub fn can_go_back(&self) -> bool {
-        self.history_index > 0
+    fn load_tab(&self, tab: &Tab) -> Command<Message> {
+        let tab_id = tab.id;
+        let load_entries = Command::perform(
+            read_dir(
+                tab.current_path.clone(),
+                self.show_hidden_files,
+                tab.sort_criteria,
+                tab.sort_order,
+                tab.group_criteria,
+            ),
+            move |result| Message::LoadEntries(tab_id, result),
+        );
+        if tab.view_mode == ViewMode::Columns {
+            Command::batch([load_entries, self.load_parent_columns(tab)])
+        } else {
+            load_entries
+        }
     }
 
-    pub fn can_go_forward(&self) -> bool {
-        self.history_index < self.history.len() - 1
+    // Reads the folders left of the current one in the column view
+    fn load_parent_columns(&self, tab: &Tab) -> Command<Message> {
+        let tab_id = tab.id;
+        let paths = tab.column_paths();
+        let show_hidden = self.show_hidden_files;
+        let (sort_criteria, sort_order, group_criteria) =
+            (tab.sort_criteria, tab.sort_order, tab.group_criteria);
+        Command::perform(
+            async move {
+                let mut columns = Vec::with_capacity(paths.len());
+                for path in paths {
+                    let entries =
+                        read_dir(path.clone(), show_hidden, sort_criteria, sort_order, group_criteria)
+                            .await
+                            .unwrap_or_default();
+                    columns.push((path, entries));
+                }
+                columns
+            },
+            move |columns| Message::ParentColumnsLoaded(tab_id, columns),
+        )
+    }
+
+    // Selects `target` after an arrow key; with Shift the range from the anchor
+    fn select_with_keyboard(&mut self, target: PathBuf, order: &[PathBuf]) -> Command<Message> {
+        let extend = self.keyboard_modifiers.shift();
+        let selection = &mut self.tab_mut().selection;
+        if extend {
+            selection.select_range(target, order, false);
+        } else {
+            selection.select_only(target);
+        }
+        Command::batch([self.load_lead_thumbnail(), self.load_column_preview()])
+    }
+
+    // Letters typed while no text field has the keyboard jump to the first
+    // matching item, or filter the items with "Type to filter" on
+    fn type_ahead(&mut self, key: &Key, modifiers: Modifiers) -> Command<Message> {
+        let typed = match key {
+            Key::Character(typed) => typed.as_str(),
+            Key::Named(Named::Space) => " ",
+            _ => return Command::none(),
+        };
+        let tab = self.tab();
+        if modifiers.control()
+            || modifiers.alt()
+            || modifiers.logo()
+            || self.path_input.is_some()
+            || tab.showing_trash
+            || tab.search.is_some()
+            || tab.renaming_path.is_some()
+        {
+            return Command::none();
+        }
+        let filtering = self.type_to_filter;
+        self.tab_mut().type_ahead.push(typed, filtering);
+        self.show_type_ahead_match()
+    }
+
+    // Selects the first item matching the typed text and scrolls to it
+    fn show_type_ahead_match(&mut self) -> Command<Message> {
+        let tab = self.tab_mut();
+        tab.deselect_hidden();
+        let Some(target) = tab.type_ahead_match() else {
+            return Command::none();
+        };
+        tab.selection.select_only(target.clone());
+        Command::batch([
+            self.load_lead_thumbnail(),
+            self.load_column_preview(),
+            self.scroll_to_item(target),
+        ])
+    }
+
+    // Scrolls the grid or list so that the item at `path` is in view
+    fn scroll_to_item(&self, path: PathBuf) -> Command<Message> {
+        let tab = self.tab();
+        if tab.view_mode == ViewMode::Columns {
+            return Command::none();
+        }
+        let items = HashMap::from([(rubber_band::item_id(&path).into(), path.clone())]);
+        item_layout::locate_items(tab.scrollable_id(), items, move |layout| {
+            Message::ItemLocated(path.clone(), layout)
+        })
     }
 
-    pub fn is_renaming(&self, path: &PathBuf) -> bool {
-        self.renaming_path.as_ref() == Some(path)
+    // Arrow keys in the column view: up and down within the last column, left
+    // back to the previous column and right into the selected folder
+    fn move_in_columns(&mut self, direction: Direction) -> Command<Message> {
+        let tab = self.tab();
+        let order = tab.visible_paths();
+        let lead = tab.selection.lead().cloned();
+        match direction {
+            Direction::Up | Direction::Down => {
+                let target = match &lead {
+                    Some(lead) => neighbour_in_order(&order, lead, direction == Direction::Down),
+                    None => order.first().cloned(),
+                };
+                match target {
+                    Some(target) => self.select_with_keyboard(target, &order),
+                    None => Command::none(),
+                }
+            }
+            Direction::Left if tab.current_path != tab.column_root => {
+                let folder = tab.current_path.clone();
+                let command = self.update(Message::GoUp);
+                self.tab_mut().selection.select_only(folder);
+                command
+            }
+            Direction::Right => match lead {
+                Some(lead) if lead.is_dir() => self.update(Message::Navigate(lead)),
+                _ => Command::none(),
+            },
+            Direction::Left => Command::none(),
+        }
+    }
+
+    // In the column view a click on a folder opens it as the next column, and a
+    // click on a file left of the last column goes back to that file's folder.
+    // Returns None for files of the last column, which are selected as usual.
+    fn column_item_clicked(&mut self, path: &Path) -> Option<Command<Message>> {
+        if path.is_dir() {
+            return Some(self.update(Message::Navigate(path.to_path_buf())));
+        }
+        let parent = path.parent()?.to_path_buf();
+        if parent == self.tab().current_path {
+            return None;
+        }
+        let command = self.update(Message::Navigate(parent));
+        self.tab_mut().selection.select_only(path.to_path_buf());
+        Some(Command::batch([command, self.load_column_preview()]))
+    }
+
+    // The column view previews the selected file in its last column
+    fn load_column_preview(&self) -> Command<Message> {
+        let tab = self.tab();
+        if tab.view_mode != ViewMode::Columns {
+            return Command::none();
+        }
+        let selected = tab
+            .selection
+            .single()
+            .and_then(|path| tab.entries.iter().find(|e| &e.path == path));
+        match selected {
+            Some(entry) if !entry.is_dir => {
+                let path = entry.path.clone();
+                Command::perform(load_preview(path.clone()), move |result| {
+                    Message::LoadPreview(path, result)
+                })
+            }
+            _ => Command::none(),
+        }
+    }
+
+    pub fn find_trash_entry(&self, trashed_path: &PathBuf) -> Option<TrashEntry> {
+        self.trash_entries
+            .iter()
+            .find(|e| &e.trashed_path == trashed_path)
+            .cloned()
+    }
+
+    pub fn trash_size(&self) -> u64 {
+        self.trash_entries.iter().map(|e| e.size).sum()
+    }
+
+    fn run_confirmed_action(&mut self, action: ConfirmAction) -> Command<Message> {
+        match action {
+            ConfirmAction::DeletePermanently(paths) => Command::perform(
+                async move {
+                    for path in paths {
+                        delete_item(path).await?;
+                    }
+                    Ok(())
+                },
+                |result| Message::ItemsDeleted(vec![], result),
+            ),
+            ConfirmAction::DeleteFromTrash(entries) => Command::perform(
+                async move {
+                    for entry in entries {
+                        delete_from_trash(entry).await?;
+                    }
+                    Ok(())
+                },
+                Message::TrashUpdated,
+            ),
+            ConfirmAction::EmptyTrash => Command::perform(empty_trash(), Message::TrashUpdated),
+        }
+    }
+
+    // Queues a copy or move in the background and shows its progress
+    fn start_paste(
+        &mut self,
+        sources: Vec<PathBuf>,
+        action: ClipboardAction,
+        destination_dir: PathBuf,
+    ) -> Command<Message> {
+        self.jobs.enqueue(action, sources, destination_dir);
+        self.show_jobs_panel = true;
+        self.start_jobs()
+    }
+
+    // Pastes the clipboard into the folder shown by the given tab
+    fn paste_into(&mut self, tab_id: TabId) -> Command<Message> {
+        let Some(destination_dir) = self
+            .all_tabs()
+            .find(|tab| tab.id == tab_id && !tab.showing_trash)
+            .map(|tab| tab.current_path.clone())
+        else {
+            return Command::none();
+        };
+        self.paste_to(destination_dir)
+    }
+
+    // Pastes the clipboard into `destination_dir`
+    fn paste_to(&mut self, destination_dir: PathBuf) -> Command<Message> {
+        if let Some((sources, action)) = self.clipboard.clone() {
+            println!(
+                "Paste requested: {:?} {} item(s) to {}",
+                action,
+                sources.len(),
+                destination_dir.display()
+            );
+
+            self.start_paste(sources, action, destination_dir)
+        } else {
+            self.tab_mut().error = Some("Clipboard is empty.".to_string());
+            Command::none()
+        }
+    }
+
+    // Shows the next conflict a job is waiting on, unless another dialog is open
+    fn open_next_conflict(&mut self) {
+        if self.dialog.is_some() {
+            return;
+        }
+        if let Some((job_id, conflict)) = self.jobs.next_conflict() {
+            self.dialog = Some(Dialog::Conflict(ConflictDialog {
+                job_id,
+                conflict: conflict.clone(),
+                apply_to_all: false,
+            }));
+        }
+    }
+
+    fn start_jobs(&mut self) -> Command<Message> {
+        Command::batch(self.jobs.start_queued().into_iter().map(|(id, events)| {
+            Command::run(events, move |event| Message::JobUpdated(id, event))
+        }))
+    }
+
+    // Adds completed operations to the undo history once they are fingerprinted
+    fn record_operations(&mut self, operations: Vec<FileOperation>) -> Command<Message> {
+        if operations.is_empty() {
+            return Command::none();
+        }
+        self.journal.start_recording();
+        Command::perform(
+            journal::fingerprint(operations),
+            Message::OperationsFingerprinted,
+        )
+    }
+
+    // Searches the folder of the tab for `query`, replacing (and stopping) the
+    // tab's previous search. An empty query ends the search.
+    fn start_search(&mut self, tab_id: TabId, query: String) -> Command<Message> {
+        let (mode, show_hidden) = (self.search_mode, self.show_hidden_files);
+        let index = self.all_tabs().find(|tab| tab.id == tab_id).and_then(|tab| {
+            self.search_index
+                .as_ref()?
+                .reader_for(&tab.current_path)
+        });
+        let Some(tab) = self.tab_by_id_mut(tab_id) else {
+            return Command::none();
+        };
+        if query.is_empty() || tab.showing_trash {
+            tab.search = None;
+            return Command::none();
+        }
+        let (search, events) =
+            Search::start(tab.current_path.clone(), query, mode, show_hidden, index);
+        let search_id = search.id;
+        tab.search = Some(search);
+        match events {
+            Some(events) => Command::run(events, move |event| {
+                Message::SearchUpdated(tab_id, search_id, event)
+            }),
+            None => Command::none(),
+        }
+    }
+
+    // Turns indexing of the home folder on
+    fn start_index(&mut self) -> Command<Message> {
+        let Some(home) = dirs::home_dir() else {
+            return Command::none();
+        };
+        let (index, statuses) = SearchIndex::start(home);
+        self.search_index = Some(index);
+        Command::run(statuses, Message::IndexStatusChanged)
+    }
+
+    // Reloads whatever is currently shown, after the filesystem was changed
+    fn refresh_view(&self) -> Command<Message> {
+        if self.tab().showing_trash {
+            return Command::perform(list_trash(), Message::TrashLoaded);
+        }
+        self.load_tab(self.tab())
+    }
+
+    // Returns from the trash view to the folder that was open before it
+    fn leave_trash(&mut self) -> Command<Message> {
+        let tab = self.tab_mut();
+        tab.showing_trash = false;
+        tab.error = None;
+        tab.selection.clear();
+        self.preview_content = None;
+        self.load_tab(self.tab())
+    }
+
+    // The selection in display order
+    pub fn selected_paths(&self) -> Vec<PathBuf> {
+        let tab = self.tab();
+        if tab.showing_trash {
+            return tab.selection.ordered(self.trash_entries.iter().map(|e| &e.trashed_path));
+        }
+        tab.selection.ordered(tab.entries.iter().map(|e| &e.path))
+    }
+
+    fn load_lead_thumbnail(&self) -> Command<Message> {
+        let tab = self.tab();
+        let lead = tab
+            .selection
+            .lead()
+            .and_then(|lead| tab.entries.iter().find(|e| &e.path == lead));
+        if let Some(entry) = lead {
+            if entry.mime_group.as_deref() == Some("Images") && entry.thumbnail.is_none() {
+                let p = entry.path.clone();
+                return Command::perform(load_thumbnail_async(p.clone()), move |handle| {
+                    Message::ThumbnailLoaded(p, handle)
+                });
+            }
+        }
+        Command::none()
+    }
+}
+
+// The item after (or before) `path` in `order`, staying at the ends
+fn neighbour_in_order(order: &[PathBuf], path: &PathBuf, forward: bool) -> Option<PathBuf> {
+    let index = order.iter().position(|p| p == path)?;
+    let target = if forward {
+        (index + 1).min(order.len() - 1)
+    } else {
+        index.saturating_sub(1)
+    };
+    order.get(target).cloned()
+}
+
+// Group id and title an entry is shown under, or None when not grouping
+pub fn group_of(entry: &DirEntry, criteria: GroupCriteria) -> Option<(String, String)> {
+    let folders = || Some(("folders".to_string(), "Folders".to_string()));
+    match criteria {
+        GroupCriteria::None => None,
+        GroupCriteria::Type if entry.is_dir => folders(),
+        GroupCriteria::Type => Some(("files".to_string(), "Files".to_string())),
+        GroupCriteria::MimeType if entry.is_dir => folders(),
+        GroupCriteria::MimeType => {
+            let name = entry
+                .mime_group
+                .clone()
+                .unwrap_or_else(|| "Other".to_string());
+            Some((name.clone(), name))
+        }
     }
 }
 
@@ -0,0 +1,649 @@
+// Batch rename: new names for many items from one set of rules.
+//
+// The new name is built from the old one without its extension: find/replace
+// (literal or regex), then prefix and suffix, then the case change; the
+// extension is kept. Prefix, suffix and the replacement may contain tokens:
+// {n} the running number, {name} the old name, {ext} the extension, {date}
+// the modification date and {exif} the date a photo was taken.
+use crate::journal::FileOperation;
+use chrono::{DateTime, Local};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseChange {
+    Keep,
+    Lower,
+    Upper,
+    Title,
+}
+
+impl CaseChange {
+    pub const ALL: [CaseChange; 4] = [
+        CaseChange::Keep,
+        CaseChange::Lower,
+        CaseChange::Upper,
+        CaseChange::Title,
+    ];
+
+    fn apply(self, name: &str) -> String {
+        match self {
+            CaseChange::Keep => name.to_string(),
+            CaseChange::Lower => name.to_lowercase(),
+            CaseChange::Upper => name.to_uppercase(),
+            CaseChange::Title => {
+                // Capitalizes the first letter after a space, '_', '-' or '.'
+                let mut at_word_start = true;
+                name.chars()
+                    .flat_map(|c| {
+                        let changed: Vec<char> = if at_word_start {
+                            c.to_uppercase().collect()
+                        } else {
+                            c.to_lowercase().collect()
+                        };
+                        at_word_start = c.is_whitespace() || matches!(c, '_' | '-' | '.');
+                        changed
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for CaseChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CaseChange::Keep => "Keep case",
+            CaseChange::Lower => "lowercase",
+            CaseChange::Upper => "UPPERCASE",
+            CaseChange::Title => "Title Case",
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RenameRules {
+    pub find: String,
+    pub replace: String,
+    pub use_regex: bool,
+    pub prefix: String,
+    pub suffix: String,
+    // {n} counts from `start`, padded with zeros to `padding` digits
+    pub start: u32,
+    pub padding: usize,
+    pub case: CaseChange,
+}
+
+impl Default for RenameRules {
+    fn default() -> Self {
+        RenameRules {
+            find: String::new(),
+            replace: String::new(),
+            use_regex: false,
+            prefix: String::new(),
+            suffix: String::new(),
+            start: 1,
+            padding: 2,
+            case: CaseChange::Keep,
+        }
+    }
+}
+
+// A change to one of the rules, from the dialog's fields
+#[derive(Debug, Clone)]
+pub enum RuleEdit {
+    Find(String),
+    Replace(String),
+    UseRegex(bool),
+    Prefix(String),
+    Suffix(String),
+    Start(String),
+    Padding(String),
+    Case(CaseChange),
+}
+
+impl RenameRules {
+    pub fn edit(&mut self, edit: RuleEdit) {
+        match edit {
+            RuleEdit::Find(find) => self.find = find,
+            RuleEdit::Replace(replace) => self.replace = replace,
+            RuleEdit::UseRegex(use_regex) => self.use_regex = use_regex,
+            RuleEdit::Prefix(prefix) => self.prefix = prefix,
+            RuleEdit::Suffix(suffix) => self.suffix = suffix,
+            // Number fields ignore anything that is not a number
+            RuleEdit::Start(start) if start.is_empty() => self.start = 0,
+            RuleEdit::Start(start) => {
+                if let Ok(start) = start.parse() {
+                    self.start = start;
+                }
+            }
+            RuleEdit::Padding(padding) if padding.is_empty() => self.padding = 0,
+            RuleEdit::Padding(padding) => {
+                if let Ok(padding) = padding.parse::<usize>() {
+                    self.padding = padding.min(10);
+                }
+            }
+            RuleEdit::Case(case) => self.case = case,
+        }
+    }
+}
+
+// An item to rename with what the tokens need to know about it
+#[derive(Debug, Clone)]
+pub struct BatchItem {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    modified: Option<SystemTime>,
+    // "YYYY-MM-DD_HHMMSS" from the photo's EXIF data
+    taken: Option<String>,
+}
+
+impl BatchItem {
+    // Names that are not UTF-8 cannot be edited as text without changing them
+    fn has_text_name(&self) -> bool {
+        self.path.file_name().unwrap_or_default().to_str().is_some()
+    }
+
+    // The old name split into the part that is renamed and the extension
+    fn stem_and_extension(&self) -> (String, Option<String>) {
+        let name = self
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        match (self.is_dir, self.path.file_stem(), self.path.extension()) {
+            (false, Some(stem), Some(ext)) => (
+                stem.to_string_lossy().into_owned(),
+                Some(ext.to_string_lossy().into_owned()),
+            ),
+            _ => (name, None),
+        }
+    }
+
+    fn date(&self) -> String {
+        self.modified
+            .map(|m| DateTime::<Local>::from(m).format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    }
+}
+
+// The EXIF date a photo was taken, if the file has one
+fn exif_date(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let exif = exif::Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()?;
+    let field = exif
+        .get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY)
+        .or_else(|| exif.get_field(exif::Tag::DateTime, exif::In::PRIMARY))?;
+    let exif::Value::Ascii(ref values) = field.value else {
+        return None;
+    };
+    let taken = exif::DateTime::from_ascii(values.first()?).ok()?;
+    Some(format!(
+        "{:04}-{:02}-{:02}_{:02}{:02}{:02}",
+        taken.year, taken.month, taken.day, taken.hour, taken.minute, taken.second
+    ))
+}
+
+// Reads the dates of the items off the UI thread
+pub async fn load_items(paths: Vec<PathBuf>) -> Result<Vec<BatchItem>, String> {
+    tokio::task::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .map(|path| {
+                let meta = fs::symlink_metadata(&path)
+                    .map_err(|e| format!("Cannot read '{}': {}", path.display(), e))?;
+                let is_dir = meta.is_dir();
+                let is_image = mime_guess::from_path(&path)
+                    .first()
+                    .is_some_and(|mime| mime.type_() == mime_guess::mime::IMAGE);
+                Ok(BatchItem {
+                    taken: if is_image { exif_date(&path) } else { None },
+                    modified: meta.modified().ok(),
+                    is_dir,
+                    path,
+                })
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+// The new name of one item and why it cannot be used, if it cannot
+#[derive(Debug, Clone)]
+pub struct PlannedName {
+    pub source: PathBuf,
+    pub new_name: String,
+    pub problem: Option<String>,
+}
+
+impl PlannedName {
+    pub fn destination(&self) -> PathBuf {
+        if self.is_unchanged() {
+            return self.source.clone();
+        }
+        self.source.with_file_name(&self.new_name)
+    }
+
+    // Compared as text, so a name that is not UTF-8 is unchanged as long as
+    // the rules leave it alone
+    pub fn is_unchanged(&self) -> bool {
+        self.source
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            == self.new_name
+    }
+}
+
+fn expand_tokens(text: &str, item: &BatchItem, number: &str) -> String {
+    let (stem, extension) = item.stem_and_extension();
+    text.replace("{n}", number)
+        .replace("{name}", &stem)
+        .replace("{ext}", extension.as_deref().unwrap_or(""))
+        .replace("{date}", &item.date())
+        .replace("{exif}", item.taken.as_deref().unwrap_or(&item.date()))
+}
+
+// The new names for `items` in order. Fails only for an invalid regex; names
+// that cannot be used are flagged in the plan.
+pub fn plan(items: &[BatchItem], rules: &RenameRules) -> Result<Vec<PlannedName>, String> {
+    let regex = if rules.use_regex && !rules.find.is_empty() {
+        Some(Regex::new(&rules.find).map_err(|e| format!("Invalid regex: {}", e))?)
+    } else {
+        None
+    };
+
+    let mut planned: Vec<PlannedName> = items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let number = format!(
+                "{:0width$}",
+                rules.start as usize + index,
+                width = rules.padding
+            );
+            let (stem, extension) = item.stem_and_extension();
+            let replacement = expand_tokens(&rules.replace, item, &number);
+            let replaced = match &regex {
+                Some(regex) => regex.replace_all(&stem, replacement.as_str()).into_owned(),
+                None if !rules.find.is_empty() => stem.replace(&rules.find, &replacement),
+                None => stem,
+            };
+            let new_stem = rules.case.apply(&format!(
+                "{}{}{}",
+                expand_tokens(&rules.prefix, item, &number),
+                replaced,
+                expand_tokens(&rules.suffix, item, &number)
+            ));
+            let new_name = match extension {
+                Some(extension) => format!("{}.{}", new_stem, extension),
+                None => new_stem,
+            };
+            PlannedName {
+                source: item.path.clone(),
+                new_name,
+                problem: None,
+            }
+        })
+        .collect();
+
+    let sources: HashSet<&Path> = items.iter().map(|item| item.path.as_path()).collect();
+    let mut uses: HashMap<PathBuf, usize> = HashMap::new();
+    for name in &planned {
+        *uses.entry(name.destination()).or_default() += 1;
+    }
+    for (name, item) in planned.iter_mut().zip(items) {
+        let destination = name.destination();
+        name.problem = if !item.has_text_name() && !name.is_unchanged() {
+            Some("Cannot change a name that is not valid UTF-8".to_string())
+        } else if name.new_name.is_empty()
+            || name.new_name.contains('/')
+            || name.new_name == "."
+            || name.new_name == ".."
+        {
+            Some("Invalid name".to_string())
+        } else if uses[&destination] > 1 {
+            Some("Same new name as another item".to_string())
+        } else if !sources.contains(destination.as_path())
+            && fs::symlink_metadata(&destination).is_ok()
+        {
+            Some("An item with this name already exists".to_string())
+        } else {
+            None
+        };
+    }
+    Ok(planned)
+}
+
+// Renames every item of the plan or none. Renames run in an order in which
+// each new name is free when it is taken; items swapping names go through a
+// temporary name. If a rename fails, the ones done so far are reversed.
+// Returns the renames for the undo journal, including those to and from
+// temporary names, in the order they were done.
+pub async fn apply(planned: Vec<PlannedName>) -> Result<Vec<FileOperation>, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut pending: Vec<(PathBuf, PathBuf)> = planned
+            .iter()
+            .filter(|name| !name.is_unchanged())
+            .map(|name| (name.source.clone(), name.destination()))
+            .collect();
+        if let Some(name) = planned.iter().find(|name| name.problem.is_some()) {
+            return Err(format!(
+                "Cannot rename '{}': {}",
+                name.source.display(),
+                name.problem.as_deref().unwrap_or_default()
+            ));
+        }
+        println!("Batch renaming {} item(s)", pending.len());
+
+        let mut done: Vec<(PathBuf, PathBuf)> = Vec::new();
+        let result: Result<(), String> = (|| {
+            while !pending.is_empty() {
+                // A rename whose new name no pending item still holds
+                let free = pending
+                    .iter()
+                    .position(|(_, to)| !pending.iter().any(|(from, _)| from == to));
+                let (from, to) = match free {
+                    Some(index) => pending.remove(index),
+                    None => {
+                        // Only cycles are left: move one item out of the way
+                        let (from, to) = pending.remove(0);
+                        let temporary = temporary_name(&from);
+                        rename_checked(&from, &temporary)?;
+                        done.push((from, temporary.clone()));
+                        pending.push((temporary, to));
+                        continue;
+                    }
+                };
+                rename_checked(&from, &to)?;
+                done.push((from, to));
+            }
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            let mut not_reverted = Vec::new();
+            for (from, to) in done.iter().rev() {
+                if let Err(rollback_error) = fs::rename(to, from) {
+                    eprintln!(
+                        "Failed to restore '{}' to '{}': {}",
+                        to.display(),
+                        from.display(),
+                        rollback_error
+                    );
+                    not_reverted.push(format!(
+                        "'{}' is still '{}' ({})",
+                        from.display(),
+                        to.display(),
+                        rollback_error
+                    ));
+                }
+            }
+            return Err(if not_reverted.is_empty() {
+                format!("{} (all renames were reverted)", e)
+            } else {
+                format!("{}; reverting failed: {}", e, not_reverted.join("; "))
+            });
+        }
+
+        Ok(done
+            .into_iter()
+            .map(|(source, destination)| FileOperation::Rename {
+                source,
+                destination,
+            })
+            .collect())
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+// Renames without replacing an item that appeared at `to` in the meantime
+fn rename_checked(from: &Path, to: &Path) -> Result<(), String> {
+    if fs::symlink_metadata(to).is_ok() {
+        return Err(format!("'{}' already exists", to.display()));
+    }
+    fs::rename(from, to).map_err(|e| {
+        format!(
+            "Failed to rename '{}' to '{}': {}",
+            from.display(),
+            to.display(),
+            e
+        )
+    })
+}
+
+fn temporary_name(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    (0..)
+        .map(|n| path.with_file_name(format!(".{}.rename-{}", name, n)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("unbounded counter always finds a free name")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::time::Duration;
+    use temp_dir::TempDir;
+
+    fn item(path: PathBuf) -> BatchItem {
+        BatchItem {
+            is_dir: path.is_dir(),
+            path,
+            modified: None,
+            taken: None,
+        }
+    }
+
+    fn files(temp: &TempDir, names: &[&str]) -> Vec<BatchItem> {
+        names
+            .iter()
+            .map(|name| {
+                let path = temp.path().join(name);
+                fs::write(&path, name).unwrap();
+                item(path)
+            })
+            .collect()
+    }
+
+    fn renames(pairs: &[(&Path, &str)]) -> Vec<PlannedName> {
+        pairs
+            .iter()
+            .map(|(source, new_name)| PlannedName {
+                source: source.to_path_buf(),
+                new_name: new_name.to_string(),
+                problem: None,
+            })
+            .collect()
+    }
+
+    fn problems(planned: &[PlannedName]) -> Vec<Option<&str>> {
+        planned.iter().map(|name| name.problem.as_deref()).collect()
+    }
+
+    #[test]
+    fn tokens_are_expanded() {
+        // Noon on 2 January 1970 in UTC is that day in every time zone
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(36 * 60 * 60);
+        let items = [
+            BatchItem {
+                path: PathBuf::from("/photos/IMG 1.jpg"),
+                is_dir: false,
+                modified: Some(modified),
+                taken: Some("2024-05-06_070809".to_string()),
+            },
+            BatchItem {
+                path: PathBuf::from("/photos/scan.v2"),
+                is_dir: true,
+                modified: Some(modified),
+                taken: None,
+            },
+        ];
+        let rules = RenameRules {
+            find: r"\s+(\d+)".to_string(),
+            replace: "-$1-{ext}".to_string(),
+            use_regex: true,
+            prefix: "{n}_".to_string(),
+            suffix: "_{exif}".to_string(),
+            start: 9,
+            padding: 3,
+            case: CaseChange::Title,
+        };
+        let planned = plan(&items, &rules).unwrap();
+        assert_eq!(planned[0].new_name, "009_Img-1-Jpg_2024-05-06_070809.jpg");
+        // Folders keep their whole name, and {exif} falls back to {date}
+        assert_eq!(planned[1].new_name, "010_Scan.V2_1970-01-02");
+
+        let rules = RenameRules {
+            find: "IMG".to_string(),
+            replace: "{name}".to_string(),
+            padding: 0,
+            ..RenameRules::default()
+        };
+        let planned = plan(&items[..1], &rules).unwrap();
+        assert_eq!(planned[0].new_name, "IMG 1 1.jpg");
+        assert!(plan(
+            &items,
+            &RenameRules {
+                find: "(".to_string(),
+                use_regex: true,
+                ..RenameRules::default()
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn unusable_names_are_flagged() {
+        let temp = TempDir::new().unwrap();
+        let items = files(&temp, &["a.txt", "b.txt", "c.txt"]);
+        fs::write(temp.path().join("taken.txt"), "").unwrap();
+
+        let rules = |find: &str, replace: &str| RenameRules {
+            find: find.to_string(),
+            replace: replace.to_string(),
+            use_regex: true,
+            ..RenameRules::default()
+        };
+        let planned = plan(&items, &rules("^[ab]$", "same")).unwrap();
+        assert_eq!(
+            problems(&planned),
+            [
+                Some("Same new name as another item"),
+                Some("Same new name as another item"),
+                None
+            ]
+        );
+        let planned = plan(&items, &rules("^a$", "taken")).unwrap();
+        assert_eq!(
+            problems(&planned)[0],
+            Some("An item with this name already exists")
+        );
+        let planned = plan(&items, &rules("^b$", "x/y")).unwrap();
+        assert_eq!(problems(&planned)[1], Some("Invalid name"));
+        // An item keeping its name still holds it
+        let planned = plan(&items[..2], &rules("^a$", "b")).unwrap();
+        assert_eq!(
+            problems(&planned),
+            [Some("Same new name as another item"); 2]
+        );
+        // Names given up by other items are free
+        let planned = plan(&items, &rules("^(.)$", "$1$1")).unwrap();
+        assert_eq!(problems(&planned), [None, None, None]);
+    }
+
+    #[test]
+    fn names_that_are_not_utf8_are_left_alone() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(OsStr::from_bytes(b"caf\xe9.txt"));
+        fs::write(&path, "").unwrap();
+        let items = [item(path)];
+
+        let planned = plan(&items, &RenameRules::default()).unwrap();
+        assert!(planned[0].is_unchanged());
+        assert_eq!(planned[0].destination(), items[0].path);
+        assert_eq!(problems(&planned), [None]);
+
+        let rules = RenameRules {
+            prefix: "x".to_string(),
+            ..RenameRules::default()
+        };
+        let planned = plan(&items, &rules).unwrap();
+        assert_eq!(
+            problems(&planned),
+            [Some("Cannot change a name that is not valid UTF-8")]
+        );
+    }
+
+    #[tokio::test]
+    async fn chains_and_swaps_are_renamed_in_a_safe_order() {
+        let temp = TempDir::new().unwrap();
+        files(&temp, &["1", "2", "3"]);
+        let path = |name: &str| temp.path().join(name);
+        let content = |name: &str| fs::read_to_string(path(name)).unwrap();
+
+        // 1 -> 2 must wait for 2 -> 3, which must wait for 3 -> 4
+        let planned = renames(&[(&path("1"), "2"), (&path("2"), "3"), (&path("3"), "4")]);
+        let operations = apply(planned).await.unwrap();
+        assert_eq!(operations.len(), 3);
+        assert_eq!(
+            (content("2"), content("3"), content("4")),
+            ("1".to_string(), "2".to_string(), "3".to_string())
+        );
+
+        // Swapping two names goes through a temporary name
+        let planned = renames(&[(&path("2"), "3"), (&path("3"), "2")]);
+        let operations = apply(planned).await.unwrap();
+        assert_eq!(operations.len(), 3);
+        assert_eq!(
+            (content("2"), content("3")),
+            ("2".to_string(), "1".to_string())
+        );
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 3);
+
+        // The swap is undone and redone through the temporary name as well
+        let entry = journal::fingerprint(operations).await.unwrap();
+        let entry = journal::undo(entry).await.unwrap();
+        assert_eq!(
+            (content("2"), content("3")),
+            ("1".to_string(), "2".to_string())
+        );
+        journal::redo(entry).await.unwrap();
+        assert_eq!(
+            (content("2"), content("3")),
+            ("2".to_string(), "1".to_string())
+        );
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn a_failed_rename_reverts_the_others() {
+        let temp = TempDir::new().unwrap();
+        let items = files(&temp, &["a", "b"]);
+        let rules = RenameRules {
+            suffix: "2".to_string(),
+            ..RenameRules::default()
+        };
+        let planned = plan(&items, &rules).unwrap();
+        assert_eq!(problems(&planned), [None, None]);
+        // Appears after the plan was made
+        fs::write(temp.path().join("b2"), "").unwrap();
+
+        let error = apply(planned).await.unwrap_err();
+        assert!(error.ends_with("(all renames were reverted)"), "{}", error);
+        assert!(temp.path().join("a").exists());
+        assert!(!temp.path().join("a2").exists());
+    }
+}
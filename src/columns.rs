@@ -0,0 +1,115 @@
+use crate::app::SortCriteria;
+
+// Columns stay at least this wide so they can still be grabbed
+pub const MIN_COLUMN_WIDTH: f32 = 48.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListColumn {
+    Name,
+    Size,
+    Modified,
+    Type,
+    Permissions,
+    Owner,
+}
+
+impl ListColumn {
+    pub fn title(self) -> &'static str {
+        match self {
+            ListColumn::Name => "Name",
+            ListColumn::Size => "Size",
+            ListColumn::Modified => "Modified",
+            ListColumn::Type => "Type",
+            ListColumn::Permissions => "Permissions",
+            ListColumn::Owner => "Owner",
+        }
+    }
+
+    // The sort order a click on the header selects, if the column has one
+    pub fn sort_criteria(self) -> Option<SortCriteria> {
+        match self {
+            ListColumn::Name => Some(SortCriteria::Name),
+            ListColumn::Size => Some(SortCriteria::Size),
+            ListColumn::Modified => Some(SortCriteria::ModifiedDate),
+            ListColumn::Type => Some(SortCriteria::Type),
+            ListColumn::Permissions | ListColumn::Owner => None,
+        }
+    }
+
+    fn default_width(self) -> f32 {
+        match self {
+            ListColumn::Name => 280.0,
+            ListColumn::Size => 90.0,
+            ListColumn::Modified => 140.0,
+            ListColumn::Type => 110.0,
+            ListColumn::Permissions => 110.0,
+            ListColumn::Owner => 90.0,
+        }
+    }
+}
+
+// Order and widths of the list view's columns, shared by all tabs
+#[derive(Debug, Clone)]
+pub struct ColumnLayout {
+    columns: Vec<(ListColumn, f32)>,
+}
+
+impl Default for ColumnLayout {
+    fn default() -> Self {
+        ColumnLayout {
+            columns: [
+                ListColumn::Name,
+                ListColumn::Size,
+                ListColumn::Modified,
+                ListColumn::Type,
+                ListColumn::Permissions,
+                ListColumn::Owner,
+            ]
+            .into_iter()
+            .map(|column| (column, column.default_width()))
+            .collect(),
+        }
+    }
+}
+
+impl ColumnLayout {
+    // Columns from left to right with their widths
+    pub fn iter(&self) -> impl Iterator<Item = (ListColumn, f32)> + '_ {
+        self.columns.iter().copied()
+    }
+
+    pub fn total_width(&self) -> f32 {
+        self.columns.iter().map(|(_, width)| width).sum()
+    }
+
+    pub fn resize(&mut self, column: ListColumn, delta: f32) {
+        if let Some((_, width)) = self.columns.iter_mut().find(|(c, _)| *c == column) {
+            *width = (*width + delta).max(MIN_COLUMN_WIDTH);
+        }
+    }
+
+    // Moves a column after its header was dragged `offset` pixels sideways. It
+    // passes a neighbour once dragged over more than half of it.
+    pub fn move_by(&mut self, column: ListColumn, offset: f32) {
+        let Some(from) = self.columns.iter().position(|(c, _)| *c == column) else {
+            return;
+        };
+        let mut to = from;
+        let mut remaining = offset;
+        if offset > 0.0 {
+            while to + 1 < self.columns.len() && remaining > self.columns[to + 1].1 / 2.0 {
+                remaining -= self.columns[to + 1].1;
+                to += 1;
+            }
+        } else {
+            while to > 0 && -remaining > self.columns[to - 1].1 / 2.0 {
+                remaining += self.columns[to - 1].1;
+                to -= 1;
+            }
+        }
+        if to != from {
+            let moved = self.columns.remove(from);
+            self.columns.insert(to, moved);
+        }
+    }
+}
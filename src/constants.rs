@@ -11,6 +11,8 @@ pub const VIDEOS_ICON_PATH: &str = "icons/videos.png";
 pub const DESKTOP_ICON_PATH: &str = "icons/desktop.png"; // Added desktop icon
                                                          // TODO: Add a dedicated applications icon
 pub const APPLICATIONS_ICON_PATH: &str = FOLDER_ICON_PATH; // Reusing folder icon for now
+                                                           // TODO: Add a dedicated trash icon
+pub const TRASH_ICON_PATH: &str = FOLDER_ICON_PATH; // Reusing folder icon for now
 
 // Icons for Top Bar Navigation
 pub const BACK_ICON_PATH: &str = "icons/chevron-left.png";
@@ -0,0 +1,139 @@
+use crate::batch_rename::{plan, BatchItem, PlannedName, RenameRules};
+use crate::fs_utils::item_size;
+use crate::jobs::{Conflict, JobId};
+use crate::trash::{list_trash, TrashEntry};
+use std::path::{Path, PathBuf};
+
+// Destructive operations that have to be confirmed before they run
+#[derive(Debug, Clone)]
+pub enum ConfirmAction {
+    DeletePermanently(Vec<PathBuf>),
+    DeleteFromTrash(Vec<TrashEntry>),
+    EmptyTrash,
+}
+
+#[derive(Debug, Clone)]
+pub struct AffectedItem {
+    pub label: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfirmDialog {
+    pub title: String,
+    pub message: String,
+    pub confirm_label: String,
+    pub items: Vec<AffectedItem>,
+    pub action: ConfirmAction,
+}
+
+impl ConfirmDialog {
+    pub fn total_size(&self) -> u64 {
+        self.items.iter().map(|item| item.size).sum()
+    }
+}
+
+// A paste job asking what to do with an item whose destination exists
+#[derive(Debug, Clone)]
+pub struct ConflictDialog {
+    pub job_id: JobId,
+    pub conflict: Conflict,
+    pub apply_to_all: bool,
+}
+
+// New names for several items at once, previewed as the rules are edited
+#[derive(Debug, Clone)]
+pub struct BatchRenameDialog {
+    pub items: Vec<BatchItem>,
+    pub rules: RenameRules,
+    // The new names, or why the rules cannot be applied
+    pub preview: Result<Vec<PlannedName>, String>,
+}
+
+impl BatchRenameDialog {
+    pub fn new(items: Vec<BatchItem>) -> Self {
+        let mut dialog = BatchRenameDialog {
+            items,
+            rules: RenameRules::default(),
+            preview: Ok(vec![]),
+        };
+        dialog.update_preview();
+        dialog
+    }
+
+    pub fn update_preview(&mut self) {
+        self.preview = plan(&self.items, &self.rules);
+    }
+
+    // The renames to run, if every new name can be used and one differs
+    pub fn ready(&self) -> Option<&Vec<PlannedName>> {
+        self.preview.as_ref().ok().filter(|planned| {
+            planned.iter().all(|name| name.problem.is_none())
+                && planned.iter().any(|name| !name.is_unchanged())
+        })
+    }
+}
+
+// Every modal the application can show. Only one is open at a time.
+#[derive(Debug, Clone)]
+pub enum Dialog {
+    Confirm(ConfirmDialog),
+    Conflict(ConflictDialog),
+    BatchRename(BatchRenameDialog),
+}
+
+fn affected_path(path: &Path) -> AffectedItem {
+    AffectedItem {
+        label: path.display().to_string(),
+        size: item_size(path),
+    }
+}
+
+fn affected_trash_entry(entry: &TrashEntry) -> AffectedItem {
+    AffectedItem {
+        label: format!("{} (from {})", entry.name, entry.original_path.display()),
+        size: entry.size,
+    }
+}
+
+// Gathers the affected items and their sizes off the UI thread and builds the
+// dialog text for `action`
+pub async fn prepare_confirmation(action: ConfirmAction) -> Result<ConfirmDialog, String> {
+    let action_for_items = action.clone();
+    let items = match action_for_items {
+        ConfirmAction::EmptyTrash => list_trash()
+            .await?
+            .iter()
+            .map(affected_trash_entry)
+            .collect(),
+        ConfirmAction::DeleteFromTrash(entries) => {
+            entries.iter().map(affected_trash_entry).collect()
+        }
+        ConfirmAction::DeletePermanently(paths) => {
+            tokio::task::spawn_blocking(move || paths.iter().map(|p| affected_path(p)).collect())
+                .await
+                .map_err(|e| format!("Task join error: {}", e))?
+        }
+    };
+
+    let (title, message, confirm_label) = match &action {
+        ConfirmAction::DeletePermanently(_) | ConfirmAction::DeleteFromTrash(_) => (
+            "Delete permanently?",
+            "These items will be deleted immediately. This cannot be undone.",
+            "Delete",
+        ),
+        ConfirmAction::EmptyTrash => (
+            "Empty the trash?",
+            "All items in the trash will be permanently deleted.",
+            "Empty trash",
+        ),
+    };
+
+    Ok(ConfirmDialog {
+        title: title.to_string(),
+        message: message.to_string(),
+        confirm_label: confirm_label.to_string(),
+        items,
+        action,
+    })
+}
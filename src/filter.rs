@@ -0,0 +1,518 @@
+// Filter for the items of the open folder, written as terms separated by
+// spaces: `type:image size:>5M modified:<7d name:*.raw`.
+//
+// An item is shown when it matches every term; a `-` in front of a term keeps
+// the items that do not match it instead. A word without a field looks for
+// that text in the name. Values holding spaces go in double quotes.
+use crate::fs_utils::DirEntry;
+use crate::search::{MatchMode, Matcher};
+use chrono::{Local, NaiveDate};
+use std::time::{Duration, SystemTime};
+
+const FIELDS: &str = "type, size, modified, name, ext or hidden";
+
+#[derive(Debug, Clone)]
+pub struct EntryFilter {
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    negated: bool,
+    condition: Condition,
+}
+
+#[derive(Debug, Clone)]
+enum Condition {
+    // Any of the kinds, as in `type:image,video`
+    Kind(Vec<Kind>),
+    Size(Comparison, u64),
+    // Modified at or after the first time and before the second
+    Modified(Option<SystemTime>, Option<SystemTime>),
+    // An age as a count of units of the given seconds. An item is n units old
+    // from n up to n + 1 units after it was modified, counting from whenever
+    // the filter is applied rather than from when it was typed.
+    Age(Comparison, u64, u64),
+    Name(Matcher),
+    // Lowercase, without the dot
+    Extension(Vec<String>),
+    Hidden(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Folder,
+    File,
+    // One of the groups of `get_mime_group`
+    MimeGroup(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    // Splits the comparison off the front of a value; none means equal
+    fn split(value: &str) -> (Self, &str) {
+        for (prefix, comparison) in [
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+            ("=", Comparison::Equal),
+        ] {
+            if let Some(rest) = value.strip_prefix(prefix) {
+                return (comparison, rest);
+            }
+        }
+        (Comparison::Equal, value)
+    }
+
+    fn holds<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Equal => left == right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Greater => left > right,
+        }
+    }
+}
+
+impl EntryFilter {
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let terms = split_terms(query)?
+            .iter()
+            .map(|term| parse_term(term))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(EntryFilter { terms })
+    }
+
+    pub fn matches(&self, entry: &DirEntry) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.condition.matches(entry) != term.negated)
+    }
+}
+
+impl Condition {
+    fn matches(&self, entry: &DirEntry) -> bool {
+        match self {
+            Condition::Kind(kinds) => kinds.iter().any(|kind| match kind {
+                Kind::Folder => entry.is_dir,
+                Kind::File => !entry.is_dir,
+                Kind::MimeGroup(group) => {
+                    !entry.is_dir && entry.mime_group.as_deref() == Some(*group)
+                }
+            }),
+            Condition::Size(comparison, size) => entry
+                .size
+                .is_some_and(|entry_size| !entry.is_dir && comparison.holds(entry_size, *size)),
+            Condition::Modified(after, before) => entry.modified.is_some_and(|modified| {
+                after.is_none_or(|after| modified >= after)
+                    && before.is_none_or(|before| modified < before)
+            }),
+            Condition::Age(comparison, count, unit_seconds) => {
+                entry.modified.is_some_and(|modified| {
+                    // Times in the future count as just now
+                    let age = SystemTime::now()
+                        .duration_since(modified)
+                        .unwrap_or(Duration::ZERO);
+                    comparison.holds(age.as_secs() / unit_seconds, *count)
+                })
+            }
+            Condition::Name(matcher) => matcher.score(&file_name(entry)).is_some(),
+            Condition::Extension(extensions) => entry
+                .path
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_lowercase())
+                .is_some_and(|extension| extensions.contains(&extension)),
+            Condition::Hidden(hidden) => file_name(entry).starts_with('.') == *hidden,
+        }
+    }
+}
+
+fn file_name(entry: &DirEntry) -> String {
+    entry
+        .path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+// Splits at spaces outside double quotes, dropping the quotes
+fn split_terms(query: &str) -> Result<Vec<String>, String> {
+    let mut terms = Vec::new();
+    let mut term = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !term.is_empty() {
+                    terms.push(std::mem::take(&mut term));
+                }
+            }
+            c => term.push(c),
+        }
+    }
+    if quoted {
+        return Err("Missing closing quote".to_string());
+    }
+    if !term.is_empty() {
+        terms.push(term);
+    }
+    Ok(terms)
+}
+
+fn parse_term(term: &str) -> Result<Term, String> {
+    let (negated, term) = match term.strip_prefix('-') {
+        Some(rest) if !rest.is_empty() => (true, rest),
+        _ => (false, term),
+    };
+    let condition = match term.split_once(':') {
+        Some((field, value)) => {
+            if value.is_empty() {
+                return Err(format!("No value for '{}:'", field));
+            }
+            match field.to_lowercase().as_str() {
+                "type" | "kind" => {
+                    Condition::Kind(value.split(',').map(parse_kind).collect::<Result<_, _>>()?)
+                }
+                "size" => {
+                    let (comparison, size) = Comparison::split(value);
+                    Condition::Size(comparison, parse_size(size)?)
+                }
+                "modified" | "mtime" => parse_modified(value)?,
+                "name" => Condition::Name(name_matcher(value)?),
+                "ext" | "extension" => Condition::Extension(
+                    value
+                        .split(',')
+                        .map(|extension| extension.trim_start_matches('.').to_lowercase())
+                        .collect(),
+                ),
+                "hidden" => Condition::Hidden(match value.to_lowercase().as_str() {
+                    "yes" | "true" => true,
+                    "no" | "false" => false,
+                    _ => return Err(format!("'hidden:' takes yes or no, not '{}'", value)),
+                }),
+                _ => return Err(format!("Unknown filter '{}:', use {}", field, FIELDS)),
+            }
+        }
+        None => Condition::Name(name_matcher(term)?),
+    };
+    Ok(Term { negated, condition })
+}
+
+fn parse_kind(value: &str) -> Result<Kind, String> {
+    Ok(match value.to_lowercase().as_str() {
+        "folder" | "folders" | "dir" | "directory" => Kind::Folder,
+        "file" | "files" => Kind::File,
+        "image" | "images" => Kind::MimeGroup("Images"),
+        "video" | "videos" => Kind::MimeGroup("Videos"),
+        "audio" | "music" => Kind::MimeGroup("Audio"),
+        "text" => Kind::MimeGroup("Text Files"),
+        "document" | "documents" | "archive" | "archives" => {
+            Kind::MimeGroup("Documents & Archives")
+        }
+        "application" | "app" | "other" => Kind::MimeGroup("Applications & Others"),
+        _ => {
+            return Err(format!(
+                "Unknown type '{}', use folder, file, image, video, audio, text, document or app",
+                value
+            ))
+        }
+    })
+}
+
+// Whole names with wildcards, otherwise any name containing the text
+fn name_matcher(value: &str) -> Result<Matcher, String> {
+    let mode = if value.contains(['*', '?', '[']) {
+        MatchMode::Glob
+    } else {
+        MatchMode::Substring
+    };
+    Matcher::new(value, mode)
+}
+
+// A number of bytes, optionally with a unit as in `5M`, `1.5GB` or `700k`;
+// units are powers of 1024, as in the displayed sizes
+fn parse_size(value: &str) -> Result<u64, String> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("Invalid size '{}'", value))?;
+    let power = match unit.to_lowercase().as_str() {
+        "" | "b" => 0,
+        "k" | "kb" | "kib" => 1,
+        "m" | "mb" | "mib" => 2,
+        "g" | "gb" | "gib" => 3,
+        "t" | "tb" | "tib" => 4,
+        _ => return Err(format!("Unknown size unit '{}', use K, M, G or T", unit)),
+    };
+    Ok((number * 1024f64.powi(power)) as u64)
+}
+
+// Either an age, where `<7d` is less than seven days old, or a date, where
+// `<2024-01-01` is before that day began
+fn parse_modified(value: &str) -> Result<Condition, String> {
+    let (comparison, value) = Comparison::split(value);
+    let (start, end) = match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        Ok(date) => {
+            let start = local_midnight(date).ok_or_else(|| format!("Invalid date '{}'", value))?;
+            let end = date
+                .succ_opt()
+                .and_then(local_midnight)
+                .ok_or_else(|| format!("Invalid date '{}'", value))?;
+            (start, end)
+        }
+        // Ages never hold a dash, so this was meant as a date
+        Err(_) if value.contains('-') => {
+            return Err(format!("Invalid date '{}', use 2024-01-31", value))
+        }
+        Err(_) => {
+            let (count, unit_seconds) = parse_age(value)?;
+            return Ok(Condition::Age(comparison, count, unit_seconds));
+        }
+    };
+    Ok(match comparison {
+        Comparison::Less => Condition::Modified(None, Some(start)),
+        Comparison::LessOrEqual => Condition::Modified(None, Some(end)),
+        Comparison::Equal => Condition::Modified(Some(start), Some(end)),
+        Comparison::GreaterOrEqual => Condition::Modified(Some(start), None),
+        Comparison::Greater => Condition::Modified(Some(end), None),
+    })
+}
+
+fn local_midnight(date: NaiveDate) -> Option<SystemTime> {
+    date.and_hms_opt(0, 0, 0)?
+        .and_local_timezone(Local)
+        .earliest()
+        .map(SystemTime::from)
+}
+
+// A count and the seconds in its unit, as in `30min`, `7d` or `2w`
+fn parse_age(value: &str) -> Result<(u64, u64), String> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (count, unit) = value.split_at(split);
+    let count = count
+        .parse()
+        .map_err(|_| format!("Invalid age or date '{}', use 7d or 2024-01-31", value))?;
+    let seconds = match unit.to_lowercase().as_str() {
+        "s" => 1,
+        "min" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        "mo" => 30 * 24 * 60 * 60,
+        "y" => 365 * 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "Unknown age unit '{}', use s, min, h, d, w, mo or y",
+                unit
+            ))
+        }
+    };
+    Ok((count, seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::path::PathBuf;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn entry(name: &str, is_dir: bool, size: u64, modified: SystemTime) -> DirEntry {
+        DirEntry {
+            path: PathBuf::from("/folder").join(name),
+            display_name: name.to_string(),
+            original_desktop_path: None,
+            icon_name: None,
+            resolved_icon_path: None,
+            is_dir,
+            size: Some(size),
+            modified: Some(modified),
+            permissions: None,
+            owner: None,
+            mime_group: (!is_dir)
+                .then(|| mime_guess::from_path(name).first())
+                .flatten()
+                .and_then(|mime| crate::fs_utils::get_mime_group(&mime)),
+            thumbnail: None,
+        }
+    }
+
+    fn file(name: &str) -> DirEntry {
+        entry(name, false, 0, SystemTime::now())
+    }
+
+    fn days_ago(days: f64) -> SystemTime {
+        SystemTime::now() - Duration::from_secs_f64(days * DAY as f64)
+    }
+
+    fn local_noon(year: i32, month: u32, day: u32) -> SystemTime {
+        Local
+            .with_ymd_and_hms(year, month, day, 12, 0, 0)
+            .unwrap()
+            .into()
+    }
+
+    // The names of `entries` that `query` keeps
+    fn matching<'a>(query: &str, entries: &'a [DirEntry]) -> Vec<&'a str> {
+        let filter = EntryFilter::parse(query).unwrap();
+        entries
+            .iter()
+            .filter(|entry| filter.matches(entry))
+            .map(|entry| entry.display_name.as_str())
+            .collect()
+    }
+
+    fn error(query: &str) -> String {
+        EntryFilter::parse(query).unwrap_err()
+    }
+
+    #[test]
+    fn quotes_group_words_and_minus_negates() {
+        let entries = [
+            file("holiday photos.zip"),
+            file("holiday.jpg"),
+            file("photos.txt"),
+            entry("Photos", true, 0, SystemTime::now()),
+        ];
+        assert_eq!(
+            matching("\"holiday photos\"", &entries),
+            ["holiday photos.zip"]
+        );
+        assert_eq!(matching("holiday photos", &entries), ["holiday photos.zip"]);
+        assert_eq!(
+            matching("photos -type:folder", &entries),
+            ["holiday photos.zip", "photos.txt"]
+        );
+        assert_eq!(matching("-ext:jpg,ZIP", &entries), ["photos.txt", "Photos"]);
+        assert_eq!(matching("name:*.jpg", &entries), ["holiday.jpg"]);
+        assert_eq!(
+            matching("type:image,text", &entries),
+            ["holiday.jpg", "photos.txt"]
+        );
+        // A lone minus is a name
+        assert_eq!(matching("-", &[file("a-b"), file("ab")]), ["a-b"]);
+        assert_eq!(
+            matching("hidden:yes", &[file(".profile"), file("profile")]),
+            [".profile"]
+        );
+    }
+
+    #[test]
+    fn sizes_take_units_of_1024() {
+        let now = SystemTime::now();
+        let entries = [
+            entry("small", false, 700, now),
+            entry("kilobyte", false, 1024, now),
+            entry("large", false, 5 * 1024 * 1024 + 1, now),
+            // Folders have no size to compare
+            entry("folder", true, 10 * 1024 * 1024, now),
+        ];
+        assert_eq!(matching("size:>5M", &entries), ["large"]);
+        assert_eq!(matching("size:>=5mb", &entries), ["large"]);
+        assert_eq!(matching("size:1k", &entries), ["kilobyte"]);
+        assert_eq!(matching("size:<=1KiB", &entries), ["small", "kilobyte"]);
+        assert!(matching("size:<0.5k", &entries).is_empty());
+        assert_eq!(matching("size:<1.5K", &entries), ["small", "kilobyte"]);
+    }
+
+    #[test]
+    fn ages_count_back_from_now() {
+        let entries = [
+            entry("today", false, 0, days_ago(0.5)),
+            entry("last week", false, 0, days_ago(7.5)),
+            entry("last month", false, 0, days_ago(30.0)),
+        ];
+        assert_eq!(matching("modified:<7d", &entries), ["today"]);
+        assert_eq!(matching("modified:7d", &entries), ["last week"]);
+        assert_eq!(matching("modified:<=7d", &entries), ["today", "last week"]);
+        assert_eq!(matching("modified:>7d", &entries), ["last month"]);
+        assert_eq!(
+            matching("modified:>=1w", &entries),
+            ["last week", "last month"]
+        );
+        assert!(matching("mtime:<12h", &entries).is_empty());
+    }
+
+    #[test]
+    fn ages_count_from_when_the_filter_is_applied() {
+        let filter = EntryFilter::parse("modified:<1s").unwrap();
+        let new = entry("new", false, 0, SystemTime::now());
+        assert!(filter.matches(&new));
+        std::thread::sleep(Duration::from_millis(1100));
+        assert!(!filter.matches(&new));
+        assert!(filter.matches(&entry("newer", false, 0, SystemTime::now())));
+    }
+
+    #[test]
+    fn dates_cover_whole_days() {
+        let entries = [
+            entry("new year's eve", false, 0, local_noon(2023, 12, 31)),
+            entry("new year", false, 0, local_noon(2024, 1, 1)),
+            entry("day after", false, 0, local_noon(2024, 1, 2)),
+        ];
+        assert_eq!(matching("modified:2024-01-01", &entries), ["new year"]);
+        assert_eq!(
+            matching("modified:<2024-01-01", &entries),
+            ["new year's eve"]
+        );
+        assert_eq!(
+            matching("modified:<=2024-01-01", &entries),
+            ["new year's eve", "new year"]
+        );
+        assert_eq!(matching("modified:>2024-01-01", &entries), ["day after"]);
+        assert_eq!(
+            matching("modified:>=2024-01-01", &entries),
+            ["new year", "day after"]
+        );
+    }
+
+    #[test]
+    fn mistakes_are_explained() {
+        assert_eq!(error("\"open quote"), "Missing closing quote");
+        assert_eq!(error("size:"), "No value for 'size:'");
+        assert_eq!(
+            error("colour:red"),
+            "Unknown filter 'colour:', use type, size, modified, name, ext or hidden"
+        );
+        assert_eq!(
+            error("type:spreadsheet"),
+            "Unknown type 'spreadsheet', use folder, file, image, video, audio, text, document or app"
+        );
+        assert_eq!(error("size:>lots"), "Invalid size 'lots'");
+        assert_eq!(error("size:5X"), "Unknown size unit 'X', use K, M, G or T");
+        assert_eq!(
+            error("modified:yesterday"),
+            "Invalid age or date 'yesterday', use 7d or 2024-01-31"
+        );
+        assert_eq!(
+            error("modified:<3days"),
+            "Unknown age unit 'days', use s, min, h, d, w, mo or y"
+        );
+        assert_eq!(
+            error("hidden:maybe"),
+            "'hidden:' takes yes or no, not 'maybe'"
+        );
+        assert_eq!(
+            error("modified:>2024-02-30"),
+            "Invalid date '2024-02-30', use 2024-01-31"
+        );
+    }
+}
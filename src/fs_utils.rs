@@ -6,7 +6,6 @@ use dashmap::DashMap;
 use directories::ProjectDirs;
 use freedesktop_desktop_entry::DesktopEntry;
 use freedesktop_icons::lookup;
-use fs_extra::dir::CopyOptions;
 use iced::widget::image as iced_image; // Alias iced's image module
 use image::{imageops, ImageError, ImageReader}; // Use ImageReader directly
 use mime_guess::{self, mime};
@@ -15,11 +14,13 @@ use ron::ser::PrettyConfig;
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::fs::{self, create_dir_all, File};
-use std::io::{self, BufReader, BufWriter, Read};
-use std::os::unix::fs::symlink;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::ffi::CStr;
+use std::os::unix::fs::{symlink, MetadataExt};
 use std::path::{Path, PathBuf};
 use std::process::Command as StdCommand;
 use std::time::SystemTime;
+use walkdir::WalkDir;
 use xdg::BaseDirectories;
 
 // Define the desired icon size (adjust as needed)
@@ -44,6 +45,9 @@ static ICON_CACHE: Lazy<DashMap<String, Option<PathBuf>>> =
         }
     });
 
+// User names by uid, looked up once for the owner column
+static OWNER_NAMES: Lazy<DashMap<u32, String>> = Lazy::new(DashMap::new);
+
 // Helper function to get the cache file path
 fn get_cache_file_path() -> Result<PathBuf, String> {
     let xdg_dirs = BaseDirectories::with_prefix("file-manager")
@@ -128,11 +132,13 @@ pub struct DirEntry {
     pub is_dir: bool,
     pub size: Option<u64>,
     pub modified: Option<SystemTime>,
+    pub permissions: Option<u32>,
+    pub owner: Option<u32>,
     pub mime_group: Option<String>,
     pub thumbnail: Option<iced_image::Handle>, // Use alias
 }
 
-fn get_mime_group(mime_type: &mime_guess::Mime) -> Option<String> {
+pub fn get_mime_group(mime_type: &mime_guess::Mime) -> Option<String> {
     match mime_type.type_() {
         mime::TEXT => Some("Text Files".to_string()),
         mime::IMAGE => Some("Images".to_string()),
@@ -245,7 +251,7 @@ pub async fn open_file(path: PathBuf) -> Result<(), String> {
             if let Ok(target_path) = fs::read_link(&path) {
                 if target_path
                     .extension()
-                    .map_or(false, |ext| ext == "desktop")
+                    .is_some_and(|ext| ext == "desktop")
                 {
                     println!(
                         "Detected symlink to .desktop file: {} -> {}",
@@ -273,7 +279,7 @@ pub async fn open_file(path: PathBuf) -> Result<(), String> {
         {
             if path_to_open
                 .extension()
-                .map_or(false, |ext| ext == "desktop")
+                .is_some_and(|ext| ext == "desktop")
             {
                 println!(
                     "Launching .desktop file using 'gio launch': {}",
@@ -349,192 +355,225 @@ pub async fn load_preview(path: PathBuf) -> Result<PreviewContent, String> {
     .map_err(|e| format!("Task join error: {}", e))?
 }
 
-pub async fn read_dir(
-    path: PathBuf,
-    show_hidden: bool,
-    sort_criteria: SortCriteria,
-    sort_order: SortOrder,
-    group_criteria: GroupCriteria,
-) -> Result<Vec<DirEntry>, String> {
-    let home_dir = dirs::home_dir().ok_or_else(|| "Could not find home directory".to_string())?;
-    let app_dir = home_dir.join("Applications");
-    let is_app_dir = path == app_dir;
-
-    let read_dir_iter = fs::read_dir(&path)
-        .map_err(|e| format!("Failed to read directory {}: {}", path.display(), e))?;
-
-    let mut entries_futures = Vec::new();
+fn is_applications_dir(path: &Path) -> bool {
+    dirs::home_dir().is_some_and(|home| path == home.join("Applications"))
+}
 
-    for entry_result in read_dir_iter {
-        if let Ok(entry) = entry_result {
-            let entry_path = entry.path();
-            let entry_path_clone = entry_path.clone();
+// Builds the entry for a single item, or None if it is hidden or no longer exists
+async fn load_entry(entry_path: PathBuf, show_hidden: bool, is_app_dir: bool) -> Option<DirEntry> {
+    let file_type = fs::symlink_metadata(&entry_path).ok()?.file_type();
 
-            entries_futures.push(tokio::spawn(async move {
-                let file_type = entry.file_type().ok();
+    let file_name = entry_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
 
-                let file_name = entry_path_clone
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string();
+    let is_hidden = file_name.starts_with('.');
+    if !show_hidden && is_hidden {
+        return None;
+    }
 
-                let is_hidden = file_name.starts_with('.');
-                if !show_hidden && is_hidden {
-                    return None;
-                }
+    let mut display_name = file_name.clone();
+    let mut original_desktop_path: Option<PathBuf> = None;
+    let mut icon_name: Option<String> = None;
+    let mut resolved_icon_path: Option<PathBuf> = None;
 
-                let mut display_name = file_name.clone();
-                let mut original_desktop_path: Option<PathBuf> = None;
-                let mut icon_name: Option<String> = None;
-                let mut resolved_icon_path: Option<PathBuf> = None;
-
-                if is_app_dir && file_type.map_or(false, |ft| ft.is_symlink()) {
-                    if let Ok(target_path) = fs::read_link(&entry_path_clone) {
-                        if target_path
-                            .extension()
-                            .map_or(false, |ext| ext == "desktop")
-                        {
-                            match DesktopEntry::from_path(&target_path, None::<&[&str]>) {
-                                Ok(desktop_entry) => {
-                                    display_name = desktop_entry
-                                        .name(&[] as &[&str])
-                                        .map(|cow| cow.into_owned())
-                                        .unwrap_or(file_name.clone());
-                                    original_desktop_path = Some(target_path);
-                                    icon_name = desktop_entry.icon().map(|cow| cow.to_owned());
-
-                                    if let Some(name) = &icon_name {
-                                        if !name.is_empty() {
-                                            resolved_icon_path = ICON_CACHE
-                                                .entry(name.clone())
-                                                .or_insert_with(|| {
-                                                    lookup(name).with_size(DESIRED_ICON_SIZE).find()
-                                                })
-                                                .value()
-                                                .clone();
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!(
-                                        "Failed to parse desktop file {}: {}",
-                                        original_desktop_path
-                                            .as_ref()
-                                            .map(|p| p.display())
-                                            .unwrap_or(entry_path_clone.display()),
-                                        e
-                                    );
-                                }
+    if is_app_dir && file_type.is_symlink() {
+        if let Ok(target_path) = fs::read_link(&entry_path) {
+            if target_path
+                .extension()
+                .is_some_and(|ext| ext == "desktop")
+            {
+                match DesktopEntry::from_path(&target_path, None::<&[&str]>) {
+                    Ok(desktop_entry) => {
+                        display_name = desktop_entry
+                            .name(&[] as &[&str])
+                            .map(|cow| cow.into_owned())
+                            .unwrap_or(file_name.clone());
+                        original_desktop_path = Some(target_path);
+                        icon_name = desktop_entry.icon().map(|cow| cow.to_owned());
+
+                        if let Some(name) = &icon_name {
+                            if !name.is_empty() {
+                                resolved_icon_path = ICON_CACHE
+                                    .entry(name.clone())
+                                    .or_insert_with(|| {
+                                        lookup(name).with_size(DESIRED_ICON_SIZE).find()
+                                    })
+                                    .value()
+                                    .clone();
                             }
                         }
                     }
+                    Err(e) => {
+                        eprintln!(
+                            "Failed to parse desktop file {}: {}",
+                            original_desktop_path
+                                .as_ref()
+                                .map(|p| p.display())
+                                .unwrap_or(entry_path.display()),
+                            e
+                        );
+                    }
                 }
+            }
+        }
+    }
 
-                let fs_metadata = fs::metadata(&entry_path_clone).ok();
-                let is_dir = fs_metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
-                let size = fs_metadata.as_ref().map(|m| m.len());
-                let modified = fs_metadata.as_ref().and_then(|m| m.modified().ok());
+    let fs_metadata = fs::metadata(&entry_path).ok();
+    let is_dir = fs_metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
+    let size = fs_metadata.as_ref().map(|m| m.len());
+    let modified = fs_metadata.as_ref().and_then(|m| m.modified().ok());
+    let permissions = fs_metadata.as_ref().map(|m| m.mode());
+    let owner = fs_metadata.as_ref().map(|m| m.uid());
 
-                let mime_type = if is_dir {
-                    None
-                } else {
-                    mime_guess::from_path(&entry_path_clone).first()
-                };
-                let mime_group = mime_type.as_ref().and_then(get_mime_group);
-
-                let thumbnail = if !is_dir && mime_group.as_deref() == Some("Images") {
-                    let path_for_thumb = entry_path_clone.clone();
-                    tokio::task::spawn_blocking(move || generate_thumbnail(&path_for_thumb).ok())
-                        .await
-                        .ok()
-                        .flatten()
-                } else {
-                    None
-                };
+    let mime_type = if is_dir {
+        None
+    } else {
+        mime_guess::from_path(&entry_path).first()
+    };
+    let mime_group = mime_type.as_ref().and_then(get_mime_group);
+
+    let thumbnail = if !is_dir && mime_group.as_deref() == Some("Images") {
+        let path_for_thumb = entry_path.clone();
+        tokio::task::spawn_blocking(move || generate_thumbnail(&path_for_thumb).ok())
+            .await
+            .ok()
+            .flatten()
+    } else {
+        None
+    };
 
-                Some(DirEntry {
-                    path: entry_path_clone,
-                    display_name,
-                    original_desktop_path,
-                    icon_name,
-                    resolved_icon_path,
-                    is_dir,
-                    size,
-                    modified,
-                    mime_group,
-                    thumbnail, // Already uses the updated DirEntry struct field type
-                })
-            }));
-        }
-    }
+    Some(DirEntry {
+        path: entry_path,
+        display_name,
+        original_desktop_path,
+        icon_name,
+        resolved_icon_path,
+        is_dir,
+        size,
+        modified,
+        permissions,
+        owner,
+        mime_group,
+        thumbnail, // Already uses the updated DirEntry struct field type
+    })
+}
 
-    let mut entries: Vec<DirEntry> = Vec::new();
-    for future in entries_futures {
-        if let Ok(Some(entry)) = future.await {
-            entries.push(entry);
-        }
-    }
+// The order entries are shown in: by group first, then by the sort criteria
+pub fn compare_entries(
+    a: &DirEntry,
+    b: &DirEntry,
+    sort_criteria: SortCriteria,
+    sort_order: SortOrder,
+    group_criteria: GroupCriteria,
+) -> Ordering {
+    let group_ordering = match group_criteria {
+        GroupCriteria::None => Ordering::Equal,
+        GroupCriteria::Type => b.is_dir.cmp(&a.is_dir),
+        GroupCriteria::MimeType => match (a.is_dir, b.is_dir) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            _ => a.mime_group.cmp(&b.mime_group),
+        },
+    };
 
-    entries.sort_by(|a, b| {
-        let group_ordering = match group_criteria {
-            GroupCriteria::None => Ordering::Equal,
-            GroupCriteria::Type => b.is_dir.cmp(&a.is_dir),
-            GroupCriteria::MimeType => match (a.is_dir, b.is_dir) {
-                (true, false) => Ordering::Less,
-                (false, true) => Ordering::Greater,
-                _ => a.mime_group.cmp(&b.mime_group),
-            },
-        };
+    if group_ordering != Ordering::Equal {
+        return group_ordering;
+    }
 
-        if group_ordering != Ordering::Equal {
-            return group_ordering;
+    let sort_ordering = match sort_criteria {
+        SortCriteria::Name => a.display_name.cmp(&b.display_name),
+        SortCriteria::Size => {
+            let a_size = if a.is_dir { 0 } else { a.size.unwrap_or(0) };
+            let b_size = if b.is_dir { 0 } else { b.size.unwrap_or(0) };
+            if a.is_dir == b.is_dir && a_size == b_size {
+                a.display_name.cmp(&b.display_name)
+            } else {
+                a_size.cmp(&b_size)
+            }
         }
-
-        let sort_ordering = match sort_criteria {
-            SortCriteria::Name => a.display_name.cmp(&b.display_name),
-            SortCriteria::Size => {
-                let a_size = if a.is_dir { 0 } else { a.size.unwrap_or(0) };
-                let b_size = if b.is_dir { 0 } else { b.size.unwrap_or(0) };
-                if a.is_dir == b.is_dir && a_size == b_size {
-                    a.display_name.cmp(&b.display_name)
-                } else {
-                    a_size.cmp(&b_size)
-                }
+        SortCriteria::ModifiedDate => {
+            let a_mod = a.modified.unwrap_or(SystemTime::UNIX_EPOCH);
+            let b_mod = b.modified.unwrap_or(SystemTime::UNIX_EPOCH);
+            if a_mod == b_mod {
+                a.display_name.cmp(&b.display_name)
+            } else {
+                a_mod.cmp(&b_mod)
             }
-            SortCriteria::ModifiedDate => {
-                let a_mod = a.modified.unwrap_or(SystemTime::UNIX_EPOCH);
-                let b_mod = b.modified.unwrap_or(SystemTime::UNIX_EPOCH);
-                if a_mod == b_mod {
+        }
+        SortCriteria::Type => {
+            if a.is_dir != b.is_dir {
+                b.is_dir.cmp(&a.is_dir)
+            } else {
+                let a_ext = a.path.extension().unwrap_or_default();
+                let b_ext = b.path.extension().unwrap_or_default();
+                if a_ext == b_ext {
                     a.display_name.cmp(&b.display_name)
                 } else {
-                    a_mod.cmp(&b_mod)
-                }
-            }
-            SortCriteria::Type => {
-                if a.is_dir != b.is_dir {
-                    b.is_dir.cmp(&a.is_dir)
-                } else {
-                    let a_ext = a.path.extension().unwrap_or_default();
-                    let b_ext = b.path.extension().unwrap_or_default();
-                    if a_ext == b_ext {
-                        a.display_name.cmp(&b.display_name)
-                    } else {
-                        a_ext.cmp(b_ext)
-                    }
+                    a_ext.cmp(b_ext)
                 }
             }
-        };
+        }
+    };
+
+    match sort_order {
+        SortOrder::Ascending => sort_ordering,
+        SortOrder::Descending => sort_ordering.reverse(),
+    }
+}
 
-        match sort_order {
-            SortOrder::Ascending => sort_ordering,
-            SortOrder::Descending => sort_ordering.reverse(),
+pub async fn read_dir(
+    path: PathBuf,
+    show_hidden: bool,
+    sort_criteria: SortCriteria,
+    sort_order: SortOrder,
+    group_criteria: GroupCriteria,
+) -> Result<Vec<DirEntry>, String> {
+    dirs::home_dir().ok_or_else(|| "Could not find home directory".to_string())?;
+    let is_app_dir = is_applications_dir(&path);
+
+    let read_dir_iter = fs::read_dir(&path)
+        .map_err(|e| format!("Failed to read directory {}: {}", path.display(), e))?;
+
+    let mut entries_futures = Vec::new();
+
+    for entry in read_dir_iter.flatten() {
+        entries_futures.push(tokio::spawn(load_entry(
+            entry.path(),
+            show_hidden,
+            is_app_dir,
+        )));
+    }
+
+    let mut entries: Vec<DirEntry> = Vec::new();
+    for future in entries_futures {
+        if let Ok(Some(entry)) = future.await {
+            entries.push(entry);
         }
-    });
+    }
+
+    entries.sort_by(|a, b| compare_entries(a, b, sort_criteria, sort_order, group_criteria));
 
     Ok(entries)
 }
 
+// Reloads the given items of a folder after they changed on disk. Items that
+// are gone (or hidden) come back as None.
+pub async fn reload_entries(
+    paths: Vec<PathBuf>,
+    show_hidden: bool,
+) -> Vec<(PathBuf, Option<DirEntry>)> {
+    let mut reloaded = Vec::with_capacity(paths.len());
+    for path in paths {
+        let is_app_dir = path.parent().is_some_and(is_applications_dir);
+        let entry = load_entry(path.clone(), show_hidden, is_app_dir).await;
+        reloaded.push((path, entry));
+    }
+    reloaded
+}
+
 pub async fn delete_item(path: PathBuf) -> Result<(), String> {
     println!("Attempting to delete: {}", path.display());
     let result = if path.is_dir() {
@@ -551,7 +590,162 @@ pub async fn delete_item(path: PathBuf) -> Result<(), String> {
     result.map_err(|e| format!("Failed to delete '{}': {}", path.display(), e))
 }
 
-pub async fn copy_item(source: PathBuf, destination_dir: PathBuf) -> Result<(), String> {
+// Moves an existing `destination` aside, runs `operation`, and then either drops
+// the old item (success) or puts it back (failure), so a failed replace never
+// loses the original.
+fn replace_existing<F>(destination: &Path, operation: F) -> Result<(), String>
+where
+    F: FnOnce() -> Result<(), String>,
+{
+    let file_name = destination
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let backup = (1..)
+        .map(|n| destination.with_file_name(format!(".{}.replaced-{}", file_name, n)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("unbounded counter always finds a free name");
+
+    fs::rename(destination, &backup).map_err(|e| {
+        format!(
+            "Failed to move existing '{}' aside: {}",
+            destination.display(),
+            e
+        )
+    })?;
+
+    match operation() {
+        Ok(()) => {
+            let removed = if backup.is_dir() && !backup.is_symlink() {
+                fs::remove_dir_all(&backup)
+            } else {
+                fs::remove_file(&backup)
+            };
+            if let Err(e) = removed {
+                eprintln!("Failed to remove replaced item {}: {}", backup.display(), e);
+            }
+            Ok(())
+        }
+        Err(e) => {
+            if let Err(restore_err) = fs::rename(&backup, destination) {
+                eprintln!(
+                    "Failed to restore {} after failed replace: {}",
+                    destination.display(),
+                    restore_err
+                );
+            }
+            Err(e)
+        }
+    }
+}
+
+// Progress reported while copying or moving. The callback runs after every
+// chunk; returning an error from it aborts the transfer.
+#[derive(Debug, Clone, Copy)]
+pub enum CopyProgress {
+    Bytes(u64),
+    Files(u64),
+}
+
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
+// Gives `destination` the permissions and timestamps of the source
+fn copy_metadata(source_meta: &fs::Metadata, destination: &Path) -> io::Result<()> {
+    let times = fs::FileTimes::new()
+        .set_accessed(source_meta.accessed()?)
+        .set_modified(source_meta.modified()?);
+    File::open(destination)?.set_times(times)?;
+    fs::set_permissions(destination, source_meta.permissions())
+}
+
+fn copy_file(
+    source: &Path,
+    destination: &Path,
+    on_progress: &mut dyn FnMut(CopyProgress) -> Result<(), String>,
+) -> Result<(), String> {
+    let io_error = |e: io::Error| {
+        format!(
+            "Failed to copy '{}' to '{}': {}",
+            source.display(),
+            destination.display(),
+            e
+        )
+    };
+
+    let mut reader = File::open(source).map_err(io_error)?;
+    let mut writer = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(destination)
+        .map_err(io_error)?;
+    let mut buffer = vec![0; COPY_BUFFER_SIZE];
+    loop {
+        let read = reader.read(&mut buffer).map_err(io_error)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read]).map_err(io_error)?;
+        on_progress(CopyProgress::Bytes(read as u64))?;
+    }
+    drop(writer);
+
+    let metadata = reader.metadata().map_err(io_error)?;
+    copy_metadata(&metadata, destination).map_err(io_error)?;
+    on_progress(CopyProgress::Files(1))
+}
+
+// Copies a file, symlink or directory tree to exactly `destination`, which must
+// not exist. Permissions and timestamps are kept.
+pub fn copy_path(
+    source: &Path,
+    destination: &Path,
+    on_progress: &mut dyn FnMut(CopyProgress) -> Result<(), String>,
+) -> Result<(), String> {
+    let io_error = |e: io::Error| {
+        format!(
+            "Failed to copy '{}' to '{}': {}",
+            source.display(),
+            destination.display(),
+            e
+        )
+    };
+
+    let metadata = fs::symlink_metadata(source).map_err(io_error)?;
+    if metadata.is_symlink() {
+        let target = fs::read_link(source).map_err(io_error)?;
+        symlink(target, destination).map_err(io_error)?;
+        on_progress(CopyProgress::Files(1))
+    } else if metadata.is_dir() {
+        fs::create_dir(destination).map_err(io_error)?;
+        for child in fs::read_dir(source).map_err(io_error)? {
+            let child = child.map_err(io_error)?;
+            copy_path(
+                &child.path(),
+                &destination.join(child.file_name()),
+                on_progress,
+            )?;
+        }
+        // Applied last, so a read-only folder can still be filled
+        copy_metadata(&metadata, destination).map_err(io_error)
+    } else {
+        copy_file(source, destination, on_progress)
+    }
+}
+
+// Best-effort removal of a partially copied item
+fn remove_partial(path: &Path) {
+    let removed = match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(_) => return,
+    };
+    if let Err(e) = removed {
+        eprintln!("Failed to clean up {}: {}", path.display(), e);
+    }
+}
+
+fn check_transfer(source: &Path, destination_dir: &Path) -> Result<PathBuf, String> {
     if !source.exists() {
         return Err(format!(
             "Source path '{}' does not exist.",
@@ -564,11 +758,141 @@ pub async fn copy_item(source: PathBuf, destination_dir: PathBuf) -> Result<(),
             destination_dir.display()
         ));
     }
+    // A folder copied into itself would keep copying the copy being written
+    if is_real_dir(source) {
+        let resolve = |path: &Path| {
+            path.canonicalize()
+                .map_err(|e| format!("Failed to resolve '{}': {}", path.display(), e))
+        };
+        if resolve(destination_dir)?.starts_with(resolve(source)?) {
+            return Err(format!(
+                "Cannot put folder '{}' inside itself.",
+                source.display()
+            ));
+        }
+    }
 
     let item_name = source
         .file_name()
         .ok_or_else(|| "Could not get file/folder name from source.".to_string())?;
-    let destination_path = destination_dir.join(item_name);
+    Ok(destination_dir.join(item_name))
+}
+
+// How to handle an item whose destination already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictChoice {
+    Replace,
+    Skip,
+    KeepBoth,
+    // Folders only: combine the contents, resolving conflicts inside again
+    Merge,
+}
+
+// Asked with (source, existing destination) whenever a destination is taken
+pub type ConflictHandler<'a> = dyn FnMut(&Path, &Path) -> Result<ConflictChoice, String> + 'a;
+
+// (source, destination) pairs that were transferred and can be reversed.
+// Replaced items are left out, since the old destination is gone for good.
+pub type Transferred = Vec<(PathBuf, PathBuf)>;
+
+// Why a transfer stopped, with what was transferred before it did
+pub type TransferError = (Transferred, String);
+
+// Fails an item of which nothing was transferred
+fn untransferred(error: String) -> TransferError {
+    (vec![], error)
+}
+
+fn is_real_dir(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|meta| meta.is_dir())
+}
+
+fn merge_error(source: &Path, destination: &Path) -> String {
+    format!(
+        "Cannot merge '{}' into '{}': only folders can be merged.",
+        source.display(),
+        destination.display()
+    )
+}
+
+fn copy_resolving(
+    source: &Path,
+    destination: &Path,
+    on_conflict: &mut ConflictHandler,
+    on_progress: &mut dyn FnMut(CopyProgress) -> Result<(), String>,
+) -> Result<Transferred, TransferError> {
+    let mut copy_to = |destination: &Path| {
+        copy_path(source, destination, on_progress).inspect_err(|_| {
+            remove_partial(destination);
+        })
+    };
+
+    if fs::symlink_metadata(destination).is_err() {
+        copy_to(destination).map_err(untransferred)?;
+        return Ok(vec![(source.to_path_buf(), destination.to_path_buf())]);
+    }
+    // Pasting into the folder the item came from makes a duplicate
+    let choice = if destination == source {
+        ConflictChoice::KeepBoth
+    } else {
+        on_conflict(source, destination).map_err(untransferred)?
+    };
+
+    match choice {
+        ConflictChoice::Skip => {
+            println!("Skipping existing {}", destination.display());
+            Ok(vec![])
+        }
+        ConflictChoice::KeepBoth => {
+            let unique = unique_destination(destination);
+            copy_to(&unique).map_err(untransferred)?;
+            Ok(vec![(source.to_path_buf(), unique)])
+        }
+        ConflictChoice::Replace => {
+            replace_existing(destination, || copy_to(destination)).map_err(untransferred)?;
+            Ok(vec![])
+        }
+        ConflictChoice::Merge => {
+            if !is_real_dir(source) || !is_real_dir(destination) {
+                return Err(untransferred(merge_error(source, destination)));
+            }
+            let read_error = |e: io::Error| format!("Failed to read '{}': {}", source.display(), e);
+            let mut transferred = vec![];
+            let children = fs::read_dir(source).map_err(|e| untransferred(read_error(e)))?;
+            for child in children {
+                // The children done so far stay transferred when one fails
+                let child = match child {
+                    Ok(child) => child,
+                    Err(e) => return Err((transferred, read_error(e))),
+                };
+                match copy_resolving(
+                    &child.path(),
+                    &destination.join(child.file_name()),
+                    on_conflict,
+                    on_progress,
+                ) {
+                    Ok(done) => transferred.extend(done),
+                    Err((done, e)) => {
+                        transferred.extend(done);
+                        return Err((transferred, e));
+                    }
+                }
+            }
+            Ok(transferred)
+        }
+    }
+}
+
+// Copies `source` into `destination_dir`, asking `on_conflict` whenever a
+// destination already exists. A failed or aborted copy of an item leaves
+// nothing behind.
+pub fn copy_item(
+    source: &Path,
+    destination_dir: &Path,
+    on_conflict: &mut ConflictHandler,
+    on_progress: &mut dyn FnMut(CopyProgress) -> Result<(), String>,
+) -> Result<Transferred, TransferError> {
+    let destination_path = check_transfer(source, destination_dir).map_err(untransferred)?;
 
     println!(
         "Copying {} to {}",
@@ -576,53 +900,135 @@ pub async fn copy_item(source: PathBuf, destination_dir: PathBuf) -> Result<(),
         destination_path.display()
     );
 
-    let options = CopyOptions {
-        overwrite: false,
-        skip_exist: true,
-        buffer_size: 64000,
-        copy_inside: false,
-        content_only: false,
-        depth: 0,
-    };
-
-    let items_to_copy = vec![&source];
+    copy_resolving(source, &destination_path, on_conflict, on_progress)
+}
 
-    match fs_extra::copy_items(&items_to_copy, &destination_dir, &options) {
-        Ok(_) => Ok(()),
+// Moves `source` to `destination`, reporting the moved bytes and files.
+// Across filesystems a rename is impossible, so the item is copied instead.
+pub fn move_path(
+    source: &Path,
+    destination: &Path,
+    on_progress: &mut dyn FnMut(CopyProgress) -> Result<(), String>,
+) -> Result<(), String> {
+    match fs::rename(source, destination) {
+        Ok(()) => {
+            let (bytes, files) = tree_totals(destination);
+            on_progress(CopyProgress::Bytes(bytes))?;
+            on_progress(CopyProgress::Files(files))
+        }
+        Err(e) if e.raw_os_error() == Some(libc::EXDEV) => {
+            println!(
+                "{} is on another filesystem, copying before removing the source",
+                destination.display()
+            );
+            move_by_copying(source, destination, on_progress)
+        }
         Err(e) => Err(format!(
-            "Failed to copy '{}' to '{}': {}",
+            "Failed to move '{}' to '{}': {}",
             source.display(),
-            destination_dir.display(),
+            destination.display(),
             e
         )),
     }
 }
 
-pub async fn move_item(source: PathBuf, destination_dir: PathBuf) -> Result<(), String> {
-    if !source.exists() {
-        return Err(format!(
-            "Source path '{}' does not exist.",
-            source.display()
-        ));
+// Copies the item with its permissions and timestamps and removes the source
+// only once the copy is complete
+fn move_by_copying(
+    source: &Path,
+    destination: &Path,
+    on_progress: &mut dyn FnMut(CopyProgress) -> Result<(), String>,
+) -> Result<(), String> {
+    copy_path(source, destination, on_progress).inspect_err(|_| {
+        remove_partial(destination);
+    })?;
+    let removed = if is_real_dir(source) {
+        fs::remove_dir_all(source)
+    } else {
+        fs::remove_file(source)
+    };
+    removed.map_err(|e| {
+        format!(
+            "Copied '{}' to '{}' but failed to remove the original: {}",
+            source.display(),
+            destination.display(),
+            e
+        )
+    })
+}
+
+fn move_resolving(
+    source: &Path,
+    destination: &Path,
+    on_conflict: &mut ConflictHandler,
+    on_progress: &mut dyn FnMut(CopyProgress) -> Result<(), String>,
+) -> Result<Transferred, TransferError> {
+    if fs::symlink_metadata(destination).is_err() {
+        move_path(source, destination, on_progress).map_err(untransferred)?;
+        return Ok(vec![(source.to_path_buf(), destination.to_path_buf())]);
     }
-    if !destination_dir.is_dir() {
-        return Err(format!(
-            "Destination path '{}' is not a valid directory.",
-            destination_dir.display()
-        ));
+    // Moving an item into the folder it is already in changes nothing
+    if destination == source {
+        return Ok(vec![]);
     }
 
-    let item_name = source
-        .file_name()
-        .ok_or_else(|| "Could not get file/folder name from source.".to_string())?;
-    let destination_path = destination_dir.join(item_name);
-
-    if destination_path.exists() {
-        return Err(format!(
-            "Destination '{}' already exists. Cannot move.",
-            destination_path.display()
-        ));
+    match on_conflict(source, destination).map_err(untransferred)? {
+        ConflictChoice::Skip => {
+            println!("Skipping existing {}", destination.display());
+            Ok(vec![])
+        }
+        ConflictChoice::KeepBoth => {
+            let unique = unique_destination(destination);
+            move_path(source, &unique, on_progress).map_err(untransferred)?;
+            Ok(vec![(source.to_path_buf(), unique)])
+        }
+        ConflictChoice::Replace => {
+            replace_existing(destination, || move_path(source, destination, on_progress))
+                .map_err(untransferred)?;
+            Ok(vec![])
+        }
+        ConflictChoice::Merge => {
+            if !is_real_dir(source) || !is_real_dir(destination) {
+                return Err(untransferred(merge_error(source, destination)));
+            }
+            let read_error = |e: io::Error| format!("Failed to read '{}': {}", source.display(), e);
+            let mut transferred = vec![];
+            let children = fs::read_dir(source).map_err(|e| untransferred(read_error(e)))?;
+            for child in children {
+                // The children done so far stay transferred when one fails
+                let child = match child {
+                    Ok(child) => child,
+                    Err(e) => return Err((transferred, read_error(e))),
+                };
+                match move_resolving(
+                    &child.path(),
+                    &destination.join(child.file_name()),
+                    on_conflict,
+                    on_progress,
+                ) {
+                    Ok(done) => transferred.extend(done),
+                    Err((done, e)) => {
+                        transferred.extend(done);
+                        return Err((transferred, e));
+                    }
+                }
+            }
+            // Skipped items stay behind, in which case the source folder is kept
+            let _ = fs::remove_dir(source);
+            Ok(transferred)
+        }
     }
+}
+
+// Moves `source` into `destination_dir`, asking `on_conflict` whenever a
+// destination already exists
+pub fn move_item(
+    source: &Path,
+    destination_dir: &Path,
+    on_conflict: &mut ConflictHandler,
+    on_progress: &mut dyn FnMut(CopyProgress) -> Result<(), String>,
+) -> Result<Transferred, TransferError> {
+    let destination_path = check_transfer(source, destination_dir).map_err(untransferred)?;
 
     println!(
         "Moving {} to {}",
@@ -630,18 +1036,40 @@ pub async fn move_item(source: PathBuf, destination_dir: PathBuf) -> Result<(),
         destination_path.display()
     );
 
-    match fs::rename(&source, &destination_path) {
-        Ok(_) => Ok(()),
-        Err(e) => Err(format!(
-            "Failed to move '{}' to '{}': {}. (Might be cross-device operation?)",
-            source.display(),
-            destination_path.display(),
-            e
-        )),
+    move_resolving(source, &destination_path, on_conflict, on_progress)
+}
+
+// Returns `path` unchanged if nothing exists there, otherwise the first free
+// "name (n).ext" sibling. Folders keep their whole name as the stem.
+pub fn unique_destination(path: &Path) -> PathBuf {
+    if fs::symlink_metadata(path).is_err() {
+        return path.to_path_buf();
     }
+
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let file_name = path.file_name().unwrap_or_default();
+    let is_dir = path.is_dir();
+    let (stem, extension) = match (is_dir, path.file_stem(), path.extension()) {
+        (false, Some(stem), Some(ext)) => (stem, Some(ext)),
+        _ => (file_name, None),
+    };
+
+    (1..)
+        .map(|n| {
+            // Built from the bytes of the name, which need not be UTF-8
+            let mut name = stem.to_os_string();
+            name.push(format!(" ({})", n));
+            if let Some(extension) = extension {
+                name.push(".");
+                name.push(extension);
+            }
+            parent.join(name)
+        })
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("unbounded counter always finds a free name")
 }
 
-pub async fn rename_item(path: PathBuf, new_name: String) -> Result<(), String> {
+pub async fn rename_item(path: PathBuf, new_name: String) -> Result<PathBuf, String> {
     if !path.exists() {
         return Err(format!("Path '{}' does not exist.", path.display()));
     }
@@ -666,7 +1094,7 @@ pub async fn rename_item(path: PathBuf, new_name: String) -> Result<(), String>
         println!("Renaming {} to {}", path.display(), new_path.display());
 
         match fs::rename(&path, &new_path) {
-            Ok(_) => Ok(()),
+            Ok(_) => Ok(new_path),
             Err(e) => Err(format!(
                 "Failed to rename '{}' to '{}': {}",
                 path.display(),
@@ -679,6 +1107,95 @@ pub async fn rename_item(path: PathBuf, new_name: String) -> Result<(), String>
     }
 }
 
+// Creates an empty folder or file called `name` in `dir`, numbered like
+// "name (1)" when the name is taken, and returns its path
+pub async fn create_item(dir: PathBuf, name: String, is_dir: bool) -> Result<PathBuf, String> {
+    let path = unique_destination(&dir.join(name));
+    let result = if is_dir {
+        fs::create_dir(&path)
+    } else {
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map(|_| ())
+    };
+    result.map_err(|e| format!("Failed to create '{}': {}", path.display(), e))?;
+    println!("Created {}", path.display());
+    Ok(path)
+}
+
+// The files in the XDG templates folder (~/Templates), by name
+pub async fn list_templates() -> Vec<PathBuf> {
+    let Some(templates_dir) = dirs::template_dir() else {
+        return vec![];
+    };
+    let Ok(read_dir) = fs::read_dir(&templates_dir) else {
+        return vec![];
+    };
+    let mut templates: Vec<PathBuf> = read_dir
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && !path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        })
+        .collect();
+    templates.sort();
+    templates
+}
+
+// Copies `template` into `dir` under the template's name, numbered like
+// "name (1).ext" when the name is taken, and returns the new file
+pub async fn create_from_template(dir: PathBuf, template: PathBuf) -> Result<PathBuf, String> {
+    let file_name = template
+        .file_name()
+        .ok_or_else(|| format!("Invalid template '{}'", template.display()))?;
+    let path = unique_destination(&dir.join(file_name));
+    fs::copy(&template, &path).map_err(|e| {
+        format!(
+            "Failed to create '{}' from '{}': {}",
+            path.display(),
+            template.display(),
+            e
+        )
+    })?;
+    println!("Created {} from {}", path.display(), template.display());
+    Ok(path)
+}
+
+// Terminals tried in order after $TERMINAL
+const TERMINALS: [&str; 8] = [
+    "x-terminal-emulator",
+    "gnome-terminal",
+    "konsole",
+    "xfce4-terminal",
+    "kitty",
+    "alacritty",
+    "foot",
+    "xterm",
+];
+
+// Starts a terminal in `dir`: $TERMINAL if set, otherwise the first installed
+// one of a few common terminals
+pub async fn open_terminal(dir: PathBuf) -> Result<(), String> {
+    let preferred = std::env::var("TERMINAL").ok().filter(|t| !t.is_empty());
+    for terminal in preferred.iter().map(String::as_str).chain(TERMINALS) {
+        match StdCommand::new(terminal).current_dir(&dir).spawn() {
+            Ok(mut child) => {
+                println!("Opened {} in {}", terminal, dir.display());
+                std::thread::spawn(move || child.wait());
+                return Ok(());
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("Failed to start {}: {}", terminal, e)),
+        }
+    }
+    Err("No terminal emulator found; set $TERMINAL".to_string())
+}
+
 pub async fn setup_applications_directory() -> Result<(), String> {
     let home_dir = dirs::home_dir().ok_or_else(|| "Could not find home directory".to_string())?;
     let app_dir = home_dir.join("Applications");
@@ -749,6 +1266,22 @@ pub async fn setup_applications_directory() -> Result<(), String> {
     Ok(())
 }
 
+// Total size and number of non-directory items in a tree, not following symlinks
+pub fn tree_totals(path: &Path) -> (u64, u64) {
+    WalkDir::new(path)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| !m.is_dir())
+        .fold((0, 0), |(bytes, files), m| (bytes + m.len(), files + 1))
+}
+
+// Total size of a file or directory tree, not following symlinks
+pub fn item_size(path: &Path) -> u64 {
+    tree_totals(path).0
+}
+
 pub fn format_size(size: Option<u64>) -> String {
     match size {
         Some(s) => {
@@ -775,3 +1308,419 @@ pub fn format_modified(modified: Option<SystemTime>) -> String {
         None => "-".to_string(),
     }
 }
+
+// "drwxr-xr-x" style permissions from a st_mode value
+pub fn format_permissions(mode: Option<u32>) -> String {
+    let Some(mode) = mode else {
+        return "-".to_string();
+    };
+    let file_type = match mode & libc::S_IFMT {
+        libc::S_IFDIR => 'd',
+        libc::S_IFLNK => 'l',
+        libc::S_IFIFO => 'p',
+        libc::S_IFSOCK => 's',
+        libc::S_IFCHR => 'c',
+        libc::S_IFBLK => 'b',
+        _ => '-',
+    };
+    let bits = [
+        (libc::S_IRUSR, 'r'),
+        (libc::S_IWUSR, 'w'),
+        (libc::S_IXUSR, 'x'),
+        (libc::S_IRGRP, 'r'),
+        (libc::S_IWGRP, 'w'),
+        (libc::S_IXGRP, 'x'),
+        (libc::S_IROTH, 'r'),
+        (libc::S_IWOTH, 'w'),
+        (libc::S_IXOTH, 'x'),
+    ];
+    std::iter::once(file_type)
+        .chain(
+            bits.iter()
+                .map(|&(bit, c)| if mode & bit != 0 { c } else { '-' }),
+        )
+        .collect()
+}
+
+// The user name for a uid, or the number if it has no passwd entry
+pub fn format_owner(uid: Option<u32>) -> String {
+    let Some(uid) = uid else {
+        return "-".to_string();
+    };
+    OWNER_NAMES
+        .entry(uid)
+        .or_insert_with(|| {
+            let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+            let mut result: *mut libc::passwd = std::ptr::null_mut();
+            let mut buffer = vec![0 as libc::c_char; 1024];
+            let status = unsafe {
+                libc::getpwuid_r(
+                    uid,
+                    &mut passwd,
+                    buffer.as_mut_ptr(),
+                    buffer.len(),
+                    &mut result,
+                )
+            };
+            if status == 0 && !result.is_null() {
+                unsafe { CStr::from_ptr(passwd.pw_name) }
+                    .to_string_lossy()
+                    .into_owned()
+            } else {
+                uid.to_string()
+            }
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_dir::TempDir;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    // Every item under `dir` as its relative path, with the content of files
+    fn tree(dir: &Path) -> Vec<(String, Option<String>)> {
+        let mut items: Vec<_> = WalkDir::new(dir)
+            .min_depth(1)
+            .into_iter()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                let relative = entry.path().strip_prefix(dir).unwrap();
+                let content = entry
+                    .file_type()
+                    .is_file()
+                    .then(|| fs::read_to_string(entry.path()).unwrap());
+                (relative.to_string_lossy().into_owned(), content)
+            })
+            .collect();
+        items.sort();
+        items
+    }
+
+    fn file(path: &str, content: &str) -> (String, Option<String>) {
+        (path.to_string(), Some(content.to_string()))
+    }
+
+    fn folder(path: &str) -> (String, Option<String>) {
+        (path.to_string(), None)
+    }
+
+    fn no_progress(_: CopyProgress) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn never_asked(source: &Path, _: &Path) -> Result<ConflictChoice, String> {
+        panic!("asked about {}", source.display())
+    }
+
+    fn sorted(mut transferred: Transferred) -> Transferred {
+        transferred.sort();
+        transferred
+    }
+
+    #[test]
+    fn replacing_restores_the_original_when_the_copy_fails() {
+        let temp = TempDir::new().unwrap();
+        let (source, target) = (temp.child("source"), temp.child("target"));
+        write(&source.join("a.txt"), "new");
+        write(&target.join("a.txt"), "old");
+
+        let error = replace_existing(&target.join("a.txt"), || Err("failed".to_string()));
+        assert_eq!(error.unwrap_err(), "failed");
+        assert_eq!(tree(&target), [file("a.txt", "old")]);
+
+        // Aborted halfway through the copy
+        let error = copy_item(
+            &source.join("a.txt"),
+            &target,
+            &mut |_, _| Ok(ConflictChoice::Replace),
+            &mut |_| Err("cancelled".to_string()),
+        );
+        assert_eq!(error.unwrap_err(), (vec![], "cancelled".to_string()));
+        assert_eq!(tree(&target), [file("a.txt", "old")]);
+
+        // Replaced items cannot be undone, so they are not returned
+        let transferred = copy_item(
+            &source.join("a.txt"),
+            &target,
+            &mut |_, _| Ok(ConflictChoice::Replace),
+            &mut no_progress,
+        );
+        assert_eq!(transferred.unwrap(), []);
+        assert_eq!(tree(&target), [file("a.txt", "new")]);
+        assert_eq!(tree(&source), [file("a.txt", "new")]);
+    }
+
+    #[test]
+    fn merging_resolves_conflicts_inside_again() {
+        let temp = TempDir::new().unwrap();
+        let (source, target) = (temp.child("source"), temp.child("target"));
+        for root in [&source, &target] {
+            write(&root.join("f/same.txt"), root.to_str().unwrap());
+            write(&root.join("f/sub/own.txt"), root.to_str().unwrap());
+        }
+        write(&source.join("f/new.txt"), "new");
+        write(&source.join("f/sub/deep.txt"), "deep");
+        let source_text = source.to_str().unwrap();
+        let target_text = target.to_str().unwrap();
+
+        let mut asked = vec![];
+        let mut on_conflict = |source: &Path, _: &Path| {
+            asked.push(source.file_name().unwrap().to_string_lossy().into_owned());
+            Ok(if is_real_dir(source) {
+                ConflictChoice::Merge
+            } else {
+                ConflictChoice::Skip
+            })
+        };
+        let transferred = copy_item(
+            &source.join("f"),
+            &target,
+            &mut on_conflict,
+            &mut no_progress,
+        );
+        assert_eq!(
+            sorted(transferred.unwrap()),
+            [
+                (source.join("f/new.txt"), target.join("f/new.txt")),
+                (source.join("f/sub/deep.txt"), target.join("f/sub/deep.txt")),
+            ]
+        );
+        asked.sort();
+        assert_eq!(asked, ["f", "own.txt", "same.txt", "sub"]);
+        assert_eq!(
+            tree(&target),
+            [
+                folder("f"),
+                file("f/new.txt", "new"),
+                file("f/same.txt", target_text),
+                folder("f/sub"),
+                file("f/sub/deep.txt", "deep"),
+                file("f/sub/own.txt", target_text),
+            ]
+        );
+
+        // Moving keeps the skipped items, and the folders holding them, behind
+        fs::remove_file(target.join("f/new.txt")).unwrap();
+        fs::remove_file(target.join("f/sub/deep.txt")).unwrap();
+        fs::remove_file(target.join("f/sub/own.txt")).unwrap();
+        let transferred = move_item(
+            &source.join("f"),
+            &target,
+            &mut |source, _| {
+                Ok(match source.file_name().unwrap().to_str() {
+                    Some("same.txt") => ConflictChoice::Skip,
+                    _ => ConflictChoice::Merge,
+                })
+            },
+            &mut no_progress,
+        );
+        assert_eq!(
+            sorted(transferred.unwrap()),
+            [
+                (source.join("f/new.txt"), target.join("f/new.txt")),
+                (source.join("f/sub/deep.txt"), target.join("f/sub/deep.txt")),
+                (source.join("f/sub/own.txt"), target.join("f/sub/own.txt")),
+            ]
+        );
+        assert_eq!(
+            tree(&source),
+            [folder("f"), file("f/same.txt", source_text)]
+        );
+        assert_eq!(
+            tree(&target),
+            [
+                folder("f"),
+                file("f/new.txt", "new"),
+                file("f/same.txt", target_text),
+                folder("f/sub"),
+                file("f/sub/deep.txt", "deep"),
+                file("f/sub/own.txt", source_text),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_failed_merge_returns_what_it_transferred() {
+        let temp = TempDir::new().unwrap();
+        let (source, target) = (temp.child("source"), temp.child("target"));
+        for name in ["a.txt", "b.txt", "c.txt", "taken.txt"] {
+            write(&source.join("f").join(name), name);
+        }
+        write(&target.join("f/taken.txt"), "old");
+
+        let error = move_item(
+            &source.join("f"),
+            &target,
+            &mut |source, _| match is_real_dir(source) {
+                true => Ok(ConflictChoice::Merge),
+                false => Err("cancelled".to_string()),
+            },
+            &mut no_progress,
+        );
+        let (transferred, error) = error.unwrap_err();
+        assert_eq!(error, "cancelled");
+        // Whichever items came before the conflict were moved, and only those
+        for (from, to) in &transferred {
+            assert!(!from.exists() && to.exists(), "{}", to.display());
+        }
+        let left = tree(&source.join("f")).len();
+        assert_eq!(transferred.len() + left, 4);
+        assert_eq!(
+            fs::read_to_string(target.join("f/taken.txt")).unwrap(),
+            "old"
+        );
+    }
+
+    #[test]
+    fn keeping_both_numbers_the_new_name() {
+        let temp = TempDir::new().unwrap();
+        let (source, target) = (temp.child("source"), temp.child("target"));
+        write(&source.join("a.txt"), "new");
+        write(&source.join("photos.2024/p.jpg"), "photo");
+        write(&target.join("a.txt"), "old");
+        write(&target.join("photos.2024/p.jpg"), "old photo");
+        let mut keep_both = |_: &Path, _: &Path| Ok(ConflictChoice::KeepBoth);
+
+        let transferred = copy_item(
+            &source.join("a.txt"),
+            &target,
+            &mut keep_both,
+            &mut no_progress,
+        );
+        assert_eq!(
+            transferred.unwrap(),
+            [(source.join("a.txt"), target.join("a (1).txt"))]
+        );
+        // Folders are numbered after their whole name
+        let transferred = move_item(
+            &source.join("photos.2024"),
+            &target,
+            &mut keep_both,
+            &mut no_progress,
+        );
+        assert_eq!(
+            transferred.unwrap(),
+            [(source.join("photos.2024"), target.join("photos.2024 (1)"))]
+        );
+        assert_eq!(tree(&source), [file("a.txt", "new")]);
+        assert_eq!(
+            tree(&target),
+            [
+                file("a (1).txt", "new"),
+                file("a.txt", "old"),
+                folder("photos.2024"),
+                folder("photos.2024 (1)"),
+                file("photos.2024 (1)/p.jpg", "photo"),
+                file("photos.2024/p.jpg", "old photo"),
+            ]
+        );
+    }
+
+    #[test]
+    fn pasting_into_the_same_folder() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        write(&dir.join("a.txt"), "text");
+        write(&dir.join("a (1).txt"), "taken");
+
+        // A copy becomes a duplicate without asking
+        let transferred = copy_item(&dir.join("a.txt"), dir, &mut never_asked, &mut no_progress);
+        assert_eq!(
+            transferred.unwrap(),
+            [(dir.join("a.txt"), dir.join("a (2).txt"))]
+        );
+        // A move leaves the item where it is
+        let transferred = move_item(&dir.join("a.txt"), dir, &mut never_asked, &mut no_progress);
+        assert_eq!(transferred.unwrap(), []);
+        assert_eq!(
+            tree(dir),
+            [
+                file("a (1).txt", "taken"),
+                file("a (2).txt", "text"),
+                file("a.txt", "text"),
+            ]
+        );
+    }
+
+    #[test]
+    fn folders_are_not_put_inside_themselves() {
+        let temp = TempDir::new().unwrap();
+        let folder_path = temp.child("f");
+        write(&folder_path.join("sub/a.txt"), "text");
+        let expected = format!(
+            "Cannot put folder '{}' inside itself.",
+            folder_path.display()
+        );
+
+        for destination in [folder_path.clone(), folder_path.join("sub")] {
+            let error = copy_item(
+                &folder_path,
+                &destination,
+                &mut never_asked,
+                &mut no_progress,
+            );
+            assert_eq!(error.unwrap_err(), (vec![], expected.clone()));
+            let error = move_item(
+                &folder_path,
+                &destination,
+                &mut never_asked,
+                &mut no_progress,
+            );
+            assert_eq!(error.unwrap_err(), (vec![], expected.clone()));
+        }
+        assert_eq!(
+            tree(temp.path()),
+            [folder("f"), folder("f/sub"), file("f/sub/a.txt", "text")]
+        );
+    }
+
+    // What `move_path` falls back to when renaming fails with EXDEV
+    #[test]
+    fn moving_across_filesystems_copies_before_removing() {
+        let temp = TempDir::new().unwrap();
+        let (source, destination) = (temp.child("f"), temp.child("moved"));
+        write(&source.join("a.txt"), "a");
+        write(&source.join("sub/b.txt"), "b");
+
+        // An aborted copy leaves the source as it was and nothing behind
+        let result = move_by_copying(&source, &destination, &mut |progress| match progress {
+            CopyProgress::Files(_) => Err("cancelled".to_string()),
+            CopyProgress::Bytes(_) => Ok(()),
+        });
+        assert_eq!(result.unwrap_err(), "cancelled");
+        assert_eq!(
+            tree(temp.path()),
+            [
+                folder("f"),
+                file("f/a.txt", "a"),
+                folder("f/sub"),
+                file("f/sub/b.txt", "b"),
+            ]
+        );
+
+        let mut files = 0;
+        let result = move_by_copying(&source, &destination, &mut |progress| {
+            if let CopyProgress::Files(count) = progress {
+                files += count;
+            }
+            Ok(())
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(files, 2);
+        assert_eq!(
+            tree(temp.path()),
+            [
+                folder("moved"),
+                file("moved/a.txt", "a"),
+                folder("moved/sub"),
+                file("moved/sub/b.txt", "b"),
+            ]
+        );
+    }
+}
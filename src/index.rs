@@ -0,0 +1,541 @@
+// Optional index of every item below the home folder, so name searches there
+// answer from memory instead of walking the disk.
+//
+// The index lives in the XDG cache dir and is loaded at startup when it
+// exists, which is what keeps indexing turned on between runs. A worker
+// thread then walks the home folder again to catch what changed while the
+// application was closed, watches every folder with inotify to stay current,
+// and saves the index now and then and when the application closes. The cache
+// dir itself is left out: it changes constantly and holds the index file.
+use crate::fs_utils::get_mime_group;
+use crate::search::{Matcher, SearchHit, MAX_HITS};
+use iced::futures::channel::mpsc;
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+use percent_encoding::{percent_decode_str, percent_encode, AsciiSet, CONTROLS};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::ops::Bound;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use walkdir::WalkDir;
+use xdg::BaseDirectories;
+
+const INDEX_FILE: &str = "search_index.tsv";
+const INDEX_HEADER: &str = "file-manager search index 1";
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+const EVENT_BUFFER_SIZE: usize = 64 * 1024;
+
+// Tabs and newlines separate the fields and lines of the index file
+const PATH_ENCODE_SET: &AsciiSet = &CONTROLS.add(b'%');
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexStatus {
+    Loading,
+    // Walking the home folder; the count of items seen so far
+    Building(usize),
+    Ready(usize),
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+struct IndexEntry {
+    name_lower: Box<str>,
+    size: u64,
+    // Seconds since the epoch
+    modified: i64,
+    is_dir: bool,
+    mime_group: Option<Box<str>>,
+}
+
+impl IndexEntry {
+    fn of(path: &Path, metadata: &fs::Metadata) -> Self {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let is_dir = metadata.is_dir();
+        IndexEntry {
+            name_lower: name.to_lowercase().into(),
+            size: if is_dir { 0 } else { metadata.len() },
+            modified: metadata
+                .modified()
+                .ok()
+                .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs() as i64),
+            is_dir,
+            mime_group: if is_dir {
+                None
+            } else {
+                mime_guess::from_path(path)
+                    .first()
+                    .and_then(|mime| get_mime_group(&mime))
+                    .map(String::into_boxed_str)
+            },
+        }
+    }
+}
+
+// Items by path, so the items below a folder are one range
+type Entries = BTreeMap<PathBuf, IndexEntry>;
+
+#[derive(Debug)]
+pub struct SearchIndex {
+    pub root: PathBuf,
+    pub status: IndexStatus,
+    // Set once there is something to search, a saved index or a finished walk
+    usable: bool,
+    entries: Arc<RwLock<Entries>>,
+    stopped: Arc<AtomicBool>,
+    // Set when indexing is turned off: the worker deletes the index file
+    discarded: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl SearchIndex {
+    // Indexing is on if an index was saved by an earlier run
+    pub fn is_enabled() -> bool {
+        index_file_path().is_ok_and(|path| path.exists())
+    }
+
+    // Loads the saved index, if any, then brings it up to date and keeps it
+    // current until the index is dropped
+    pub fn start(root: PathBuf) -> (Self, mpsc::UnboundedReceiver<IndexStatus>) {
+        let mut index = SearchIndex {
+            root: root.clone(),
+            status: IndexStatus::Loading,
+            usable: false,
+            entries: Arc::new(RwLock::new(Entries::new())),
+            stopped: Arc::new(AtomicBool::new(false)),
+            discarded: Arc::new(AtomicBool::new(false)),
+            worker: None,
+        };
+        let (sender, receiver) = mpsc::unbounded();
+        let mut worker = Worker {
+            root,
+            excluded: dirs::cache_dir(),
+            entries: index.entries.clone(),
+            stopped: index.stopped.clone(),
+            discarded: index.discarded.clone(),
+            sender,
+            watches: HashMap::new(),
+            watch_limit_reached: false,
+        };
+        index.worker = Some(thread::spawn(move || worker.run()));
+        (index, receiver)
+    }
+
+    // Stops indexing and removes the index file. The worker finishes on its
+    // own, as nothing it holds needs saving.
+    pub fn discard(mut self) {
+        self.discarded.store(true, Ordering::Relaxed);
+        self.worker = None;
+    }
+
+    pub fn set_status(&mut self, status: IndexStatus) {
+        if matches!(status, IndexStatus::Ready(_)) {
+            self.usable = true;
+        }
+        self.status = status;
+    }
+
+    // For searching below `path`, if the index can answer that
+    pub fn reader_for(&self, path: &Path) -> Option<IndexReader> {
+        (self.usable && path.starts_with(&self.root)).then(|| IndexReader(self.entries.clone()))
+    }
+}
+
+// Waits for the worker to save the latest changes, as the index is dropped
+// when the application closes and the worker would not outlive it
+impl Drop for SearchIndex {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            if worker.join().is_err() {
+                eprintln!("Search index worker panicked");
+            }
+        }
+    }
+}
+
+// Read access to the index for a search thread
+#[derive(Debug, Clone)]
+pub struct IndexReader(Arc<RwLock<Entries>>);
+
+impl IndexReader {
+    // Items below `root` whose name matches, best first, and whether there
+    // were more than MAX_HITS
+    pub fn query(
+        &self,
+        root: &Path,
+        matcher: &Matcher,
+        show_hidden: bool,
+    ) -> (Vec<SearchHit>, bool) {
+        let entries = self.0.read().unwrap_or_else(|e| e.into_inner());
+        // The best MAX_HITS so far, worst on top; of equal scores the first
+        // path found is kept
+        let mut best = BinaryHeap::new();
+        let mut truncated = false;
+        for (order, (path, entry)) in entries
+            .range::<Path, _>((Bound::Included(root), Bound::Unbounded))
+            .take_while(|(path, _)| path.starts_with(root))
+            .enumerate()
+        {
+            let Ok(relative) = path.strip_prefix(root) else {
+                continue;
+            };
+            if relative.as_os_str().is_empty() || (!show_hidden && is_hidden_path(relative)) {
+                continue;
+            }
+            let Some(score) = matcher.score_lowercase(&entry.name_lower) else {
+                continue;
+            };
+            best.push(Reverse((score, Reverse(order), path, entry.is_dir)));
+            if best.len() > MAX_HITS {
+                best.pop();
+                truncated = true;
+            }
+        }
+        // Ascending order of the reversed keys is best first
+        let hits = best
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((score, _, path, is_dir))| SearchHit {
+                path: path.clone(),
+                relative: path.strip_prefix(root).unwrap_or(path).to_path_buf(),
+                is_dir,
+                score,
+                line_match: None,
+            })
+            .collect();
+        (hits, truncated)
+    }
+}
+
+fn is_hidden_path(relative: &Path) -> bool {
+    relative.components().any(|component| match component {
+        Component::Normal(name) => name.as_bytes().starts_with(b"."),
+        _ => false,
+    })
+}
+
+fn index_file_path() -> Result<PathBuf, String> {
+    let xdg_dirs = BaseDirectories::with_prefix("file-manager")
+        .map_err(|e| format!("Failed to get XDG base directories: {}", e))?;
+    xdg_dirs
+        .place_cache_file(INDEX_FILE)
+        .map_err(|e| format!("Failed to place index file: {}", e))
+}
+
+// One line per item: size, mtime, folder flag, MIME group and the path
+fn save(entries: &Entries, path: &Path) -> io::Result<()> {
+    let temporary = path.with_extension("tmp");
+    let mut writer = BufWriter::new(File::create(&temporary)?);
+    writeln!(writer, "{}", INDEX_HEADER)?;
+    for (item, entry) in entries {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}",
+            entry.size,
+            entry.modified,
+            u8::from(entry.is_dir),
+            entry.mime_group.as_deref().unwrap_or("-"),
+            percent_encode(item.as_os_str().as_bytes(), PATH_ENCODE_SET)
+        )?;
+    }
+    writer.flush()?;
+    fs::rename(temporary, path)
+}
+
+fn load(path: &Path) -> io::Result<Entries> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    if lines.next().transpose()?.as_deref() != Some(INDEX_HEADER) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unknown index format",
+        ));
+    }
+    let mut entries = Entries::new();
+    for line in lines {
+        let line = line?;
+        let mut fields = line.splitn(5, '\t');
+        let (Some(size), Some(modified), Some(is_dir), Some(mime_group), Some(item)) = (
+            fields.next(),
+            fields.next(),
+            fields.next(),
+            fields.next(),
+            fields.next(),
+        ) else {
+            continue;
+        };
+        let item = PathBuf::from(OsString::from_vec(percent_decode_str(item).collect()));
+        let name = item.file_name().unwrap_or_default().to_string_lossy();
+        entries.insert(
+            item.clone(),
+            IndexEntry {
+                name_lower: name.to_lowercase().into(),
+                size: size.parse().unwrap_or(0),
+                modified: modified.parse().unwrap_or(0),
+                is_dir: is_dir == "1",
+                mime_group: (mime_group != "-").then(|| mime_group.into()),
+            },
+        );
+    }
+    Ok(entries)
+}
+
+struct Worker {
+    root: PathBuf,
+    excluded: Option<PathBuf>,
+    entries: Arc<RwLock<Entries>>,
+    stopped: Arc<AtomicBool>,
+    discarded: Arc<AtomicBool>,
+    sender: mpsc::UnboundedSender<IndexStatus>,
+    watches: HashMap<WatchDescriptor, PathBuf>,
+    watch_limit_reached: bool,
+}
+
+impl Worker {
+    fn report(&self, status: IndexStatus) {
+        let _ = self.sender.unbounded_send(status);
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed) || self.discarded.load(Ordering::Relaxed)
+    }
+
+    fn write_entries(&self) -> std::sync::RwLockWriteGuard<'_, Entries> {
+        self.entries.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn run(&mut self) {
+        let index_file = match index_file_path() {
+            Ok(path) => path,
+            Err(e) => return self.report(IndexStatus::Failed(e)),
+        };
+        // The saved index answers searches while the walk brings it up to date
+        match load(&index_file) {
+            Ok(saved) => {
+                let count = saved.len();
+                *self.write_entries() = saved;
+                self.report(IndexStatus::Ready(count));
+            }
+            // Writes an empty index right away, so indexing stays on even if
+            // the application is closed before the first walk is done
+            Err(_) => self.save_to(&index_file),
+        }
+
+        let mut inotify = match Inotify::init() {
+            Ok(inotify) => inotify,
+            Err(e) => return self.report(IndexStatus::Failed(format!("inotify: {}", e))),
+        };
+        self.rebuild(&mut inotify, &index_file);
+
+        let mut buffer = vec![0u8; EVENT_BUFFER_SIZE];
+        let mut last_saved = Instant::now();
+        let mut dirty = false;
+        while !self.is_stopped() {
+            let mut changes = Vec::new();
+            let mut overflowed = false;
+            match inotify.read_events(&mut buffer) {
+                Ok(events) => {
+                    for event in events {
+                        if event.mask.contains(EventMask::Q_OVERFLOW) {
+                            overflowed = true;
+                        } else {
+                            changes.push((event.wd, event.mask, event.name.map(OsString::from)));
+                        }
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                Err(e) => {
+                    self.report(IndexStatus::Failed(format!("inotify: {}", e)));
+                    break;
+                }
+            }
+
+            if overflowed {
+                // Changes were lost; only a new walk can tell what they were
+                self.rebuild(&mut inotify, &index_file);
+                last_saved = Instant::now();
+                dirty = false;
+            } else if !changes.is_empty() {
+                for (wd, mask, name) in changes {
+                    self.apply_change(&mut inotify, wd, mask, name);
+                }
+                dirty = true;
+                let count = self.entries.read().map_or(0, |entries| entries.len());
+                self.report(IndexStatus::Ready(count));
+            }
+            if dirty && last_saved.elapsed() >= SAVE_INTERVAL {
+                self.save_to(&index_file);
+                last_saved = Instant::now();
+                dirty = false;
+            }
+        }
+
+        if self.discarded.load(Ordering::Relaxed) {
+            println!("Search index turned off, removing {}", index_file.display());
+            let _ = fs::remove_file(&index_file);
+        } else if dirty {
+            self.save_to(&index_file);
+        }
+    }
+
+    fn save_to(&self, index_file: &Path) {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = save(&entries, index_file) {
+            eprintln!(
+                "Failed to save search index {}: {}",
+                index_file.display(),
+                e
+            );
+        }
+    }
+
+    // Walks the whole root again, watching every folder on the way
+    fn rebuild(&mut self, inotify: &mut Inotify, index_file: &Path) {
+        println!("Indexing {}", self.root.display());
+        for wd in self.watches.drain().map(|(wd, _)| wd).collect::<Vec<_>>() {
+            let _ = inotify.watches().remove(wd);
+        }
+        let root = self.root.clone();
+        let Some(walked) = self.walk(inotify, &root, true) else {
+            return;
+        };
+        let count = walked.len();
+        *self.write_entries() = walked;
+        self.save_to(index_file);
+        println!("Indexed {} items", count);
+        self.report(IndexStatus::Ready(count));
+    }
+
+    // The items below `dir`, or None if the index was stopped meanwhile
+    fn walk(
+        &mut self,
+        inotify: &mut Inotify,
+        dir: &Path,
+        report_progress: bool,
+    ) -> Option<Entries> {
+        let excluded = self.excluded.clone();
+        let mut walked = Entries::new();
+        let mut last_report = Instant::now();
+        let items = WalkDir::new(dir)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|entry| excluded.as_deref() != Some(entry.path()));
+        for item in items {
+            if self.is_stopped() {
+                return None;
+            }
+            let Ok(item) = item else {
+                continue;
+            };
+            if item.file_type().is_dir() {
+                self.watch(inotify, item.path());
+            }
+            if let Ok(metadata) = item.metadata() {
+                walked.insert(
+                    item.path().to_path_buf(),
+                    IndexEntry::of(item.path(), &metadata),
+                );
+            }
+            if report_progress && last_report.elapsed() >= PROGRESS_INTERVAL {
+                self.report(IndexStatus::Building(walked.len()));
+                last_report = Instant::now();
+            }
+        }
+        Some(walked)
+    }
+
+    fn watch(&mut self, inotify: &mut Inotify, dir: &Path) {
+        if self.watch_limit_reached {
+            return;
+        }
+        let mask = WatchMask::CREATE
+            | WatchMask::DELETE
+            | WatchMask::MOVED_FROM
+            | WatchMask::MOVED_TO
+            | WatchMask::CLOSE_WRITE
+            | WatchMask::ATTRIB
+            | WatchMask::DELETE_SELF
+            | WatchMask::ONLYDIR
+            | WatchMask::DONT_FOLLOW;
+        match inotify.watches().add(dir, mask) {
+            Ok(wd) => {
+                self.watches.insert(wd, dir.to_path_buf());
+            }
+            // Out of watches (fs.inotify.max_user_watches): the rest of the
+            // tree is indexed but not kept current
+            Err(e) if e.raw_os_error() == Some(libc::ENOSPC) => {
+                eprintln!("inotify watch limit reached; some folders will not be kept current in the search index");
+                self.watch_limit_reached = true;
+            }
+            Err(_) => {}
+        }
+    }
+
+    fn apply_change(
+        &mut self,
+        inotify: &mut Inotify,
+        wd: WatchDescriptor,
+        mask: EventMask,
+        name: Option<OsString>,
+    ) {
+        if mask.intersects(EventMask::IGNORED | EventMask::DELETE_SELF) {
+            self.watches.remove(&wd);
+            return;
+        }
+        let (Some(dir), Some(name)) = (self.watches.get(&wd), name) else {
+            return;
+        };
+        let path = dir.join(name);
+        if self.excluded.as_deref() == Some(path.as_path()) {
+            return;
+        }
+
+        if mask.intersects(EventMask::DELETE | EventMask::MOVED_FROM) {
+            self.remove_tree(inotify, &path);
+        } else if mask.contains(EventMask::ISDIR)
+            && mask.intersects(EventMask::CREATE | EventMask::MOVED_TO)
+        {
+            // A folder moved in brings everything inside it along
+            if let Some(walked) = self.walk(inotify, &path, false) {
+                self.write_entries().extend(walked);
+            }
+        } else if let Ok(metadata) = fs::symlink_metadata(&path) {
+            let entry = IndexEntry::of(&path, &metadata);
+            self.write_entries().insert(path, entry);
+        }
+    }
+
+    // Forgets `path` and everything below it, including their watches
+    fn remove_tree(&mut self, inotify: &mut Inotify, path: &Path) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        let below: Vec<PathBuf> = entries
+            .range::<Path, _>((Bound::Included(path), Bound::Unbounded))
+            .take_while(|(item, _)| item.starts_with(path))
+            .map(|(item, _)| item.clone())
+            .collect();
+        for item in below {
+            entries.remove(&item);
+        }
+        drop(entries);
+
+        let stale: Vec<WatchDescriptor> = self
+            .watches
+            .iter()
+            .filter(|(_, dir)| dir.starts_with(path))
+            .map(|(wd, _)| wd.clone())
+            .collect();
+        for wd in stale {
+            self.watches.remove(&wd);
+            let _ = inotify.watches().remove(wd);
+        }
+    }
+}
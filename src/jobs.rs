@@ -0,0 +1,560 @@
+// Background copy and move jobs.
+//
+// Each job runs on its own worker thread and streams `JobEvent`s back to the
+// application. Workers check their `JobControl` after every chunk, which is
+// where pausing blocks and cancelling aborts. When a destination already
+// exists the worker sends a `Conflict` and waits for the user's answer.
+use crate::app::ClipboardAction;
+use crate::fs_utils::{
+    copy_item, item_size, move_item, tree_totals, ConflictChoice, CopyProgress, Transferred,
+};
+use crate::journal::FileOperation;
+use iced::futures::channel::mpsc;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc as answer_channel, Arc};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+const MAX_RUNNING_JOBS: usize = 2;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const CANCELLED_MESSAGE: &str = "Cancelled by user.";
+
+pub type JobId = usize;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JobProgress {
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub files_done: u64,
+    pub files_total: u64,
+}
+
+impl JobProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.bytes_total == 0 {
+            return if self.files_total == 0 {
+                0.0
+            } else {
+                self.files_done as f32 / self.files_total as f32
+            };
+        }
+        self.bytes_done as f32 / self.bytes_total as f32
+    }
+}
+
+// One side of a conflict, as shown to the user
+#[derive(Debug, Clone)]
+pub struct ItemInfo {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl ItemInfo {
+    fn of(path: &Path) -> Self {
+        let metadata = fs::symlink_metadata(path).ok();
+        ItemInfo {
+            path: path.to_path_buf(),
+            is_dir: metadata.as_ref().is_some_and(|m| m.is_dir()),
+            size: item_size(path),
+            modified: metadata.and_then(|m| m.modified().ok()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub source: ItemInfo,
+    pub destination: ItemInfo,
+}
+
+impl Conflict {
+    pub fn can_merge(&self) -> bool {
+        self.source.is_dir && self.destination.is_dir
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ConflictAnswer {
+    choice: ConflictChoice,
+    apply_to_all: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum JobEvent {
+    Progress(JobProgress, Option<PathBuf>),
+    Conflict(Conflict),
+    // Operations that completed, which are journaled even if the job failed later
+    Finished(Vec<FileOperation>, Result<(), String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Paused,
+    WaitingForAnswer,
+    Completed,
+    Failed(String),
+    Cancelled,
+}
+
+#[derive(Debug, Default)]
+struct JobControl {
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+impl JobControl {
+    // Blocks while the job is paused and fails once it is cancelled
+    fn checkpoint(&self) -> Result<(), String> {
+        while self.paused.load(Ordering::Relaxed) && !self.cancelled.load(Ordering::Relaxed) {
+            thread::sleep(PAUSE_POLL_INTERVAL);
+        }
+        if self.cancelled.load(Ordering::Relaxed) {
+            Err(CANCELLED_MESSAGE.to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Job {
+    pub id: JobId,
+    pub action: ClipboardAction,
+    pub sources: Vec<PathBuf>,
+    pub destination_dir: PathBuf,
+    pub status: JobStatus,
+    pub progress: JobProgress,
+    pub current: Option<PathBuf>,
+    pub pending_conflict: Option<Conflict>,
+    control: Arc<JobControl>,
+    answers: Option<answer_channel::Sender<ConflictAnswer>>,
+    // Time spent running, excluding pauses, for throughput and ETA
+    active_time: Duration,
+    resumed_at: Option<Instant>,
+}
+
+impl Job {
+    pub fn title(&self) -> String {
+        let verb = match (self.action, self.is_finished()) {
+            (ClipboardAction::Copy, false) => "Copying",
+            (ClipboardAction::Copy, true) => "Copy",
+            (ClipboardAction::Cut, false) => "Moving",
+            (ClipboardAction::Cut, true) => "Move",
+        };
+        let what = match self.sources.as_slice() {
+            [source] => format!(
+                "'{}'",
+                source.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            sources => format!("{} items", sources.len()),
+        };
+        let destination = self
+            .destination_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.destination_dir.display().to_string());
+        format!("{} {} to {}", verb, what, destination)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.status,
+            JobStatus::Completed | JobStatus::Failed(_) | JobStatus::Cancelled
+        )
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self.status,
+            JobStatus::Running | JobStatus::Paused | JobStatus::WaitingForAnswer
+        )
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.active_time + self.resumed_at.map_or(Duration::ZERO, |t| t.elapsed())
+    }
+
+    // Average bytes per second while running
+    pub fn throughput(&self) -> Option<f64> {
+        let seconds = self.elapsed().as_secs_f64();
+        (seconds > 0.5 && self.progress.bytes_done > 0)
+            .then(|| self.progress.bytes_done as f64 / seconds)
+    }
+
+    pub fn eta(&self) -> Option<Duration> {
+        if self.status != JobStatus::Running {
+            return None;
+        }
+        let remaining = self
+            .progress
+            .bytes_total
+            .saturating_sub(self.progress.bytes_done);
+        self.throughput()
+            .map(|rate| Duration::from_secs_f64(remaining as f64 / rate))
+    }
+
+    fn stop_clock(&mut self) {
+        if let Some(resumed_at) = self.resumed_at.take() {
+            self.active_time += resumed_at.elapsed();
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct JobManager {
+    jobs: Vec<Job>,
+    next_id: JobId,
+}
+
+impl JobManager {
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.jobs.iter().filter(|job| !job.is_finished()).count()
+    }
+
+    fn find_mut(&mut self, id: JobId) -> Option<&mut Job> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    pub fn enqueue(
+        &mut self,
+        action: ClipboardAction,
+        sources: Vec<PathBuf>,
+        destination_dir: PathBuf,
+    ) -> JobId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.jobs.push(Job {
+            id,
+            action,
+            sources,
+            destination_dir,
+            status: JobStatus::Queued,
+            progress: JobProgress::default(),
+            current: None,
+            pending_conflict: None,
+            control: Arc::default(),
+            answers: None,
+            active_time: Duration::ZERO,
+            resumed_at: None,
+        });
+        id
+    }
+
+    // Starts queued jobs while there is room. Returns the event stream of every
+    // job that was started.
+    pub fn start_queued(&mut self) -> Vec<(JobId, mpsc::UnboundedReceiver<JobEvent>)> {
+        let mut running = self.jobs.iter().filter(|job| job.is_active()).count();
+        let mut started = vec![];
+        for job in &mut self.jobs {
+            if running >= MAX_RUNNING_JOBS {
+                break;
+            }
+            if job.status != JobStatus::Queued {
+                continue;
+            }
+            let (answers, answer_receiver) = answer_channel::channel();
+            job.status = JobStatus::Running;
+            job.resumed_at = Some(Instant::now());
+            job.answers = Some(answers);
+            running += 1;
+            started.push((
+                job.id,
+                spawn_worker(
+                    job.action,
+                    job.sources.clone(),
+                    job.destination_dir.clone(),
+                    job.control.clone(),
+                    answer_receiver,
+                ),
+            ));
+        }
+        started
+    }
+
+    // Applies an event from a worker. Returns the outcome once the job is done.
+    pub fn apply(
+        &mut self,
+        id: JobId,
+        event: JobEvent,
+    ) -> Option<(Vec<FileOperation>, Result<(), String>)> {
+        let job = self.find_mut(id)?;
+        match event {
+            JobEvent::Progress(progress, current) => {
+                job.progress = progress;
+                job.current = current;
+                None
+            }
+            JobEvent::Conflict(conflict) => {
+                job.stop_clock();
+                job.status = JobStatus::WaitingForAnswer;
+                job.pending_conflict = Some(conflict);
+                None
+            }
+            JobEvent::Finished(operations, result) => {
+                job.stop_clock();
+                job.current = None;
+                job.pending_conflict = None;
+                job.answers = None;
+                job.status = match &result {
+                    Ok(()) => JobStatus::Completed,
+                    Err(_) if job.control.cancelled.load(Ordering::Relaxed) => {
+                        JobStatus::Cancelled
+                    }
+                    Err(e) => JobStatus::Failed(e.clone()),
+                };
+                Some((operations, result))
+            }
+        }
+    }
+
+    pub fn pause(&mut self, id: JobId) {
+        if let Some(job) = self.find_mut(id) {
+            if job.status == JobStatus::Running {
+                job.control.paused.store(true, Ordering::Relaxed);
+                job.status = JobStatus::Paused;
+                job.stop_clock();
+            }
+        }
+    }
+
+    pub fn resume(&mut self, id: JobId) {
+        if let Some(job) = self.find_mut(id) {
+            if job.status == JobStatus::Paused {
+                job.control.paused.store(false, Ordering::Relaxed);
+                job.status = JobStatus::Running;
+                job.resumed_at = Some(Instant::now());
+            }
+        }
+    }
+
+    // The first conflict still waiting for the user, oldest job first
+    pub fn next_conflict(&self) -> Option<(JobId, &Conflict)> {
+        self.jobs
+            .iter()
+            .find_map(|job| job.pending_conflict.as_ref().map(|c| (job.id, c)))
+    }
+
+    pub fn answer_conflict(&mut self, id: JobId, choice: ConflictChoice, apply_to_all: bool) {
+        if let Some(job) = self.find_mut(id) {
+            if job.pending_conflict.take().is_none() {
+                return;
+            }
+            if let Some(answers) = &job.answers {
+                let _ = answers.send(ConflictAnswer {
+                    choice,
+                    apply_to_all,
+                });
+            }
+            job.status = JobStatus::Running;
+            job.resumed_at = Some(Instant::now());
+        }
+    }
+
+    // Queued jobs are dropped right away; running ones stop at their next checkpoint
+    pub fn cancel(&mut self, id: JobId) {
+        if let Some(job) = self.find_mut(id) {
+            match job.status {
+                JobStatus::Queued => job.status = JobStatus::Cancelled,
+                JobStatus::Running | JobStatus::Paused | JobStatus::WaitingForAnswer => {
+                    job.control.cancelled.store(true, Ordering::Relaxed);
+                    job.pending_conflict = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.jobs.retain(|job| !job.is_finished());
+    }
+}
+
+// Tracks progress across all sources of a job and reports it at most every
+// PROGRESS_INTERVAL
+struct Reporter {
+    sender: mpsc::UnboundedSender<JobEvent>,
+    progress: JobProgress,
+    current: Option<PathBuf>,
+    last_report: Instant,
+}
+
+impl Reporter {
+    fn advance(&mut self, step: CopyProgress) {
+        match step {
+            CopyProgress::Bytes(bytes) => self.progress.bytes_done += bytes,
+            CopyProgress::Files(files) => self.progress.files_done += files,
+        }
+        if self.last_report.elapsed() >= PROGRESS_INTERVAL {
+            self.report();
+        }
+    }
+
+    fn report(&mut self) {
+        self.last_report = Instant::now();
+        let _ = self.sender.unbounded_send(JobEvent::Progress(
+            self.progress,
+            self.current.clone(),
+        ));
+    }
+
+    fn finish(self, operations: Vec<FileOperation>, result: Result<(), String>) {
+        let _ = self
+            .sender
+            .unbounded_send(JobEvent::Finished(operations, result));
+    }
+}
+
+// Answers conflicts for the worker: from an "apply to all" choice made
+// earlier, or by asking the user and waiting for the reply
+struct ConflictAsker {
+    sender: mpsc::UnboundedSender<JobEvent>,
+    answers: answer_channel::Receiver<ConflictAnswer>,
+    control: Arc<JobControl>,
+    file_policy: Option<ConflictChoice>,
+    folder_policy: Option<ConflictChoice>,
+}
+
+impl ConflictAsker {
+    fn resolve(&mut self, source: &Path, destination: &Path) -> Result<ConflictChoice, String> {
+        let conflict = Conflict {
+            source: ItemInfo::of(source),
+            destination: ItemInfo::of(destination),
+        };
+        let policy = if conflict.can_merge() {
+            self.folder_policy
+        } else {
+            self.file_policy
+        };
+        if let Some(choice) = policy {
+            return Ok(choice);
+        }
+
+        let _ = self.sender.unbounded_send(JobEvent::Conflict(conflict));
+        let answer = loop {
+            match self.answers.recv_timeout(PAUSE_POLL_INTERVAL) {
+                Ok(answer) => break answer,
+                Err(answer_channel::RecvTimeoutError::Timeout) => self.control.checkpoint()?,
+                Err(answer_channel::RecvTimeoutError::Disconnected) => {
+                    return Err(CANCELLED_MESSAGE.to_string())
+                }
+            }
+        };
+
+        if answer.apply_to_all {
+            self.folder_policy = Some(answer.choice);
+            // Merging only applies to folders
+            if answer.choice != ConflictChoice::Merge {
+                self.file_policy = Some(answer.choice);
+            }
+        }
+        Ok(answer.choice)
+    }
+}
+
+fn transfer(
+    action: ClipboardAction,
+    source: &Path,
+    destination_dir: &Path,
+    asker: &mut ConflictAsker,
+    on_progress: &mut dyn FnMut(CopyProgress) -> Result<(), String>,
+) -> Result<Vec<FileOperation>, (Vec<FileOperation>, String)> {
+    let mut on_conflict = |source: &Path, destination: &Path| asker.resolve(source, destination);
+    let operation: fn(PathBuf, PathBuf) -> FileOperation = match action {
+        ClipboardAction::Copy => |source, destination| FileOperation::Copy {
+            source,
+            destination,
+        },
+        ClipboardAction::Cut => |source, destination| FileOperation::Move {
+            source,
+            destination,
+        },
+    };
+    let to_operations = |transferred: Transferred| {
+        transferred
+            .into_iter()
+            .map(|(source, destination)| operation(source, destination))
+            .collect()
+    };
+    let result = match action {
+        ClipboardAction::Copy => copy_item(source, destination_dir, &mut on_conflict, on_progress),
+        ClipboardAction::Cut => move_item(source, destination_dir, &mut on_conflict, on_progress),
+    };
+    // What was transferred before a failure can still be undone
+    result
+        .map(to_operations)
+        .map_err(|(transferred, e)| (to_operations(transferred), e))
+}
+
+fn spawn_worker(
+    action: ClipboardAction,
+    sources: Vec<PathBuf>,
+    destination_dir: PathBuf,
+    control: Arc<JobControl>,
+    answers: answer_channel::Receiver<ConflictAnswer>,
+) -> mpsc::UnboundedReceiver<JobEvent> {
+    let (sender, receiver) = mpsc::unbounded();
+
+    thread::spawn(move || {
+        let totals: Vec<(u64, u64)> = sources.iter().map(|s| tree_totals(s)).collect();
+        let mut asker = ConflictAsker {
+            sender: sender.clone(),
+            answers,
+            control: control.clone(),
+            file_policy: None,
+            folder_policy: None,
+        };
+        let mut reporter = Reporter {
+            sender,
+            progress: JobProgress {
+                bytes_total: totals.iter().map(|t| t.0).sum(),
+                files_total: totals.iter().map(|t| t.1).sum(),
+                ..JobProgress::default()
+            },
+            current: None,
+            last_report: Instant::now(),
+        };
+        reporter.report();
+
+        let mut operations = vec![];
+        for (source, (bytes, files)) in sources.iter().zip(totals) {
+            if let Err(e) = control.checkpoint() {
+                return reporter.finish(operations, Err(e));
+            }
+            let before = reporter.progress;
+            reporter.current = Some(source.clone());
+
+            let result = transfer(action, source, &destination_dir, &mut asker, &mut |step| {
+                reporter.advance(step);
+                control.checkpoint()
+            });
+            match result {
+                Ok(done) => operations.extend(done),
+                Err((done, e)) => {
+                    operations.extend(done);
+                    return reporter.finish(operations, Err(e));
+                }
+            }
+
+            // Skipped items count as done too
+            reporter.progress.bytes_done = before.bytes_done + bytes;
+            reporter.progress.files_done = before.files_done + files;
+        }
+
+        reporter.current = None;
+        reporter.report();
+        reporter.finish(operations, Ok(()));
+    });
+
+    receiver
+}
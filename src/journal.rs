@@ -0,0 +1,418 @@
+// Undo/redo journal for completed file operations.
+//
+// Every entry remembers a fingerprint of the items it touched. Before an entry
+// is reversed or replayed the fingerprints are compared with the filesystem,
+// and the entry is refused if something changed the items in the meantime.
+use crate::fs_utils::{copy_path, move_path};
+use crate::trash::{move_to_trash, untrash, TrashedItem};
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const MAX_JOURNAL_ENTRIES: usize = 100;
+
+// A single completed, reversible file operation
+#[derive(Debug, Clone)]
+pub enum FileOperation {
+    Copy {
+        source: PathBuf,
+        destination: PathBuf,
+    },
+    Move {
+        source: PathBuf,
+        destination: PathBuf,
+    },
+    Rename {
+        source: PathBuf,
+        destination: PathBuf,
+    },
+    Trash(TrashedItem),
+}
+
+impl FileOperation {
+    // Where the affected item lives after the operation has been applied
+    fn applied_path(&self) -> &Path {
+        match self {
+            FileOperation::Copy { destination, .. }
+            | FileOperation::Move { destination, .. }
+            | FileOperation::Rename { destination, .. } => destination,
+            FileOperation::Trash(item) => &item.trashed_path,
+        }
+    }
+
+    // Where the affected item lives when the operation is not applied
+    fn reverted_path(&self) -> &Path {
+        match self {
+            FileOperation::Copy { source, .. }
+            | FileOperation::Move { source, .. }
+            | FileOperation::Rename { source, .. } => source,
+            FileOperation::Trash(item) => &item.original_path,
+        }
+    }
+
+    fn verb(&self) -> &'static str {
+        match self {
+            FileOperation::Copy { .. } => "copy",
+            FileOperation::Move { .. } => "move",
+            FileOperation::Rename { .. } => "rename",
+            FileOperation::Trash(_) => "trash",
+        }
+    }
+}
+
+// Identity and state of an item at the time it was recorded
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Fingerprint {
+    device: u64,
+    inode: u64,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl Fingerprint {
+    fn of(path: &Path) -> Option<Self> {
+        fs::symlink_metadata(path).ok().map(|meta| Fingerprint {
+            device: meta.dev(),
+            inode: meta.ino(),
+            len: meta.len(),
+            modified: meta.modified().ok(),
+        })
+    }
+}
+
+// A group of operations that are undone and redone together
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    operations: Vec<FileOperation>,
+    fingerprints: Vec<Option<Fingerprint>>,
+}
+
+impl JournalEntry {
+    // Fingerprints the items where they are now, i.e. with the operations applied
+    fn applied(operations: Vec<FileOperation>) -> Self {
+        let fingerprints = operations
+            .iter()
+            .map(|op| Fingerprint::of(op.applied_path()))
+            .collect();
+        JournalEntry {
+            operations,
+            fingerprints,
+        }
+    }
+
+    fn reverted(operations: Vec<FileOperation>) -> Self {
+        let fingerprints = operations
+            .iter()
+            .map(|op| Fingerprint::of(op.reverted_path()))
+            .collect();
+        JournalEntry {
+            operations,
+            fingerprints,
+        }
+    }
+
+    // The operations from `start` on, keeping their recorded fingerprints
+    fn split_off(&mut self, start: usize) -> JournalEntry {
+        JournalEntry {
+            operations: self.operations.split_off(start),
+            fingerprints: self.fingerprints.split_off(start),
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self.operations.as_slice() {
+            [op] => format!(
+                "{} of '{}'",
+                op.verb(),
+                op.reverted_path()
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+            ),
+            ops => format!("{} operations", ops.len()),
+        }
+    }
+
+    // Fails if any item is missing or differs from when the entry was recorded.
+    // `applied` tells whether the operations are applied or reverted now.
+    fn verify(&self, applied: bool) -> Result<(), String> {
+        for (index, (op, expected)) in self.operations.iter().zip(&self.fingerprints).enumerate() {
+            let path = if applied {
+                op.applied_path()
+            } else {
+                op.reverted_path()
+            };
+            // An item passing through a temporary name is checked where the
+            // next operation took it instead
+            let passed_on = if applied {
+                self.operations[index + 1..]
+                    .iter()
+                    .any(|later| later.reverted_path() == path)
+            } else {
+                self.operations[..index]
+                    .iter()
+                    .any(|earlier| earlier.applied_path() == path)
+            };
+            if passed_on {
+                continue;
+            }
+            let actual = Fingerprint::of(path);
+            if actual.is_none() || actual != *expected {
+                return Err(format!(
+                    "'{}' was changed after the {}; refusing to continue.",
+                    path.display(),
+                    op.verb()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Why an undo or redo stopped, with the operations it did not get to, which go
+// back on their stack, and those it did, which go on the other one
+#[derive(Debug, Clone)]
+pub struct JournalFailure {
+    pub error: String,
+    pub remaining: JournalEntry,
+    pub completed: Option<JournalEntry>,
+}
+
+impl JournalFailure {
+    fn refused(error: String, entry: JournalEntry) -> Self {
+        JournalFailure {
+            error,
+            remaining: entry,
+            completed: None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Journal {
+    undo_stack: Vec<JournalEntry>,
+    redo_stack: Vec<JournalEntry>,
+    // Set while an entry is being undone or redone
+    busy: bool,
+    // Completed operations still being fingerprinted, not yet on the stack
+    recording: usize,
+}
+
+impl Journal {
+    // Notes that completed operations are being fingerprinted. Until they are
+    // recorded, undo would act on an older entry, so it waits.
+    pub fn start_recording(&mut self) {
+        self.recording += 1;
+    }
+
+    // Records freshly completed operations, fingerprinted by `fingerprint`.
+    // A new action invalidates the redo history.
+    pub fn record(&mut self, entry: JournalEntry) {
+        self.recording = self.recording.saturating_sub(1);
+        self.redo_stack.clear();
+        self.push_undo(entry);
+    }
+
+    // The operations from `start_recording` could not be fingerprinted
+    pub fn recording_failed(&mut self) {
+        self.recording = self.recording.saturating_sub(1);
+    }
+
+    // Whether undo and redo have to wait for the journal to settle
+    pub fn is_busy(&self) -> bool {
+        self.busy || self.recording > 0
+    }
+
+    pub fn push_undo(&mut self, entry: JournalEntry) {
+        self.undo_stack.push(entry);
+        if self.undo_stack.len() > MAX_JOURNAL_ENTRIES {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    pub fn push_redo(&mut self, entry: JournalEntry) {
+        self.redo_stack.push(entry);
+    }
+
+    // The entry to undo, if any and nothing else is in progress. The journal
+    // stays busy until `finish` is called.
+    pub fn take_undo(&mut self) -> Option<JournalEntry> {
+        if self.is_busy() {
+            return None;
+        }
+        let entry = self.undo_stack.pop()?;
+        self.busy = true;
+        Some(entry)
+    }
+
+    pub fn take_redo(&mut self) -> Option<JournalEntry> {
+        if self.is_busy() {
+            return None;
+        }
+        let entry = self.redo_stack.pop()?;
+        self.busy = true;
+        Some(entry)
+    }
+
+    // An undo or redo from `take_undo` or `take_redo` has finished
+    pub fn finish(&mut self) {
+        self.busy = false;
+    }
+}
+
+fn reverse_operation(op: &FileOperation) -> Result<(), String> {
+    match op {
+        // Undoing a copy sends the copy to the trash rather than erasing it
+        FileOperation::Copy { destination, .. } => move_to_trash(destination).map(|_| ()),
+        FileOperation::Move {
+            source,
+            destination,
+        }
+        | FileOperation::Rename {
+            source,
+            destination,
+        } => {
+            if fs::symlink_metadata(source).is_ok() {
+                return Err(format!("'{}' already exists.", source.display()));
+            }
+            // A merge may have removed the folder the item came from
+            if let Some(parent) = source.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to recreate '{}': {}", parent.display(), e))?;
+            }
+            move_path(destination, source, &mut |_| Ok(()))
+        }
+        FileOperation::Trash(item) => untrash(item),
+    }
+}
+
+// Applies `op` again. Trashing yields a new trash location, so the replayed
+// operation is returned.
+fn replay_operation(op: &FileOperation) -> Result<FileOperation, String> {
+    match op {
+        FileOperation::Copy {
+            source,
+            destination,
+        } => {
+            if fs::symlink_metadata(destination).is_ok() {
+                return Err(format!("'{}' already exists.", destination.display()));
+            }
+            copy_path(source, destination, &mut |_| Ok(()))?;
+            Ok(op.clone())
+        }
+        FileOperation::Move {
+            source,
+            destination,
+        }
+        | FileOperation::Rename {
+            source,
+            destination,
+        } => {
+            if fs::symlink_metadata(destination).is_ok() {
+                return Err(format!("'{}' already exists.", destination.display()));
+            }
+            move_path(source, destination, &mut |_| Ok(()))?;
+            Ok(op.clone())
+        }
+        FileOperation::Trash(item) => move_to_trash(&item.original_path).map(FileOperation::Trash),
+    }
+}
+
+// Fingerprints freshly completed operations for `Journal::record`, off the UI
+// thread as it reads the metadata of every item
+pub async fn fingerprint(operations: Vec<FileOperation>) -> Result<JournalEntry, String> {
+    tokio::task::spawn_blocking(move || JournalEntry::applied(operations))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))
+}
+
+// Reverses every operation of `entry`, newest first. Returns the entry to put on the redo stack.
+pub async fn undo(entry: JournalEntry) -> Result<JournalEntry, JournalFailure> {
+    println!("Undoing {}", entry.description());
+    let fallback = entry.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut entry = entry;
+        if let Err(e) = entry.verify(true) {
+            return Err(JournalFailure::refused(e, entry));
+        }
+        for index in (0..entry.operations.len()).rev() {
+            if let Err(error) = reverse_operation(&entry.operations[index]) {
+                // The operations after `index` were reversed, the rest still apply
+                let reversed = entry.split_off(index + 1);
+                return Err(JournalFailure {
+                    error,
+                    remaining: entry,
+                    completed: (!reversed.operations.is_empty())
+                        .then(|| JournalEntry::reverted(reversed.operations)),
+                });
+            }
+        }
+        Ok(JournalEntry::reverted(entry.operations))
+    })
+    .await
+    .map_err(|e| JournalFailure::refused(format!("Task join error: {}", e), fallback))?
+}
+
+// Applies every operation of `entry` again. Returns the entry to put back on the undo stack.
+pub async fn redo(entry: JournalEntry) -> Result<JournalEntry, JournalFailure> {
+    println!("Redoing {}", entry.description());
+    let fallback = entry.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut entry = entry;
+        if let Err(e) = entry.verify(false) {
+            return Err(JournalFailure::refused(e, entry));
+        }
+        let mut replayed = Vec::new();
+        for index in 0..entry.operations.len() {
+            match replay_operation(&entry.operations[index]) {
+                Ok(op) => replayed.push(op),
+                Err(error) => {
+                    // The operations before `index` were replayed, the rest were not
+                    let remaining = entry.split_off(index);
+                    return Err(JournalFailure {
+                        error,
+                        remaining,
+                        completed: (!replayed.is_empty()).then(|| JournalEntry::applied(replayed)),
+                    });
+                }
+            }
+        }
+        Ok(JournalEntry::applied(replayed))
+    })
+    .await
+    .map_err(|e| JournalFailure::refused(format!("Task join error: {}", e), fallback))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_dir::TempDir;
+
+    #[tokio::test]
+    async fn undo_and_redo_wait_for_each_other_and_for_recording() {
+        let dir = TempDir::new().unwrap();
+        let (source, destination) = (dir.child("a"), dir.child("b"));
+        fs::write(&destination, "text").unwrap();
+        let renamed = || FileOperation::Rename {
+            source: source.clone(),
+            destination: destination.clone(),
+        };
+
+        let mut journal = Journal::default();
+        journal.start_recording();
+        journal.start_recording();
+        journal.record(fingerprint(vec![renamed()]).await.unwrap());
+        // The second operations are not on the stack yet
+        assert!(journal.take_undo().is_none());
+        journal.recording_failed();
+
+        let entry = journal.take_undo().unwrap();
+        // Nothing else runs until the undo has finished
+        journal.push_redo(entry);
+        assert!(journal.take_redo().is_none());
+        journal.finish();
+        assert!(journal.take_redo().is_some());
+        assert!(journal.is_busy());
+    }
+}
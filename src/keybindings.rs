@@ -0,0 +1,563 @@
+// Keyboard shortcuts, loaded from keybindings.ron in the XDG config dir.
+//
+// The file maps key chords such as "Ctrl+Shift+Z" to action names. It is
+// written with the default bindings on first start. Its entries apply on top
+// of the defaults, so bindings added in later versions still work, and a chord
+// bound to "None" is turned off. Bindings that cannot be understood, and chords
+// bound more than once, are reported on stderr and skipped; the rest still
+// apply.
+use crate::app::{Direction, Message};
+use iced::keyboard::key::Named;
+use iced::keyboard::{Key, Modifiers};
+use serde::de::{Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use xdg::BaseDirectories;
+
+const KEYBINDINGS_FILE: &str = "keybindings.ron";
+// The action that removes a default binding
+const UNBOUND: &str = "None";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Undo,
+    Redo,
+    SelectAll,
+    InvertSelection,
+    NewTab,
+    CloseTab,
+    NextTab,
+    PreviousTab,
+    CopyToOtherPane,
+    MoveToOtherPane,
+    Copy,
+    Cut,
+    Paste,
+    ToggleHiddenFiles,
+    FocusPathBar,
+    FocusSearch,
+    GoUp,
+    GoBack,
+    GoForward,
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Open,
+    Rename,
+    Trash,
+    DeletePermanently,
+    NewFolder,
+    NewFile,
+}
+
+impl Action {
+    const ALL: [Action; 29] = [
+        Action::Undo,
+        Action::Redo,
+        Action::SelectAll,
+        Action::InvertSelection,
+        Action::NewTab,
+        Action::CloseTab,
+        Action::NextTab,
+        Action::PreviousTab,
+        Action::CopyToOtherPane,
+        Action::MoveToOtherPane,
+        Action::Copy,
+        Action::Cut,
+        Action::Paste,
+        Action::ToggleHiddenFiles,
+        Action::FocusPathBar,
+        Action::FocusSearch,
+        Action::GoUp,
+        Action::GoBack,
+        Action::GoForward,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Open,
+        Action::Rename,
+        Action::Trash,
+        Action::DeletePermanently,
+        Action::NewFolder,
+        Action::NewFile,
+    ];
+
+    // The name used in the keybindings file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::SelectAll => "SelectAll",
+            Action::InvertSelection => "InvertSelection",
+            Action::NewTab => "NewTab",
+            Action::CloseTab => "CloseTab",
+            Action::NextTab => "NextTab",
+            Action::PreviousTab => "PreviousTab",
+            Action::CopyToOtherPane => "CopyToOtherPane",
+            Action::MoveToOtherPane => "MoveToOtherPane",
+            Action::Copy => "Copy",
+            Action::Cut => "Cut",
+            Action::Paste => "Paste",
+            Action::ToggleHiddenFiles => "ToggleHiddenFiles",
+            Action::FocusPathBar => "FocusPathBar",
+            Action::FocusSearch => "FocusSearch",
+            Action::GoUp => "GoUp",
+            Action::GoBack => "GoBack",
+            Action::GoForward => "GoForward",
+            Action::MoveUp => "MoveUp",
+            Action::MoveDown => "MoveDown",
+            Action::MoveLeft => "MoveLeft",
+            Action::MoveRight => "MoveRight",
+            Action::Open => "Open",
+            Action::Rename => "Rename",
+            Action::Trash => "Trash",
+            Action::DeletePermanently => "DeletePermanently",
+            Action::NewFolder => "NewFolder",
+            Action::NewFile => "NewFile",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|action| action.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn message(self) -> Message {
+        match self {
+            Action::Undo => Message::Undo,
+            Action::Redo => Message::Redo,
+            Action::SelectAll => Message::SelectAll,
+            Action::InvertSelection => Message::InvertSelection,
+            Action::NewTab => Message::NewTab,
+            Action::CloseTab => Message::CloseActiveTab,
+            Action::NextTab => Message::NextTab,
+            Action::PreviousTab => Message::PreviousTab,
+            Action::CopyToOtherPane => Message::CopyToOtherPane,
+            Action::MoveToOtherPane => Message::MoveToOtherPane,
+            Action::Copy => Message::CopySelection,
+            Action::Cut => Message::CutSelection,
+            Action::Paste => Message::Paste,
+            Action::ToggleHiddenFiles => Message::ToggleHiddenFiles,
+            Action::FocusPathBar => Message::FocusPathBar,
+            Action::FocusSearch => Message::FocusSearch,
+            Action::GoUp => Message::GoUp,
+            Action::GoBack => Message::GoBack,
+            Action::GoForward => Message::GoForward,
+            Action::MoveUp => Message::MoveSelection(Direction::Up),
+            Action::MoveDown => Message::MoveSelection(Direction::Down),
+            Action::MoveLeft => Message::MoveSelection(Direction::Left),
+            Action::MoveRight => Message::MoveSelection(Direction::Right),
+            Action::Open => Message::OpenSelection,
+            Action::Rename => Message::RenameSelection,
+            Action::Trash => Message::TrashSelection,
+            Action::DeletePermanently => Message::DeleteSelectionPermanently,
+            Action::NewFolder => Message::NewFolder,
+            Action::NewFile => Message::NewFile,
+        }
+    }
+}
+
+// Written to the keybindings file on first start, and in effect for every chord
+// the file does not mention. Shift+arrow extends the selection, so the arrows
+// are bound with and without it.
+const DEFAULT_BINDINGS: &[(&str, Action)] = &[
+    ("Ctrl+Z", Action::Undo),
+    ("Ctrl+Shift+Z", Action::Redo),
+    ("Ctrl+A", Action::SelectAll),
+    ("Ctrl+Shift+I", Action::InvertSelection),
+    ("Ctrl+T", Action::NewTab),
+    ("Ctrl+W", Action::CloseTab),
+    ("Ctrl+Tab", Action::NextTab),
+    ("Ctrl+Shift+Tab", Action::PreviousTab),
+    ("F5", Action::CopyToOtherPane),
+    ("F6", Action::MoveToOtherPane),
+    ("Ctrl+C", Action::Copy),
+    ("Ctrl+X", Action::Cut),
+    ("Ctrl+V", Action::Paste),
+    ("Ctrl+H", Action::ToggleHiddenFiles),
+    ("Ctrl+L", Action::FocusPathBar),
+    ("Ctrl+F", Action::FocusSearch),
+    ("Backspace", Action::GoUp),
+    ("Alt+Up", Action::GoUp),
+    ("Alt+Left", Action::GoBack),
+    ("Alt+Right", Action::GoForward),
+    ("Up", Action::MoveUp),
+    ("Down", Action::MoveDown),
+    ("Left", Action::MoveLeft),
+    ("Right", Action::MoveRight),
+    ("Shift+Up", Action::MoveUp),
+    ("Shift+Down", Action::MoveDown),
+    ("Shift+Left", Action::MoveLeft),
+    ("Shift+Right", Action::MoveRight),
+    ("Enter", Action::Open),
+    ("F2", Action::Rename),
+    ("Delete", Action::Trash),
+    ("Shift+Delete", Action::DeletePermanently),
+    ("Ctrl+Shift+N", Action::NewFolder),
+];
+
+// Names of the keys that are not a single character
+const NAMED_KEYS: &[(&str, Named)] = &[
+    ("Enter", Named::Enter),
+    ("Backspace", Named::Backspace),
+    ("Delete", Named::Delete),
+    ("Insert", Named::Insert),
+    ("Tab", Named::Tab),
+    ("Space", Named::Space),
+    ("Up", Named::ArrowUp),
+    ("Down", Named::ArrowDown),
+    ("Left", Named::ArrowLeft),
+    ("Right", Named::ArrowRight),
+    ("Home", Named::Home),
+    ("End", Named::End),
+    ("PageUp", Named::PageUp),
+    ("PageDown", Named::PageDown),
+    ("F1", Named::F1),
+    ("F2", Named::F2),
+    ("F3", Named::F3),
+    ("F4", Named::F4),
+    ("F5", Named::F5),
+    ("F6", Named::F6),
+    ("F7", Named::F7),
+    ("F8", Named::F8),
+    ("F9", Named::F9),
+    ("F10", Named::F10),
+    ("F11", Named::F11),
+    ("F12", Named::F12),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ChordKey {
+    // Stored in lower case, as Shift changes the reported character
+    Character(String),
+    Named(Named),
+}
+
+// A key with the modifiers held down. Ctrl stands for Cmd on macOS.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct KeyChord {
+    key: ChordKey,
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+}
+
+impl KeyChord {
+    // Shift is ignored for characters without a case, as it is often needed
+    // to type them: "Ctrl++" is pressed as Ctrl+Shift+= on many layouts
+    fn new(key: ChordKey, ctrl: bool, shift: bool, alt: bool) -> KeyChord {
+        let shift = match &key {
+            ChordKey::Character(character) => {
+                shift && character.to_lowercase() != character.to_uppercase()
+            }
+            ChordKey::Named(_) => shift,
+        };
+        KeyChord {
+            key,
+            ctrl,
+            shift,
+            alt,
+        }
+    }
+
+    // Parses chords like "Ctrl+Shift+Z", "Alt+Left" or "Ctrl++"
+    fn parse(chord: &str) -> Result<KeyChord, String> {
+        let (modifier_part, key_part) = if chord == "+" {
+            ("", "+")
+        } else if let Some(modifiers) = chord.strip_suffix("++") {
+            (modifiers, "+")
+        } else {
+            chord.rsplit_once('+').unwrap_or(("", chord))
+        };
+
+        let (mut ctrl, mut shift, mut alt) = (false, false, false);
+        for modifier in modifier_part.split('+').filter(|m| !m.is_empty()) {
+            match modifier.trim().to_lowercase().as_str() {
+                "ctrl" | "control" | "cmd" => ctrl = true,
+                "shift" => shift = true,
+                "alt" => alt = true,
+                _ => return Err(format!("unknown modifier \"{}\"", modifier.trim())),
+            }
+        }
+
+        let key_part = key_part.trim();
+        let key = if key_part.is_empty() {
+            return Err("no key after the modifiers".to_string());
+        } else if key_part.eq_ignore_ascii_case("Escape") {
+            return Err("Escape is reserved for cancelling".to_string());
+        } else if key_part.chars().count() == 1 {
+            ChordKey::Character(key_part.to_lowercase())
+        } else {
+            NAMED_KEYS
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key_part))
+                .map(|(_, named)| ChordKey::Named(*named))
+                .ok_or_else(|| format!("unknown key \"{}\"", key_part))?
+        };
+
+        Ok(KeyChord::new(key, ctrl, shift, alt))
+    }
+
+    fn from_key_press(key: &Key, modifiers: Modifiers) -> Option<KeyChord> {
+        let key = match key.as_ref() {
+            Key::Character(character) => ChordKey::Character(character.to_lowercase()),
+            Key::Named(named) => ChordKey::Named(named),
+            Key::Unidentified => return None,
+        };
+        Some(KeyChord::new(
+            key,
+            modifiers.command(),
+            modifiers.shift(),
+            modifiers.alt(),
+        ))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Keymap {
+    bindings: HashMap<KeyChord, Action>,
+}
+
+impl Keymap {
+    // Builds the keymap from the defaults and the (chord, action name) pairs of
+    // the file, in file order. Each binding that is skipped is described in
+    // `problems`.
+    fn from_entries(entries: &[(String, String)], problems: &mut Vec<String>) -> Keymap {
+        let mut bindings: HashMap<KeyChord, Action> = DEFAULT_BINDINGS
+            .iter()
+            .map(|(chord, action)| {
+                let chord = KeyChord::parse(chord).expect("default chords are valid");
+                (chord, *action)
+            })
+            .collect();
+        let mut bound = HashSet::new();
+        for (chord_text, action_name) in entries {
+            let chord = match KeyChord::parse(chord_text) {
+                Ok(chord) => chord,
+                Err(e) => {
+                    problems.push(format!("\"{}\": {}", chord_text, e));
+                    continue;
+                }
+            };
+            let action = if action_name.eq_ignore_ascii_case(UNBOUND) {
+                None
+            } else {
+                match Action::from_name(action_name) {
+                    Some(action) => Some(action),
+                    None => {
+                        problems.push(format!(
+                            "\"{}\": unknown action \"{}\"",
+                            chord_text, action_name
+                        ));
+                        continue;
+                    }
+                }
+            };
+            if !bound.insert(chord.clone()) {
+                problems.push(format!(
+                    "\"{}\" is bound more than once; keeping {}",
+                    chord_text,
+                    bindings.get(&chord).map_or(UNBOUND, |action| action.name())
+                ));
+                continue;
+            }
+            match action {
+                Some(action) => bindings.insert(chord, action),
+                None => bindings.remove(&chord),
+            };
+        }
+        Keymap { bindings }
+    }
+
+    fn defaults() -> Keymap {
+        Keymap::from_entries(&[], &mut Vec::new())
+    }
+
+    pub fn action(&self, key: &Key, modifiers: Modifiers) -> Option<Action> {
+        let chord = KeyChord::from_key_press(key, modifiers)?;
+        self.bindings.get(&chord).copied()
+    }
+}
+
+// The file's map, keeping its entries in order and with duplicates so that
+// chords bound twice can be reported
+struct BindingEntries(Vec<(String, String)>);
+
+impl<'de> Deserialize<'de> for BindingEntries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = BindingEntries;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map from key chords to action names")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry::<String, String>()? {
+                    entries.push(entry);
+                }
+                Ok(BindingEntries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+fn keybindings_file_path() -> Result<PathBuf, String> {
+    let xdg_dirs = BaseDirectories::with_prefix("file-manager")
+        .map_err(|e| format!("Failed to get XDG base directories: {}", e))?;
+    xdg_dirs
+        .place_config_file(KEYBINDINGS_FILE)
+        .map_err(|e| format!("Failed to place keybindings file: {}", e))
+}
+
+fn default_file_contents() -> String {
+    let actions: Vec<&str> = Action::ALL.iter().map(|action| action.name()).collect();
+    let mut contents = format!(
+        "// Key chords and the actions they run. Modifiers are Ctrl, Shift and Alt,\n\
+         // e.g. \"Ctrl+Shift+Z\"; keys are characters or one of {}.\n\
+         // Actions: {}\n\
+         // Chords left out keep their default action; use \"{}\" to turn one off.\n{{\n",
+        NAMED_KEYS
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(", "),
+        actions.join(", "),
+        UNBOUND
+    );
+    for (chord, action) in DEFAULT_BINDINGS {
+        contents.push_str(&format!("    \"{}\": \"{}\",\n", chord, action.name()));
+    }
+    contents.push_str("}\n");
+    contents
+}
+
+fn read_binding_entries(path: &PathBuf) -> Result<Vec<(String, String)>, String> {
+    if !path.exists() {
+        fs::write(path, default_file_contents())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        println!("Wrote default keybindings to {}", path.display());
+    }
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    ron::from_str::<BindingEntries>(&contents)
+        .map(|entries| entries.0)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+// Loads the keybindings file, falling back to the defaults if it is missing
+// or unreadable
+pub fn load() -> Keymap {
+    let entries = keybindings_file_path()
+        .and_then(|path| read_binding_entries(&path).map(|entries| (path, entries)));
+    match entries {
+        Ok((path, entries)) => {
+            let mut problems = Vec::new();
+            let keymap = Keymap::from_entries(&entries, &mut problems);
+            for problem in problems {
+                eprintln!("Skipping keybinding in {}: {}", path.display(), problem);
+            }
+            keymap
+        }
+        Err(e) => {
+            eprintln!("{}. Using the default keybindings.", e);
+            Keymap::defaults()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(chord, action)| (chord.to_string(), action.to_string()))
+            .collect()
+    }
+
+    fn chord(text: &str) -> KeyChord {
+        KeyChord::parse(text).unwrap()
+    }
+
+    #[test]
+    fn file_entries_apply_over_the_defaults() {
+        let mut problems = Vec::new();
+        let keymap = Keymap::from_entries(
+            &entries(&[("Ctrl+Y", "Redo"), ("Ctrl+Z", "Paste"), ("Ctrl+H", "None")]),
+            &mut problems,
+        );
+        assert!(problems.is_empty());
+        assert_eq!(keymap.bindings.get(&chord("Ctrl+Y")), Some(&Action::Redo));
+        assert_eq!(keymap.bindings.get(&chord("Ctrl+Z")), Some(&Action::Paste));
+        assert_eq!(keymap.bindings.get(&chord("Ctrl+H")), None);
+        // Not in the file, so still bound
+        assert_eq!(
+            keymap.bindings.get(&chord("Ctrl+F")),
+            Some(&Action::FocusSearch)
+        );
+        assert_eq!(
+            keymap.bindings.get(&chord("Ctrl+Shift+N")),
+            Some(&Action::NewFolder)
+        );
+    }
+
+    #[test]
+    fn invalid_and_repeated_entries_are_reported() {
+        let mut problems = Vec::new();
+        let keymap = Keymap::from_entries(
+            &entries(&[
+                ("Ctrl+J", "Undo"),
+                ("ctrl+j", "Redo"),
+                ("Hyper+K", "Undo"),
+                ("Ctrl+K", "Fly"),
+            ]),
+            &mut problems,
+        );
+        assert_eq!(
+            problems,
+            [
+                "\"ctrl+j\" is bound more than once; keeping Undo",
+                "\"Hyper+K\": unknown modifier \"Hyper\"",
+                "\"Ctrl+K\": unknown action \"Fly\"",
+            ]
+        );
+        assert_eq!(keymap.bindings.get(&chord("Ctrl+J")), Some(&Action::Undo));
+    }
+
+    #[test]
+    fn shift_only_counts_for_letters_and_named_keys() {
+        let keymap = Keymap::from_entries(
+            &entries(&[("Ctrl++", "ToggleHiddenFiles")]),
+            &mut Vec::new(),
+        );
+        let plus = Key::Character("+".into());
+        assert_eq!(
+            keymap.action(&plus, Modifiers::CTRL | Modifiers::SHIFT),
+            Some(Action::ToggleHiddenFiles)
+        );
+        let z = Key::Character("Z".into());
+        assert_eq!(
+            keymap.action(&z, Modifiers::CTRL | Modifiers::SHIFT),
+            Some(Action::Redo)
+        );
+        assert_eq!(keymap.action(&z, Modifiers::CTRL), Some(Action::Undo));
+        let tab = Key::Named(Named::Tab);
+        assert_eq!(
+            keymap.action(&tab, Modifiers::CTRL | Modifiers::SHIFT),
+            Some(Action::PreviousTab)
+        );
+    }
+}
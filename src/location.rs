@@ -0,0 +1,342 @@
+// Locations in the path bar: expanding and completing what is typed into it
+// (Ctrl+L), and listing the folders for the breadcrumbs' menus.
+use percent_encoding::percent_decode_str;
+use std::ffi::OsString;
+use std::fs;
+use std::os::unix::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+
+// Candidates listed under the field after an ambiguous completion
+const MAX_CANDIDATES: usize = 12;
+
+// The path bar while it is being edited
+#[derive(Debug, Clone, Default)]
+pub struct PathInput {
+    pub value: String,
+    // Why the typed location cannot be opened, after Enter
+    pub error: Option<String>,
+    // Folders the typed name could be completed to, after an ambiguous Tab
+    pub candidates: Vec<String>,
+}
+
+impl PathInput {
+    pub fn new(value: String) -> Self {
+        PathInput {
+            value,
+            ..PathInput::default()
+        }
+    }
+}
+
+// `~` is the home folder, `$NAME` and `${NAME}` are environment variables and
+// `file://` URIs are decoded. Relative paths start at `base`.
+pub fn expand(input: &str, base: &Path) -> Result<PathBuf, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Type a location".to_string());
+    }
+    if let Some(uri) = input.strip_prefix("file://") {
+        // An empty host or localhost, then an absolute path
+        let path = uri.strip_prefix("localhost").unwrap_or(uri);
+        if !path.starts_with('/') {
+            return Err(format!("Not a local file URI: {}", input));
+        }
+        let bytes: Vec<u8> = percent_decode_str(path).collect();
+        return Ok(PathBuf::from(OsString::from_vec(bytes)));
+    }
+
+    let expanded = expand_variables(input)?;
+    let path = if expanded == "~" {
+        home_dir()?
+    } else if let Some(rest) = expanded.strip_prefix("~/") {
+        home_dir()?.join(rest)
+    } else {
+        PathBuf::from(expanded)
+    };
+    Ok(if path.is_absolute() {
+        path
+    } else {
+        base.join(path)
+    })
+}
+
+fn home_dir() -> Result<PathBuf, String> {
+    dirs::home_dir().ok_or_else(|| "No home folder".to_string())
+}
+
+fn expand_variables(input: &str) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut rest = input;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, remaining) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => return Err(format!("Missing '}}' in {}", input)),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        if name.is_empty() {
+            // A lone `$` is part of the name
+            expanded.push('$');
+        } else {
+            let value = std::env::var(name).map_err(|_| format!("${} is not set", name))?;
+            expanded.push_str(&value);
+        }
+        rest = remaining;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+// The folder to open for `input`, or why it cannot be opened
+pub fn resolve(input: &str, base: &Path) -> Result<PathBuf, String> {
+    let path = expand(input, base)?;
+    match fs::metadata(&path) {
+        Ok(_) => Ok(path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err(format!("{} does not exist", path.display()))
+        }
+        Err(e) => Err(format!("Cannot open {}: {}", path.display(), e)),
+    }
+}
+
+// What a Tab press turns the input into, and the folders it could still be
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub value: String,
+    pub candidates: Vec<String>,
+}
+
+// Completes the last part of `input` to the names of the folders it starts.
+// With one such folder the name is finished with a `/`; with several it is
+// extended as far as they agree.
+pub async fn complete(input: String, base: PathBuf, show_hidden: bool) -> Option<Completion> {
+    tokio::task::spawn_blocking(move || find_completion(&input, &base, show_hidden))
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("Task join error: {}", e);
+            None
+        })
+}
+
+// Blocking implementation of `complete`
+fn find_completion(input: &str, base: &Path, show_hidden: bool) -> Option<Completion> {
+    if input == "~" {
+        return Some(Completion {
+            value: "~/".to_string(),
+            candidates: vec![],
+        });
+    }
+    let (folder, partial) = match input.rfind('/') {
+        Some(index) => input.split_at(index + 1),
+        None => ("", input),
+    };
+    let folder_path = if folder.is_empty() {
+        base.to_path_buf()
+    } else {
+        expand(folder, base).ok()?
+    };
+
+    let show_hidden = show_hidden || partial.starts_with('.');
+    let mut names: Vec<String> = fs::read_dir(folder_path)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| show_hidden || !name.starts_with('.'))
+        .collect();
+    // Matching the case typed, or any case if nothing does
+    let exact: Vec<String> = names
+        .iter()
+        .filter(|name| name.starts_with(partial))
+        .cloned()
+        .collect();
+    names = if exact.is_empty() {
+        let partial = partial.to_lowercase();
+        names
+            .into_iter()
+            .filter(|name| name.to_lowercase().starts_with(&partial))
+            .collect()
+    } else {
+        exact
+    };
+    names.sort();
+
+    match names.as_slice() {
+        [] => None,
+        [name] => Some(Completion {
+            value: format!("{}{}/", folder, name),
+            candidates: vec![],
+        }),
+        [first, others @ ..] => {
+            let common = others.iter().fold(first.as_str(), |common, name| {
+                let length = common
+                    .char_indices()
+                    .zip(name.chars())
+                    .take_while(|((_, a), b)| a == b)
+                    .last()
+                    .map_or(0, |((index, a), _)| index + a.len_utf8());
+                &common[..length]
+            });
+            // A case-insensitive match may agree on less than was typed
+            let completed = if common.len() >= partial.len() {
+                common
+            } else {
+                partial
+            };
+            let value = format!("{}{}", folder, completed);
+            names.truncate(MAX_CANDIDATES);
+            Some(Completion {
+                value,
+                candidates: names,
+            })
+        }
+    }
+}
+
+// The folders in `path` by name, for the breadcrumbs' drop-down menus
+pub async fn subfolders(path: PathBuf, show_hidden: bool) -> Vec<PathBuf> {
+    tokio::task::spawn_blocking(move || {
+        let mut folders: Vec<PathBuf> = match fs::read_dir(&path) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    show_hidden || !entry.file_name().as_encoded_bytes().starts_with(b".")
+                })
+                .map(|entry| entry.path())
+                .filter(|folder| folder.is_dir())
+                .collect(),
+            Err(e) => {
+                eprintln!("Failed to list folders in {}: {}", path.display(), e);
+                vec![]
+            }
+        };
+        folders.sort_by_cached_key(|folder| {
+            folder
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_lowercase()
+        });
+        folders
+    })
+    .await
+    .unwrap_or_else(|e| {
+        eprintln!("Task join error: {}", e);
+        vec![]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_dir::TempDir;
+
+    fn expanded(input: &str) -> Result<PathBuf, String> {
+        expand(input, Path::new("/base"))
+    }
+
+    #[test]
+    fn home_and_relative_paths() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expanded("~"), Ok(home.clone()));
+        assert_eq!(expanded(" ~/Music "), Ok(home.join("Music")));
+        // Only a leading `~` is the home folder
+        assert_eq!(expanded("a/~"), Ok(PathBuf::from("/base/a/~")));
+        assert_eq!(expanded("~other"), Ok(PathBuf::from("/base/~other")));
+        assert_eq!(expanded("/etc"), Ok(PathBuf::from("/etc")));
+        assert_eq!(expanded("   "), Err("Type a location".to_string()));
+    }
+
+    #[test]
+    fn environment_variables() {
+        std::env::set_var("LOCATION_TEST_DIR", "/srv/data");
+        assert_eq!(
+            expanded("$LOCATION_TEST_DIR"),
+            Ok(PathBuf::from("/srv/data"))
+        );
+        assert_eq!(
+            expanded("$LOCATION_TEST_DIR/a b"),
+            Ok(PathBuf::from("/srv/data/a b"))
+        );
+        assert_eq!(
+            expanded("${LOCATION_TEST_DIR}x"),
+            Ok(PathBuf::from("/srv/datax"))
+        );
+        assert_eq!(expanded("/price$"), Ok(PathBuf::from("/price$")));
+        assert_eq!(
+            expanded("$LOCATION_TEST_UNSET"),
+            Err("$LOCATION_TEST_UNSET is not set".to_string())
+        );
+        assert_eq!(
+            expanded("${LOCATION_TEST_DIR"),
+            Err("Missing '}' in ${LOCATION_TEST_DIR".to_string())
+        );
+    }
+
+    #[test]
+    fn file_uris() {
+        assert_eq!(
+            expanded("file:///home/me/My%20Files"),
+            Ok(PathBuf::from("/home/me/My Files"))
+        );
+        assert_eq!(
+            expanded("file://localhost/tmp/%E9t%C3%A9"),
+            Ok(PathBuf::from(OsString::from_vec(
+                b"/tmp/\xe9t\xc3\xa9".to_vec()
+            )))
+        );
+        // Neither `~` nor variables are expanded in a URI
+        assert_eq!(expanded("file:///~/$HOME"), Ok(PathBuf::from("/~/$HOME")));
+        assert_eq!(
+            expanded("file://server/share"),
+            Err("Not a local file URI: file://server/share".to_string())
+        );
+    }
+
+    #[test]
+    fn completion_extends_to_the_common_prefix() {
+        let temp = TempDir::new().unwrap();
+        for folder in ["Documents", "Downloads", "Desktop", ".dotfiles", "Music"] {
+            fs::create_dir(temp.path().join(folder)).unwrap();
+        }
+        fs::write(temp.path().join("Dolphin.txt"), "").unwrap();
+        let complete = |input: &str| find_completion(input, temp.path(), false);
+        let completion = |value: &str, candidates: &[&str]| {
+            Some(Completion {
+                value: value.to_string(),
+                candidates: candidates.iter().map(|c| c.to_string()).collect(),
+            })
+        };
+
+        // Files are not completed to
+        assert_eq!(
+            complete("Do"),
+            completion("Do", &["Documents", "Downloads"])
+        );
+        assert_eq!(complete("Doc"), completion("Documents/", &[]));
+        assert_eq!(
+            complete("D"),
+            completion("D", &["Desktop", "Documents", "Downloads"])
+        );
+        // Any case when nothing matches the case typed
+        assert_eq!(complete("mu"), completion("Music/", &[]));
+        // Hidden folders once their name is started
+        assert_eq!(complete("."), completion(".dotfiles/", &[]));
+        assert_eq!(complete("x"), None);
+
+        let folder = format!("{}/", temp.path().display());
+        assert_eq!(
+            complete(&format!("{}Dow", folder)),
+            completion(&format!("{}Downloads/", folder), &[])
+        );
+        assert_eq!(complete("~"), completion("~/", &[]));
+    }
+}
@@ -1,9 +1,26 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
 mod app;
+mod batch_rename;
+mod columns;
 mod constants;
+mod dialog;
+mod filter;
 mod fs_utils;
+mod index;
+mod jobs;
+mod keybindings;
+mod journal;
+mod location;
+mod open_with;
+mod pane;
+mod search;
+mod selection;
+mod tab;
+mod trash;
+mod type_ahead;
 mod ui;
+mod watcher;
 
 use crate::app::FileManager;
 use iced::font::{Family, Stretch, Style, Weight}; // Import necessary font traits
@@ -0,0 +1,72 @@
+// Applications that can open a file, read from the installed desktop entries
+use freedesktop_desktop_entry::{desktop_entries, get_languages_from_env, DesktopEntry};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::Command as StdCommand;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct App {
+    pub name: String,
+    pub desktop_file: PathBuf,
+}
+
+// Apps whose desktop entry lists the file's MIME type (or its "type/*"), by name
+pub async fn apps_for(path: PathBuf) -> Vec<App> {
+    tokio::task::spawn_blocking(move || {
+        let mime_type = mime_guess::from_path(&path).first_or_octet_stream();
+        let wildcard = format!("{}/*", mime_type.type_());
+        let locales = get_languages_from_env();
+
+        let mut names = HashSet::new();
+        let mut apps: Vec<App> = desktop_entries(&locales)
+            .into_iter()
+            .filter(|entry| {
+                entry.type_() == Some("Application") && !entry.no_display() && !entry.terminal()
+            })
+            .filter(|entry| {
+                entry.mime_type().is_some_and(|types| {
+                    types
+                        .iter()
+                        .any(|t| *t == mime_type.essence_str() || *t == wildcard)
+                })
+            })
+            .filter_map(|entry| {
+                let name = entry.name(&locales)?.into_owned();
+                Some(App {
+                    name,
+                    desktop_file: entry.path,
+                })
+            })
+            // The same app is often installed system-wide and as a flatpak
+            .filter(|app| names.insert(app.name.clone()))
+            .collect();
+        apps.sort_by_key(|app| app.name.to_lowercase());
+        apps
+    })
+    .await
+    .unwrap_or_default()
+}
+
+// Starts the app of `desktop_file` with `paths`, without waiting for it
+pub async fn launch(desktop_file: PathBuf, paths: Vec<PathBuf>) -> Result<(), String> {
+    let locales = get_languages_from_env();
+    let entry = DesktopEntry::from_path(&desktop_file, Some(&locales))
+        .map_err(|e| format!("Failed to read {}: {}", desktop_file.display(), e))?;
+    let paths: Vec<String> = paths
+        .iter()
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect();
+    let uris: Vec<&str> = paths.iter().map(String::as_str).collect();
+    let args = entry
+        .parse_exec_with_uris(&uris, &locales)
+        .map_err(|e| format!("Cannot start {}: {}", desktop_file.display(), e))?;
+
+    println!("Launching {:?}", args);
+    let mut child = StdCommand::new(&args[0])
+        .args(&args[1..])
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", args[0], e))?;
+    // Reaps the app once it exits
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use crate::tab::{Tab, TabId};
+
+// One side of the split view: its own tabs, one of which is shown. Without
+// the split view there is a single pane.
+#[derive(Debug)]
+pub struct Pane {
+    pub tabs: Vec<Tab>,
+    pub active_tab: usize,
+}
+
+impl Pane {
+    pub fn new(tab: Tab) -> Self {
+        Pane {
+            tabs: vec![tab],
+            active_tab: 0,
+        }
+    }
+
+    pub fn tab(&self) -> &Tab {
+        &self.tabs[self.active_tab]
+    }
+
+    pub fn tab_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.active_tab]
+    }
+
+    // Adds a tab right after the current one and shows it
+    pub fn insert_tab(&mut self, tab: Tab) {
+        self.tabs.insert(self.active_tab + 1, tab);
+        self.active_tab += 1;
+    }
+
+    // Returns false if that tab was already shown
+    pub fn select_tab(&mut self, index: usize) -> bool {
+        if index == self.active_tab || index >= self.tabs.len() {
+            return false;
+        }
+        self.active_tab = index;
+        true
+    }
+
+    pub fn select_next_tab(&mut self) -> bool {
+        self.select_tab((self.active_tab + 1) % self.tabs.len())
+    }
+
+    pub fn select_previous_tab(&mut self) -> bool {
+        self.select_tab((self.active_tab + self.tabs.len() - 1) % self.tabs.len())
+    }
+
+    // Closes a tab, keeping the last one open. Returns true if another tab is
+    // shown as a result.
+    pub fn close_tab(&mut self, tab_id: TabId) -> bool {
+        if self.tabs.len() == 1 {
+            return false;
+        }
+        let Some(index) = self.tabs.iter().position(|tab| tab.id == tab_id) else {
+            return false;
+        };
+        let was_shown = index == self.active_tab;
+        self.tabs.remove(index);
+        if index < self.active_tab || self.active_tab == self.tabs.len() {
+            self.active_tab -= 1;
+        }
+        was_shown
+    }
+}
@@ -0,0 +1,464 @@
+// Recursive search below the open folder, by name or by the text in files.
+//
+// A search walks the folder on its own thread and streams what it finds back
+// in batches, so results show up while the walk goes on. Dropping the
+// `Search` (a new query, leaving the folder, closing the tab) stops the walk.
+use crate::index::IndexReader;
+use iced::futures::channel::mpsc;
+use iced::widget::scrollable;
+use regex::{Regex, RegexBuilder};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use walkdir::{DirEntry, WalkDir};
+
+const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+// Files containing a NUL byte in their first bytes are taken as binary
+const SNIFF_LENGTH: usize = 8192;
+const MAX_TEXT_FILE_SIZE: u64 = 32 * 1024 * 1024;
+// Characters of a matching line kept before the match, and in all
+const SNIPPET_LEAD: usize = 60;
+const SNIPPET_LENGTH: usize = 200;
+// Lines shown on each side of the match in the text preview
+const PREVIEW_CONTEXT_LINES: usize = 500;
+// Stops there; a query matching more is too broad to be useful
+pub const MAX_HITS: usize = 5000;
+
+static NEXT_SEARCH_ID: AtomicUsize = AtomicUsize::new(0);
+
+pub type SearchId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    Prefix,
+    Substring,
+    Glob,
+    Fuzzy,
+    // Lines of text files containing the query, or matching it as a regex
+    Text,
+    TextRegex,
+}
+
+impl MatchMode {
+    pub const ALL: [MatchMode; 6] = [
+        MatchMode::Prefix,
+        MatchMode::Substring,
+        MatchMode::Glob,
+        MatchMode::Fuzzy,
+        MatchMode::Text,
+        MatchMode::TextRegex,
+    ];
+}
+
+impl std::fmt::Display for MatchMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MatchMode::Prefix => "Name starts with",
+            MatchMode::Substring => "Name",
+            MatchMode::Glob => "Name glob",
+            MatchMode::Fuzzy => "Name fuzzy",
+            MatchMode::Text => "Text",
+            MatchMode::TextRegex => "Text regex",
+        })
+    }
+}
+
+// Tells whether a name matches the query and how well (higher is better), or
+// finds the query in lines of text
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    Prefix(String),
+    Substring(String),
+    Glob(Regex),
+    Fuzzy(Vec<char>),
+    Text(Regex),
+}
+
+impl Matcher {
+    // Names and plain text are compared case-insensitively; a text regex is
+    // used as written
+    pub fn new(query: &str, mode: MatchMode) -> Result<Self, String> {
+        Ok(match mode {
+            MatchMode::Prefix => Matcher::Prefix(query.to_lowercase()),
+            MatchMode::Substring => Matcher::Substring(query.to_lowercase()),
+            MatchMode::Glob => Matcher::Glob(
+                Regex::new(&glob_to_regex(query)).map_err(|e| format!("Invalid pattern: {}", e))?,
+            ),
+            MatchMode::Fuzzy => Matcher::Fuzzy(query.to_lowercase().chars().collect()),
+            MatchMode::Text => Matcher::Text(
+                RegexBuilder::new(&regex::escape(query))
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| format!("Invalid pattern: {}", e))?,
+            ),
+            MatchMode::TextRegex => {
+                Matcher::Text(Regex::new(query).map_err(|e| format!("Invalid regex: {}", e))?)
+            }
+        })
+    }
+
+    pub fn score(&self, name: &str) -> Option<i64> {
+        self.score_lowercase(&name.to_lowercase())
+    }
+
+    // For names that are already lowercase, as in the search index
+    pub fn score_lowercase(&self, name: &str) -> Option<i64> {
+        match self {
+            Matcher::Prefix(query) => name.starts_with(query.as_str()).then_some(0),
+            Matcher::Substring(query) => name.contains(query.as_str()).then_some(0),
+            Matcher::Glob(regex) => regex.is_match(name).then_some(0),
+            Matcher::Fuzzy(query) => fuzzy_score(query, name),
+            Matcher::Text(_) => None,
+        }
+    }
+}
+
+// `*` is any run of characters, `?` any one character and `[...]` a set, as in
+// the shell; the whole name has to match
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("(?i)^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                let mut set = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ']' && !set.is_empty() {
+                        closed = true;
+                        break;
+                    }
+                    set.push(c);
+                }
+                if closed {
+                    regex.push('[');
+                    let set = match set.strip_prefix('!') {
+                        Some(rest) => {
+                            regex.push('^');
+                            rest.to_string()
+                        }
+                        None => set,
+                    };
+                    regex.push_str(&set.replace('\\', "\\\\").replace('[', "\\["));
+                    regex.push(']');
+                } else {
+                    regex.push_str(&regex::escape(&format!("[{}", set)));
+                }
+            }
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+// The query's characters have to appear in the name in order. Runs of
+// adjacent characters and matches at the start of words score higher, and
+// shorter names win ties.
+fn fuzzy_score(query: &[char], name: &str) -> Option<i64> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut score = 0;
+    let mut matched = 0;
+    let mut previous: Option<usize> = None;
+    for (i, c) in chars.iter().enumerate() {
+        if matched == query.len() {
+            break;
+        }
+        if *c != query[matched] {
+            continue;
+        }
+        score += 1;
+        if i > 0 && previous == Some(i - 1) {
+            score += 5;
+        }
+        if i == 0 || !chars[i - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(i);
+        matched += 1;
+    }
+    (matched == query.len()).then(|| score * 100 - chars.len() as i64)
+}
+
+// A line of a file that contains the query
+#[derive(Debug, Clone)]
+pub struct LineMatch {
+    // Counted from 1
+    pub line: usize,
+    // Part of the line around the match, and where the match is in it
+    pub snippet: String,
+    pub highlight: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub path: PathBuf,
+    // Path below the folder the search started in
+    pub relative: PathBuf,
+    pub is_dir: bool,
+    pub score: i64,
+    // Set for text searches, one hit per matching line
+    pub line_match: Option<LineMatch>,
+}
+
+// The lines around a match, for the preview next to the results
+#[derive(Debug, Clone)]
+pub struct TextPreview {
+    pub path: PathBuf,
+    pub line: usize,
+    // Number of the first line in `lines`
+    pub first_line: usize,
+    pub lines: Vec<String>,
+}
+
+impl TextPreview {
+    pub async fn load(path: PathBuf, line: usize) -> Result<TextPreview, String> {
+        tokio::task::spawn_blocking(move || {
+            let bytes =
+                fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let first_line = line.saturating_sub(PREVIEW_CONTEXT_LINES).max(1);
+            let lines = String::from_utf8_lossy(&bytes)
+                .lines()
+                .skip(first_line - 1)
+                .take(line + PREVIEW_CONTEXT_LINES + 1 - first_line)
+                .map(|line| line.replace('\t', "    "))
+                .collect();
+            Ok(TextPreview {
+                path,
+                line,
+                first_line,
+                lines,
+            })
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SearchEvent {
+    Found(Vec<SearchHit>),
+    // The walk is over; `truncated` if it stopped at MAX_HITS
+    Finished { truncated: bool },
+}
+
+#[derive(Debug)]
+pub struct Search {
+    pub id: SearchId,
+    pub query: String,
+    pub root: PathBuf,
+    // Best matches first, otherwise in the order they were found
+    pub hits: Vec<SearchHit>,
+    pub running: bool,
+    pub truncated: bool,
+    pub error: Option<String>,
+    // The file of the text match that was clicked last
+    pub preview: Option<Result<TextPreview, String>>,
+    // Answered from the search index rather than by walking the folder
+    pub from_index: bool,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Search {
+    // Starts walking `root` for names matching `query`. Hidden items (and
+    // everything inside hidden folders) are skipped unless `show_hidden`.
+    pub fn start(
+        root: PathBuf,
+        query: String,
+        mode: MatchMode,
+        show_hidden: bool,
+        index: Option<IndexReader>,
+    ) -> (Self, Option<mpsc::UnboundedReceiver<SearchEvent>>) {
+        let mut search = Search {
+            id: NEXT_SEARCH_ID.fetch_add(1, Ordering::Relaxed),
+            query: query.clone(),
+            root: root.clone(),
+            hits: vec![],
+            running: false,
+            truncated: false,
+            error: None,
+            preview: None,
+            from_index: false,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        };
+        let matcher = match Matcher::new(&query, mode) {
+            Ok(matcher) => matcher,
+            Err(e) => {
+                search.error = Some(e);
+                return (search, None);
+            }
+        };
+
+        let (sender, receiver) = mpsc::unbounded();
+        match index.filter(|_| !matches!(matcher, Matcher::Text(_))) {
+            // Names are looked up in the index instead of on disk
+            Some(index) => {
+                search.from_index = true;
+                thread::spawn(move || {
+                    let (hits, truncated) = index.query(&root, &matcher, show_hidden);
+                    let _ = sender.unbounded_send(SearchEvent::Found(hits));
+                    let _ = sender.unbounded_send(SearchEvent::Finished { truncated });
+                });
+            }
+            None => {
+                println!("Searching {} for '{}' ({})", root.display(), query, mode);
+                let cancelled = search.cancelled.clone();
+                thread::spawn(move || walk(&root, &matcher, show_hidden, &cancelled, &sender));
+            }
+        }
+        search.running = true;
+        (search, Some(receiver))
+    }
+
+    // Id of the text preview's scrollable, scrolled to the clicked line
+    pub fn preview_id(&self) -> scrollable::Id {
+        scrollable::Id::new(format!("search-preview-{}", self.id))
+    }
+
+    pub fn apply(&mut self, event: SearchEvent) {
+        match event {
+            SearchEvent::Found(hits) => {
+                for hit in hits {
+                    let index = self.hits.partition_point(|h| h.score >= hit.score);
+                    self.hits.insert(index, hit);
+                }
+            }
+            SearchEvent::Finished { truncated } => {
+                self.running = false;
+                self.truncated = truncated;
+            }
+        }
+    }
+}
+
+impl Drop for Search {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+fn is_hidden(name: &std::ffi::OsStr) -> bool {
+    name.to_string_lossy().starts_with('.')
+}
+
+fn walk(
+    root: &Path,
+    matcher: &Matcher,
+    show_hidden: bool,
+    cancelled: &AtomicBool,
+    sender: &mpsc::UnboundedSender<SearchEvent>,
+) {
+    let mut batch = Vec::new();
+    let mut last_sent = Instant::now();
+    let mut found = 0;
+    let entries = WalkDir::new(root)
+        .min_depth(1)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| show_hidden || !is_hidden(entry.file_name()));
+
+    for entry in entries {
+        if cancelled.load(Ordering::Relaxed) {
+            return;
+        }
+        // Folders that cannot be read are skipped
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let hit = |score, line_match| SearchHit {
+            relative: entry
+                .path()
+                .strip_prefix(root)
+                .unwrap_or(entry.path())
+                .to_path_buf(),
+            path: entry.path().to_path_buf(),
+            is_dir: entry.file_type().is_dir(),
+            score,
+            line_match,
+        };
+        let hits: Vec<SearchHit> = match matcher {
+            Matcher::Text(regex) => find_in_file(&entry, regex)
+                .into_iter()
+                .map(|line_match| hit(0, Some(line_match)))
+                .collect(),
+            _ => matcher
+                .score(&entry.file_name().to_string_lossy())
+                .map(|score| hit(score, None))
+                .into_iter()
+                .collect(),
+        };
+        for hit in hits {
+            batch.push(hit);
+            found += 1;
+            if found >= MAX_HITS {
+                let _ = sender.unbounded_send(SearchEvent::Found(batch));
+                let _ = sender.unbounded_send(SearchEvent::Finished { truncated: true });
+                return;
+            }
+        }
+        if !batch.is_empty() && last_sent.elapsed() >= BATCH_INTERVAL {
+            if sender
+                .unbounded_send(SearchEvent::Found(std::mem::take(&mut batch)))
+                .is_err()
+            {
+                return;
+            }
+            last_sent = Instant::now();
+        }
+    }
+    let _ = sender.unbounded_send(SearchEvent::Found(batch));
+    let _ = sender.unbounded_send(SearchEvent::Finished { truncated: false });
+}
+
+// The lines of a text file matching `regex`. Folders, special files, big files
+// and binaries (a NUL byte near the start) have none.
+fn find_in_file(entry: &DirEntry, regex: &Regex) -> Vec<LineMatch> {
+    if !entry.file_type().is_file()
+        || entry
+            .metadata()
+            .map_or(true, |meta| meta.len() > MAX_TEXT_FILE_SIZE)
+    {
+        return vec![];
+    }
+    let Ok(bytes) = fs::read(entry.path()) else {
+        return vec![];
+    };
+    if bytes[..bytes.len().min(SNIFF_LENGTH)].contains(&0) {
+        return vec![];
+    }
+    String::from_utf8_lossy(&bytes)
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let found = regex.find(line)?;
+            Some(snippet(line, found.range(), index + 1))
+        })
+        .collect()
+}
+
+// Cuts a long line down to the part around the match, without leading spaces
+fn snippet(line: &str, found: Range<usize>, number: usize) -> LineMatch {
+    let indent = line.len() - line.trim_start().len();
+    let lead_start = line[..found.start]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_LEAD - 1)
+        .map_or(0, |(i, _)| i);
+    let start = lead_start.max(indent.min(found.start));
+    let end = line[start..]
+        .char_indices()
+        .nth(SNIPPET_LENGTH)
+        .map_or(line.len(), |(i, _)| start + i)
+        .max(found.end);
+    LineMatch {
+        line: number,
+        snippet: line[start..end].replace('\t', " "),
+        highlight: found.start - start..found.end - start,
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+// The set of selected items in the current folder.
+//
+// `anchor` is where a Shift-click range starts; it moves with every plain or
+// Ctrl-click. `lead` is the item clicked last and is what single-item views
+// (details, preview) show.
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    paths: HashSet<PathBuf>,
+    anchor: Option<PathBuf>,
+    lead: Option<PathBuf>,
+}
+
+impl Selection {
+    pub fn contains(&self, path: &Path) -> bool {
+        self.paths.contains(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn lead(&self) -> Option<&PathBuf> {
+        self.lead.as_ref()
+    }
+
+    // The only selected item, if exactly one is selected
+    pub fn single(&self) -> Option<&PathBuf> {
+        if self.paths.len() == 1 {
+            self.paths.iter().next()
+        } else {
+            None
+        }
+    }
+
+    // Selected paths in the order they appear in `order`
+    pub fn ordered<'a>(&'a self, order: impl IntoIterator<Item = &'a PathBuf>) -> Vec<PathBuf> {
+        order
+            .into_iter()
+            .filter(|path| self.paths.contains(*path))
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.anchor = None;
+        self.lead = None;
+    }
+
+    pub fn select_only(&mut self, path: PathBuf) {
+        self.paths.clear();
+        self.paths.insert(path.clone());
+        self.anchor = Some(path.clone());
+        self.lead = Some(path);
+    }
+
+    // Ctrl-click: adds or removes a single item, keeping the rest
+    pub fn toggle(&mut self, path: PathBuf) {
+        if !self.paths.remove(&path) {
+            self.paths.insert(path.clone());
+        }
+        self.anchor = Some(path.clone());
+        self.lead = self.paths.contains(&path).then_some(path);
+    }
+
+    // Shift-click: selects everything between the anchor and `path` in `order`.
+    // With `extend` (Ctrl+Shift) the range is added to the existing selection.
+    pub fn select_range(&mut self, path: PathBuf, order: &[PathBuf], extend: bool) {
+        let anchor = self.anchor.clone().unwrap_or_else(|| path.clone());
+        let anchor_index = order.iter().position(|p| *p == anchor);
+        let target_index = order.iter().position(|p| *p == path);
+
+        if !extend {
+            self.paths.clear();
+        }
+        match (anchor_index, target_index) {
+            (Some(a), Some(b)) => {
+                let (start, end) = if a <= b { (a, b) } else { (b, a) };
+                self.paths.extend(order[start..=end].iter().cloned());
+            }
+            _ => {
+                self.paths.insert(path.clone());
+                self.anchor = Some(path.clone());
+            }
+        }
+        self.lead = Some(path);
+    }
+
+    // Replaces the selection, e.g. with the items inside a rubber band
+    pub fn set(&mut self, paths: impl IntoIterator<Item = PathBuf>, extend: bool) {
+        if !extend {
+            self.paths.clear();
+        }
+        let mut last = None;
+        for path in paths {
+            self.paths.insert(path.clone());
+            last = Some(path);
+        }
+        if last.is_some() {
+            self.anchor = last.clone();
+            self.lead = last;
+        } else if !extend {
+            self.anchor = None;
+            self.lead = None;
+        }
+    }
+
+    pub fn select_all<'a>(&mut self, all: impl IntoIterator<Item = &'a PathBuf>) {
+        self.paths = all.into_iter().cloned().collect();
+        if !self
+            .lead
+            .as_ref()
+            .is_some_and(|lead| self.paths.contains(lead))
+        {
+            self.lead = None;
+        }
+    }
+
+    pub fn invert<'a>(&mut self, all: impl IntoIterator<Item = &'a PathBuf>) {
+        self.paths = all
+            .into_iter()
+            .filter(|path| !self.paths.contains(*path))
+            .cloned()
+            .collect();
+        self.anchor = None;
+        self.lead = None;
+    }
+
+    // Drops items that are no longer present, e.g. after the folder was reloaded
+    pub fn retain(&mut self, existing: &HashSet<&PathBuf>) {
+        self.paths.retain(|path| existing.contains(path));
+        if !self.anchor.as_ref().is_some_and(|p| existing.contains(p)) {
+            self.anchor = None;
+        }
+        if !self.lead.as_ref().is_some_and(|p| self.paths.contains(p)) {
+            self.lead = None;
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use crate::app::{group_of, GroupCriteria, SortCriteria, SortOrder, ViewMode};
+use crate::filter::EntryFilter;
+use crate::fs_utils::{compare_entries, DirEntry};
+use crate::search::Search;
+use crate::selection::Selection;
+use crate::type_ahead::{fold, TypeAhead};
+use iced::widget::scrollable::{self, AbsoluteOffset};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::Instant;
+
+pub type TabId = usize;
+
+// One open location: its folder, history, selection and view settings.
+// Everything that is shared between tabs (clipboard, jobs, journal, trash
+// listing) stays on `FileManager`.
+#[derive(Debug)]
+pub struct Tab {
+    pub id: TabId,
+    pub current_path: PathBuf,
+    pub entries: Vec<DirEntry>,
+    pub error: Option<String>,
+    pub selection: Selection,
+    history: Vec<PathBuf>,
+    history_index: usize,
+    pub sort_criteria: SortCriteria,
+    pub sort_order: SortOrder,
+    pub group_criteria: GroupCriteria,
+    pub view_mode: ViewMode,
+    // First folder of the column view and the items of the folders between it
+    // and `current_path`
+    pub column_root: PathBuf,
+    pub parent_columns: Vec<(PathBuf, Vec<DirEntry>)>,
+    pub collapsed_groups: HashSet<String>,
+    pub renaming_path: Option<PathBuf>,
+    pub rename_input_value: String,
+    pub showing_trash: bool,
+    pub scroll_offset: AbsoluteOffset,
+    pub last_click_time: Option<Instant>,
+    pub last_clicked_path: Option<PathBuf>,
+    // Shown instead of the folder's items while it is set
+    pub search: Option<Search>,
+    // The filter field: only the items matching the parsed query are shown
+    pub filter_query: String,
+    pub filter: Option<EntryFilter>,
+    pub filter_error: Option<String>,
+    pub type_ahead: TypeAhead,
+}
+
+impl Tab {
+    pub fn new(
+        id: TabId,
+        path: PathBuf,
+        sort_criteria: SortCriteria,
+        sort_order: SortOrder,
+        group_criteria: GroupCriteria,
+        view_mode: ViewMode,
+    ) -> Self {
+        Tab {
+            id,
+            current_path: path.clone(),
+            column_root: path.clone(),
+            parent_columns: vec![],
+            entries: vec![],
+            error: None,
+            selection: Selection::default(),
+            history: vec![path],
+            history_index: 0,
+            sort_criteria,
+            sort_order,
+            group_criteria,
+            view_mode,
+            collapsed_groups: HashSet::new(),
+            renaming_path: None,
+            rename_input_value: String::new(),
+            showing_trash: false,
+            scroll_offset: AbsoluteOffset::default(),
+            last_click_time: None,
+            last_clicked_path: None,
+            search: None,
+            filter_query: String::new(),
+            filter: None,
+            filter_error: None,
+            type_ahead: TypeAhead::default(),
+        }
+    }
+
+    // The label shown on the tab: the folder name, or "/" for the root
+    pub fn title(&self) -> String {
+        if self.showing_trash {
+            return "Trash".to_string();
+        }
+        self.current_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.current_path.display().to_string())
+    }
+
+    // Id of the file grid's scrollable, used to restore the scroll position
+    pub fn scrollable_id(&self) -> scrollable::Id {
+        scrollable::Id::new(format!("file-grid-{}", self.id))
+    }
+
+    // Switches to `path` without touching the history
+    pub fn open(&mut self, path: PathBuf) {
+        // The column view starts over when leaving the folders below its root
+        if !path.starts_with(&self.column_root) {
+            self.column_root = path.clone();
+        }
+        self.showing_trash = false;
+        self.current_path = path;
+        self.error = None;
+        self.selection.clear();
+        self.cancel_rename();
+        self.search = None;
+        self.set_filter(String::new());
+        self.type_ahead.clear();
+        self.scroll_offset = AbsoluteOffset::default();
+    }
+
+    // Shows only the items matching `query`. A query that does not parse shows
+    // every item along with the error.
+    pub fn set_filter(&mut self, query: String) {
+        (self.filter, self.filter_error) = if query.trim().is_empty() {
+            (None, None)
+        } else {
+            match EntryFilter::parse(&query) {
+                Ok(filter) => (Some(filter), None),
+                Err(e) => (None, Some(e)),
+            }
+        };
+        self.filter_query = query;
+        self.deselect_hidden();
+    }
+
+    // Items filtered out are not acted on
+    pub fn deselect_hidden(&mut self) {
+        let shown: HashSet<PathBuf> = self.shown_entries().map(|e| e.path.clone()).collect();
+        self.selection.retain(&shown.iter().collect());
+    }
+
+    pub fn is_shown(&self, entry: &DirEntry) -> bool {
+        self.filter.as_ref().is_none_or(|filter| filter.matches(entry))
+            && self.type_ahead.allows(&entry.display_name)
+    }
+
+    // The first shown item whose name starts with the typed text, or else
+    // contains it
+    pub fn type_ahead_match(&self) -> Option<PathBuf> {
+        let typed = fold(&self.type_ahead.text);
+        if typed.is_empty() {
+            return None;
+        }
+        let display_names: HashMap<&PathBuf, &str> = self
+            .entries
+            .iter()
+            .map(|e| (&e.path, e.display_name.as_str()))
+            .collect();
+        let names: Vec<(PathBuf, String)> = self
+            .visible_paths()
+            .into_iter()
+            .filter_map(|path| {
+                let name = fold(display_names.get(&path)?);
+                Some((path, name))
+            })
+            .collect();
+        names
+            .iter()
+            .find(|(_, name)| name.starts_with(&typed))
+            .or_else(|| names.iter().find(|(_, name)| name.contains(&typed)))
+            .map(|(path, _)| path.clone())
+    }
+
+    // The folder's items that pass the filter
+    pub fn shown_entries(&self) -> impl Iterator<Item = &DirEntry> {
+        self.entries.iter().filter(|e| self.is_shown(e))
+    }
+
+    // Folders shown to the left of the current one in the column view, from
+    // the column root down to the parent of `current_path`
+    pub fn column_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self
+            .current_path
+            .ancestors()
+            .skip(1)
+            .take_while(|path| path.starts_with(&self.column_root))
+            .map(PathBuf::from)
+            .collect();
+        paths.reverse();
+        paths
+    }
+
+    pub fn cancel_rename(&mut self) {
+        self.renaming_path = None;
+        self.rename_input_value.clear();
+    }
+
+    pub fn update_history(&mut self, new_path: PathBuf) {
+        self.cancel_rename();
+
+        if self.history_index < self.history.len() - 1 {
+            self.history.truncate(self.history_index + 1);
+        }
+        if self.history.last() != Some(&new_path) {
+            self.history.push(new_path);
+        }
+        self.history_index = self.history.len() - 1;
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.showing_trash || self.history_index > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.history_index < self.history.len() - 1
+    }
+
+    // Steps back in the history and returns the folder to show
+    pub fn history_back(&mut self) -> Option<PathBuf> {
+        if self.history_index == 0 {
+            return None;
+        }
+        self.history_index -= 1;
+        Some(self.history[self.history_index].clone())
+    }
+
+    pub fn history_forward(&mut self) -> Option<PathBuf> {
+        if !self.can_go_forward() {
+            return None;
+        }
+        self.history_index += 1;
+        Some(self.history[self.history_index].clone())
+    }
+
+    // Paths in the order they are shown, skipping collapsed groups. Shift-click
+    // ranges and Select All work on this order.
+    pub fn visible_paths(&self) -> Vec<PathBuf> {
+        let mut visible: Vec<&DirEntry> = self
+            .shown_entries()
+            .filter(|e| {
+                group_of(e, self.group_criteria)
+                    .is_none_or(|(id, _)| !self.collapsed_groups.contains(&id))
+            })
+            .collect();
+        if self.group_criteria == GroupCriteria::MimeType {
+            // Groups other than folders are shown by name
+            visible.sort_by_key(|e| (!e.is_dir, group_of(e, self.group_criteria)));
+        }
+        visible.into_iter().map(|e| e.path.clone()).collect()
+    }
+
+    pub fn selected_entries(&self) -> Vec<&DirEntry> {
+        self.entries
+            .iter()
+            .filter(|e| self.selection.contains(&e.path))
+            .collect()
+    }
+
+    pub fn is_renaming(&self, path: &PathBuf) -> bool {
+        self.renaming_path.as_ref() == Some(path)
+    }
+
+    // Updates the changed items in place, keeping the sort order, the selection
+    // and the scroll position
+    pub fn apply_reloaded_entries(&mut self, reloaded: &[(PathBuf, Option<DirEntry>)]) {
+        for (path, entry) in reloaded {
+            self.entries.retain(|e| e.path != *path);
+            if let Some(entry) = entry {
+                let index = self.entries.partition_point(|e| {
+                    compare_entries(
+                        e,
+                        entry,
+                        self.sort_criteria,
+                        self.sort_order,
+                        self.group_criteria,
+                    )
+                    .is_le()
+                });
+                self.entries.insert(index, entry.clone());
+            }
+        }
+
+        let existing: HashSet<&PathBuf> = self.entries.iter().map(|e| &e.path).collect();
+        self.selection.retain(&existing);
+        if self
+            .renaming_path
+            .as_ref()
+            .is_some_and(|path| !existing.contains(path))
+        {
+            self.cancel_rename();
+        }
+    }
+}
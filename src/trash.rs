@@ -0,0 +1,654 @@
+// Trash support following the freedesktop.org Trash specification:
+// https://specifications.freedesktop.org/trash-spec/trashspec-latest.html
+use crate::fs_utils::{item_size, unique_destination};
+use chrono::{Local, NaiveDateTime};
+use percent_encoding::{percent_decode_str, percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::ffi::OsString;
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use xdg::BaseDirectories;
+
+// Characters left unescaped in the `Path=` key (RFC 2396 unreserved + '/')
+const TRASH_PATH_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~')
+    .remove(b'!')
+    .remove(b'*')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')');
+
+pub const TRASH_INFO_EXTENSION: &str = "trashinfo";
+const TRASH_INFO_HEADER: &str = "[Trash Info]";
+const TRASH_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+// A trash directory together with the location its `Path=` keys are relative to.
+// The home trash stores absolute paths, per-mount trashes store paths relative
+// to the top directory of the mount.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashDir {
+    pub root: PathBuf,
+    pub topdir: Option<PathBuf>,
+}
+
+impl TrashDir {
+    pub fn files_dir(&self) -> PathBuf {
+        self.root.join("files")
+    }
+
+    pub fn info_dir(&self) -> PathBuf {
+        self.root.join("info")
+    }
+
+    fn ensure_exists(&self) -> io::Result<()> {
+        let mut builder = DirBuilder::new();
+        builder.recursive(true).mode(0o700);
+        builder.create(self.files_dir())?;
+        builder.create(self.info_dir())
+    }
+}
+
+// Result of moving an item to the trash
+#[derive(Debug, Clone)]
+pub struct TrashedItem {
+    pub original_path: PathBuf,
+    pub trashed_path: PathBuf,
+    pub info_path: PathBuf,
+}
+
+pub fn home_trash_dir() -> Result<TrashDir, String> {
+    let xdg_dirs =
+        BaseDirectories::new().map_err(|e| format!("Failed to get XDG base directories: {}", e))?;
+    Ok(TrashDir {
+        root: xdg_dirs.get_data_home().join("Trash"),
+        topdir: None,
+    })
+}
+
+fn current_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail
+    unsafe { libc::getuid() }
+}
+
+// Walks up from `path` to the top directory of the filesystem it lives on
+fn find_mount_topdir(path: &Path, device: u64) -> PathBuf {
+    let mut topdir = path.to_path_buf();
+    while let Some(parent) = topdir.parent() {
+        match fs::metadata(parent) {
+            Ok(meta) if meta.dev() == device => topdir = parent.to_path_buf(),
+            _ => break,
+        }
+    }
+    topdir
+}
+
+// Picks `$topdir/.Trash/$uid` if the administrator provided a valid shared
+// `.Trash` (a real directory with the sticky bit), otherwise `$topdir/.Trash-$uid`
+fn topdir_trash_dir(topdir: &Path) -> Result<TrashDir, String> {
+    let uid = current_uid();
+    let shared = topdir.join(".Trash");
+    if let Ok(meta) = fs::symlink_metadata(&shared) {
+        let is_valid = meta.is_dir() && meta.permissions().mode() & 0o1000 != 0;
+        if is_valid {
+            let candidate = TrashDir {
+                root: shared.join(uid.to_string()),
+                topdir: Some(topdir.to_path_buf()),
+            };
+            if candidate.ensure_exists().is_ok() {
+                return Ok(candidate);
+            }
+        } else {
+            eprintln!(
+                "Ignoring invalid shared trash directory {} (not a directory or sticky bit unset)",
+                shared.display()
+            );
+        }
+    }
+
+    let candidate = TrashDir {
+        root: topdir.join(format!(".Trash-{}", uid)),
+        topdir: Some(topdir.to_path_buf()),
+    };
+    candidate.ensure_exists().map_err(|e| {
+        format!(
+            "Failed to create trash directory {}: {}",
+            candidate.root.display(),
+            e
+        )
+    })?;
+    Ok(candidate)
+}
+
+// Resolves the trash directory that should receive `path`, which must be absolute
+pub fn trash_dir_for(path: &Path) -> Result<TrashDir, String> {
+    let home_trash = home_trash_dir()?;
+    home_trash.ensure_exists().map_err(|e| {
+        format!(
+            "Failed to create trash directory {}: {}",
+            home_trash.root.display(),
+            e
+        )
+    })?;
+
+    let item_device = fs::symlink_metadata(path)
+        .map_err(|e| format!("Failed to read metadata of '{}': {}", path.display(), e))?
+        .dev();
+    let home_device = fs::metadata(&home_trash.root)
+        .map_err(|e| {
+            format!(
+                "Failed to read metadata of '{}': {}",
+                home_trash.root.display(),
+                e
+            )
+        })?
+        .dev();
+
+    if item_device == home_device {
+        return Ok(home_trash);
+    }
+
+    let parent = path.parent().unwrap_or(path);
+    let topdir = find_mount_topdir(parent, item_device);
+    topdir_trash_dir(&topdir)
+}
+
+// Builds the value of the `Path=` key for an item going into `trash_dir`.
+// The bytes of the path are encoded as they are, whatever their encoding.
+fn encode_original_path(trash_dir: &TrashDir, original: &Path) -> String {
+    let relative = trash_dir
+        .topdir
+        .as_ref()
+        .and_then(|topdir| original.strip_prefix(topdir).ok())
+        .unwrap_or(original);
+    percent_encode(relative.as_os_str().as_bytes(), TRASH_PATH_ENCODE_SET).to_string()
+}
+
+// Creates the `.trashinfo` file with O_EXCL so that the chosen name is reserved
+// before anything is moved. Returns the name used inside `files/`.
+fn reserve_trash_name(
+    trash_dir: &TrashDir,
+    original: &Path,
+) -> Result<(OsString, PathBuf), String> {
+    let file_name = original
+        .file_name()
+        .ok_or_else(|| "Could not get file/folder name to trash.".to_string())?;
+    let stem = Path::new(file_name).file_stem().unwrap_or(file_name);
+    let extension = Path::new(file_name).extension();
+
+    let contents = format!(
+        "{}\nPath={}\nDeletionDate={}\n",
+        TRASH_INFO_HEADER,
+        encode_original_path(trash_dir, original),
+        Local::now().format(TRASH_DATE_FORMAT)
+    );
+
+    let info_dir = trash_dir.info_dir();
+    let files_dir = trash_dir.files_dir();
+    for counter in 1.. {
+        let candidate = if counter == 1 {
+            file_name.to_os_string()
+        } else {
+            let mut candidate = stem.to_os_string();
+            candidate.push(format!(".{}", counter));
+            if let Some(extension) = extension {
+                candidate.push(".");
+                candidate.push(extension);
+            }
+            candidate
+        };
+        // A stray entry in files/ without its info file also blocks the name
+        if fs::symlink_metadata(files_dir.join(&candidate)).is_ok() {
+            continue;
+        }
+        let mut info_name = candidate.clone();
+        info_name.push(format!(".{}", TRASH_INFO_EXTENSION));
+        let info_path = info_dir.join(info_name);
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&info_path)
+        {
+            Ok(mut file) => {
+                if let Err(e) = file.write_all(contents.as_bytes()) {
+                    let _ = fs::remove_file(&info_path);
+                    return Err(format!(
+                        "Failed to write trash info file {}: {}",
+                        info_path.display(),
+                        e
+                    ));
+                }
+                return Ok((candidate, info_path));
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => {
+                return Err(format!(
+                    "Failed to create trash info file {}: {}",
+                    info_path.display(),
+                    e
+                ))
+            }
+        }
+    }
+    unreachable!("trash name counter exhausted")
+}
+
+// Blocking implementation of `trash_item`, for callers already off the UI thread
+pub fn move_to_trash(path: &Path) -> Result<TrashedItem, String> {
+    // Resolve the parent only, so that a trashed symlink is the link itself
+    let original_path = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve '{}': {}", parent.display(), e))?
+            .join(name),
+        _ => return Err("Cannot move root directory to the trash.".to_string()),
+    };
+
+    let trash_dir = trash_dir_for(&original_path)?;
+    if original_path.starts_with(&trash_dir.root) {
+        return Err(format!(
+            "'{}' is already in the trash.",
+            original_path.display()
+        ));
+    }
+
+    let (trash_name, info_path) = reserve_trash_name(&trash_dir, &original_path)?;
+    let trashed_path = trash_dir.files_dir().join(&trash_name);
+
+    // Both directories live on the item's filesystem, so this never crosses devices
+    if let Err(e) = fs::rename(&original_path, &trashed_path) {
+        let _ = fs::remove_file(&info_path);
+        return Err(format!(
+            "Failed to move '{}' to the trash: {}",
+            original_path.display(),
+            e
+        ));
+    }
+
+    Ok(TrashedItem {
+        original_path,
+        trashed_path,
+        info_path,
+    })
+}
+
+// Puts an item trashed by this session back at exactly its original path.
+// Unlike `restore_item` this refuses to pick another name.
+pub fn untrash(item: &TrashedItem) -> Result<(), String> {
+    if fs::symlink_metadata(&item.original_path).is_ok() {
+        return Err(format!(
+            "'{}' already exists.",
+            item.original_path.display()
+        ));
+    }
+    fs::rename(&item.trashed_path, &item.original_path).map_err(|e| {
+        format!(
+            "Failed to restore '{}': {}",
+            item.original_path.display(),
+            e
+        )
+    })?;
+    if let Err(e) = fs::remove_file(&item.info_path) {
+        eprintln!(
+            "Restored item but failed to remove {}: {}",
+            item.info_path.display(),
+            e
+        );
+    }
+    Ok(())
+}
+
+pub async fn trash_item(path: PathBuf) -> Result<TrashedItem, String> {
+    println!("Moving to trash: {}", path.display());
+    tokio::task::spawn_blocking(move || move_to_trash(&path))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+// An item currently in one of the trash directories, as described by its `.trashinfo`
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    pub name: String,
+    pub original_path: PathBuf,
+    pub deletion_date: Option<NaiveDateTime>,
+    pub trashed_path: PathBuf,
+    pub info_path: PathBuf,
+    pub is_dir: bool,
+    pub size: u64,
+}
+
+// Decodes the octal escapes (`\040` for a space, ...) used in /proc/mounts
+fn unescape_mount_point(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let is_escape = bytes[i] == b'\\'
+            && i + 3 < bytes.len()
+            && bytes[i + 1..i + 4]
+                .iter()
+                .all(|b| (b'0'..=b'7').contains(b));
+        if is_escape {
+            let value = bytes[i + 1..i + 4]
+                .iter()
+                .fold(0u8, |acc, b| acc.wrapping_mul(8).wrapping_add(b - b'0'));
+            out.push(value);
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// Every trash directory that currently exists for this user: the home trash
+// plus the per-mount ones found under the mount points in /proc/mounts
+pub fn all_trash_dirs() -> Result<Vec<TrashDir>, String> {
+    let mut dirs = vec![home_trash_dir()?];
+    let uid = current_uid().to_string();
+
+    let mounts = fs::read_to_string("/proc/mounts").unwrap_or_default();
+    for line in mounts.lines() {
+        let Some(mount_point) = line.split_whitespace().nth(1) else {
+            continue;
+        };
+        let topdir = PathBuf::from(unescape_mount_point(mount_point));
+        let candidates = [
+            topdir.join(".Trash").join(&uid),
+            topdir.join(format!(".Trash-{}", uid)),
+        ];
+        for root in candidates {
+            let is_dir = fs::symlink_metadata(&root).is_ok_and(|m| m.is_dir());
+            if is_dir && !dirs.iter().any(|d| d.root == root) {
+                dirs.push(TrashDir {
+                    root,
+                    topdir: Some(topdir.clone()),
+                });
+            }
+        }
+    }
+
+    Ok(dirs)
+}
+
+// The `Path=` key decoded to the exact bytes it was written from, and the
+// deletion date if it can be read
+fn parse_trash_info(contents: &str) -> Option<(PathBuf, Option<NaiveDateTime>)> {
+    let mut in_section = false;
+    let mut path = None;
+    let mut deletion_date = None;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_section = line == TRASH_INFO_HEADER;
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        if let Some(value) = line.strip_prefix("Path=") {
+            path = Some(PathBuf::from(OsString::from_vec(
+                percent_decode_str(value).collect(),
+            )));
+        } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+            deletion_date = NaiveDateTime::parse_from_str(value, TRASH_DATE_FORMAT).ok();
+        }
+    }
+    path.map(|p| (p, deletion_date))
+}
+
+fn read_trash_dir(trash_dir: &TrashDir) -> Vec<TrashEntry> {
+    let Ok(info_iter) = fs::read_dir(trash_dir.info_dir()) else {
+        return Vec::new();
+    };
+
+    let mut entries = Vec::new();
+    for info in info_iter.filter_map(|e| e.ok()) {
+        let info_path = info.path();
+        if info_path.extension().and_then(|e| e.to_str()) != Some(TRASH_INFO_EXTENSION) {
+            continue;
+        }
+        let Some(trash_name) = info_path.file_stem() else {
+            continue;
+        };
+        let trashed_path = trash_dir.files_dir().join(trash_name);
+        let Ok(meta) = fs::symlink_metadata(&trashed_path) else {
+            // Orphaned info file, the spec says to ignore it
+            continue;
+        };
+        let Some((raw_path, deletion_date)) = fs::read_to_string(&info_path)
+            .ok()
+            .and_then(|c| parse_trash_info(&c))
+        else {
+            eprintln!("Skipping malformed trash info file {}", info_path.display());
+            continue;
+        };
+
+        let original_path = match &trash_dir.topdir {
+            Some(topdir) if !raw_path.has_root() => topdir.join(&raw_path),
+            _ => raw_path,
+        };
+        let name = original_path
+            .file_name()
+            .unwrap_or(trash_name)
+            .to_string_lossy()
+            .into_owned();
+
+        entries.push(TrashEntry {
+            name,
+            original_path,
+            deletion_date,
+            size: item_size(&trashed_path),
+            is_dir: meta.is_dir(),
+            trashed_path,
+            info_path,
+        });
+    }
+    entries
+}
+
+// Lists everything in every trash directory, most recently deleted first
+pub async fn list_trash() -> Result<Vec<TrashEntry>, String> {
+    tokio::task::spawn_blocking(|| {
+        let mut entries: Vec<TrashEntry> =
+            all_trash_dirs()?.iter().flat_map(read_trash_dir).collect();
+        entries.sort_by(|a, b| {
+            b.deletion_date
+                .cmp(&a.deletion_date)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        Ok(entries)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+fn remove_path(path: &Path) -> io::Result<()> {
+    let meta = fs::symlink_metadata(path)?;
+    if meta.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+// Moves a trashed item back to where it came from. If something else now
+// occupies the original path, the item is restored next to it under a free
+// "name (n).ext" name. Returns the path the item was restored to.
+pub async fn restore_item(entry: TrashEntry) -> Result<PathBuf, String> {
+    println!(
+        "Restoring {} to {}",
+        entry.trashed_path.display(),
+        entry.original_path.display()
+    );
+    tokio::task::spawn_blocking(move || {
+        if let Some(parent) = entry.original_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                format!(
+                    "Failed to recreate original folder '{}': {}",
+                    parent.display(),
+                    e
+                )
+            })?;
+        }
+
+        let destination = unique_destination(&entry.original_path);
+        fs::rename(&entry.trashed_path, &destination).map_err(|e| {
+            format!(
+                "Failed to restore '{}' to '{}': {}",
+                entry.name,
+                destination.display(),
+                e
+            )
+        })?;
+
+        if let Err(e) = fs::remove_file(&entry.info_path) {
+            eprintln!(
+                "Restored item but failed to remove {}: {}",
+                entry.info_path.display(),
+                e
+            );
+        }
+        Ok(destination)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+// Permanently removes a single trashed item and its info file
+pub async fn delete_from_trash(entry: TrashEntry) -> Result<(), String> {
+    println!(
+        "Permanently deleting from trash: {}",
+        entry.trashed_path.display()
+    );
+    tokio::task::spawn_blocking(move || {
+        remove_path(&entry.trashed_path)
+            .map_err(|e| format!("Failed to delete '{}' from the trash: {}", entry.name, e))?;
+        fs::remove_file(&entry.info_path).map_err(|e| {
+            format!(
+                "Failed to remove trash info file {}: {}",
+                entry.info_path.display(),
+                e
+            )
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+// Permanently removes the contents of every trash directory
+pub async fn empty_trash() -> Result<(), String> {
+    println!("Emptying trash");
+    tokio::task::spawn_blocking(|| {
+        let mut failures = Vec::new();
+        for trash_dir in all_trash_dirs()? {
+            for dir in [trash_dir.files_dir(), trash_dir.info_dir()] {
+                let Ok(iter) = fs::read_dir(&dir) else {
+                    continue;
+                };
+                for item in iter.filter_map(|e| e.ok()) {
+                    if let Err(e) = remove_path(&item.path()) {
+                        failures.push(format!("{}: {}", item.path().display(), e));
+                    }
+                }
+            }
+            // Cached sizes are meaningless once the trash is empty
+            let _ = fs::remove_file(trash_dir.root.join("directorysizes"));
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Failed to empty the trash completely: {}",
+                failures.join("; ")
+            ))
+        }
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use temp_dir::TempDir;
+
+    fn non_utf8_name() -> &'static OsStr {
+        OsStr::from_bytes(b"caf\xe9 100%.txt")
+    }
+
+    #[test]
+    fn original_path_round_trips_through_the_info_file() {
+        let trash_dir = TrashDir {
+            root: PathBuf::from("/home/user/.local/share/Trash"),
+            topdir: None,
+        };
+        let original = Path::new("/home/user/Documents").join(non_utf8_name());
+        let encoded = encode_original_path(&trash_dir, &original);
+        assert_eq!(encoded, "/home/user/Documents/caf%E9%20100%25.txt");
+
+        let contents = format!(
+            "{}\nPath={}\nDeletionDate=2024-03-01T12:30:00\n",
+            TRASH_INFO_HEADER, encoded
+        );
+        let (path, deletion_date) = parse_trash_info(&contents).unwrap();
+        assert_eq!(path, original);
+        assert_eq!(
+            deletion_date,
+            NaiveDateTime::parse_from_str("2024-03-01T12:30:00", TRASH_DATE_FORMAT).ok()
+        );
+    }
+
+    #[test]
+    fn mount_trash_paths_are_relative_to_the_topdir() {
+        let trash_dir = TrashDir {
+            root: PathBuf::from("/media/disk/.Trash-1000"),
+            topdir: Some(PathBuf::from("/media/disk")),
+        };
+        let encoded = encode_original_path(&trash_dir, Path::new("/media/disk/photos/a b.jpg"));
+        assert_eq!(encoded, "photos/a%20b.jpg");
+    }
+
+    #[test]
+    fn info_outside_the_trash_section_is_ignored() {
+        assert!(parse_trash_info("[Other]\nPath=/tmp/a\n").is_none());
+        let (path, deletion_date) =
+            parse_trash_info("[Trash Info]\nPath=/tmp/a\nDeletionDate=yesterday\n").unwrap();
+        assert_eq!(path, Path::new("/tmp/a"));
+        assert_eq!(deletion_date, None);
+    }
+
+    #[test]
+    fn trashed_names_keep_their_bytes() {
+        let temp = TempDir::new().unwrap();
+        let trash_dir = TrashDir {
+            root: temp.path().join("Trash"),
+            topdir: None,
+        };
+        trash_dir.ensure_exists().unwrap();
+        let original = temp.path().join(non_utf8_name());
+        fs::write(&original, b"contents").unwrap();
+        // Taken by an earlier item of the same name
+        fs::write(trash_dir.files_dir().join(non_utf8_name()), b"").unwrap();
+
+        let (trash_name, _) = reserve_trash_name(&trash_dir, &original).unwrap();
+        assert_eq!(trash_name.as_bytes(), b"caf\xe9 100%.2.txt");
+        fs::rename(&original, trash_dir.files_dir().join(&trash_name)).unwrap();
+
+        let entries = read_trash_dir(&trash_dir);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].original_path, original);
+        assert_eq!(
+            entries[0].trashed_path,
+            trash_dir.files_dir().join(&trash_name)
+        );
+    }
+}
@@ -0,0 +1,112 @@
+// Typing while the folder view has the keyboard: jumps to the first item whose
+// name starts with what was typed or, with "Type to filter" on, shows only the
+// items whose name contains it. Names are compared ignoring case and accents.
+use std::time::{Duration, Instant};
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+// A pause this long starts a new name when jumping
+const RESET_AFTER: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default)]
+pub struct TypeAhead {
+    // As typed, shown while filtering
+    pub text: String,
+    // Set when the text filters the items instead of jumping to one
+    pub filtering: bool,
+    last_typed: Option<Instant>,
+}
+
+impl TypeAhead {
+    pub fn push(&mut self, typed: &str, filtering: bool) {
+        let paused = self
+            .last_typed
+            .is_some_and(|last| last.elapsed() > RESET_AFTER);
+        if self.filtering != filtering || (!filtering && paused) {
+            self.text.clear();
+        }
+        self.text.push_str(typed);
+        self.filtering = filtering;
+        self.last_typed = Some(Instant::now());
+    }
+
+    // Removes the last character; false if there was none
+    pub fn pop(&mut self) -> bool {
+        self.last_typed = Some(Instant::now());
+        self.text.pop().is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.last_typed = None;
+    }
+
+    pub fn is_filtering(&self) -> bool {
+        self.filtering && !self.text.is_empty()
+    }
+
+    // Whether the item named `name` is shown while filtering
+    pub fn allows(&self, name: &str) -> bool {
+        !self.is_filtering() || fold(name).contains(&fold(&self.text))
+    }
+}
+
+// Lowercase with the accents removed, so "Été" and "ete" compare equal
+pub fn fold(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text
+        .nfd()
+        .filter(|&c| !is_combining_mark(c))
+        .flat_map(char::to_lowercase)
+    {
+        match unaccented(c) {
+            Some(letters) => folded.push_str(letters),
+            None => folded.push(c),
+        }
+    }
+    folded
+}
+
+// Letters whose accent or ligature is not a separate mark after decomposing
+fn unaccented(c: char) -> Option<&'static str> {
+    Some(match c {
+        'ß' => "ss",
+        'æ' => "ae",
+        'œ' => "oe",
+        'ø' => "o",
+        'đ' => "d",
+        'ħ' => "h",
+        'ı' => "i",
+        'ł' => "l",
+        'ŧ' => "t",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fold_removes_case_and_accents() {
+        assert_eq!(fold("Été"), "ete");
+        // Decomposed, as some file systems store names
+        assert_eq!(fold("E\u{301}te\u{301}"), "ete");
+        assert_eq!(fold("Tạp chí Thế giới"), "tap chi the gioi");
+        assert_eq!(fold("Hǎo"), "hao");
+        assert_eq!(fold("Straße"), "strasse");
+        assert_eq!(fold("Æther"), "aether");
+        assert_eq!(fold("Œuvre"), "oeuvre");
+        assert_eq!(fold("Łódź"), "lodz");
+        assert_eq!(fold("Ørsted"), "orsted");
+    }
+
+    #[test]
+    fn filter_matches_folded_names() {
+        let mut typed = TypeAhead::default();
+        typed.push("the", true);
+        assert!(typed.allows("Thế giới.txt"));
+        assert!(!typed.allows("notes.txt"));
+    }
+}
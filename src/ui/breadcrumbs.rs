@@ -0,0 +1,333 @@
+// A row of breadcrumb segments that fits the space it is given.
+//
+// When the segments are too wide, the ones after the first are left out,
+// starting next to it, until the rest fits; the overflow element (the "…"
+// menu) takes their place. The range of left out segments is shared through
+// `collapsed`, so the overflow menu can list them.
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::renderer;
+use iced::advanced::widget::{Operation, Tree, Widget};
+use iced::advanced::{overlay, Clipboard, Shell};
+use iced::{event, mouse, Element, Event, Length, Point, Rectangle, Size, Vector};
+use std::cell::Cell;
+use std::ops::Range;
+use std::rc::Rc;
+
+pub struct Breadcrumbs<'a, Message> {
+    // The segments followed by the overflow element
+    children: Vec<Element<'a, Message>>,
+    spacing: f32,
+    collapsed: Rc<Cell<(usize, usize)>>,
+}
+
+impl<'a, Message> Breadcrumbs<'a, Message> {
+    pub fn new(
+        segments: Vec<Element<'a, Message>>,
+        overflow: impl Into<Element<'a, Message>>,
+        collapsed: Rc<Cell<(usize, usize)>>,
+    ) -> Self {
+        let mut children = segments;
+        children.push(overflow.into());
+        Breadcrumbs {
+            children,
+            spacing: 0.0,
+            collapsed,
+        }
+    }
+
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    fn collapsed(&self) -> Range<usize> {
+        let (start, end) = self.collapsed.get();
+        start..end
+    }
+
+    // Whether the child at `index` is laid out, the overflow element only
+    // while segments are left out
+    fn is_shown(&self, index: usize) -> bool {
+        let collapsed = self.collapsed();
+        if index == self.children.len() - 1 {
+            !collapsed.is_empty()
+        } else {
+            !collapsed.contains(&index)
+        }
+    }
+}
+
+impl<'a, Message> Widget<Message, iced::Theme, iced::Renderer> for Breadcrumbs<'a, Message> {
+    fn children(&self) -> Vec<Tree> {
+        self.children.iter().map(Tree::new).collect()
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(&self.children);
+    }
+
+    fn size(&self) -> Size<Length> {
+        Size::new(Length::Shrink, Length::Shrink)
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &iced::Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        let max = limits.max();
+        let child_limits = layout::Limits::new(Size::ZERO, Size::new(f32::INFINITY, max.height));
+        let mut nodes: Vec<layout::Node> = self
+            .children
+            .iter()
+            .zip(&mut tree.children)
+            .map(|(child, tree)| child.as_widget().layout(tree, renderer, &child_limits))
+            .collect();
+
+        let segments = self.children.len() - 1;
+        let width = |index: usize| nodes[index].size().width + self.spacing;
+        let all: f32 = (0..segments).map(width).sum::<f32>() - self.spacing;
+        // Leaves out segments 1..end for the smallest `end` that fits, keeping
+        // at least the first and the last segment
+        let collapsed = if all <= max.width || segments <= 2 {
+            0..0
+        } else {
+            let overflow = width(segments);
+            let end = (2..segments)
+                .find(|&end| {
+                    width(0) + overflow + (end..segments).map(width).sum::<f32>() - self.spacing
+                        <= max.width
+                })
+                .unwrap_or(segments - 1);
+            1..end
+        };
+        self.collapsed.set((collapsed.start, collapsed.end));
+
+        let height = nodes
+            .iter()
+            .map(|node| node.size().height)
+            .fold(0.0, f32::max);
+        // The overflow element goes where the left out segments were
+        let mut order: Vec<usize> = (0..segments).collect();
+        if !collapsed.is_empty() {
+            order.splice(collapsed.clone(), [segments]);
+        }
+        let mut x = 0.0;
+        for &index in &order {
+            let node = &mut nodes[index];
+            let size = node.size();
+            *node = node
+                .clone()
+                .move_to(Point::new(x, (height - size.height) / 2.0));
+            x += size.width + self.spacing;
+        }
+        // Children that are left out keep their layout, as their widgets expect
+        // one, but are skipped by everything else
+        let width = (x - self.spacing).max(0.0);
+        layout::Node::with_children(Size::new(width, height), nodes)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &iced::Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        for (index, ((child, tree), layout)) in self
+            .children
+            .iter()
+            .zip(&mut tree.children)
+            .zip(layout.children())
+            .enumerate()
+        {
+            if self.is_shown(index) {
+                child.as_widget().operate(tree, layout, renderer, operation);
+            }
+        }
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &iced::Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        let shown: Vec<bool> = (0..self.children.len()).map(|i| self.is_shown(i)).collect();
+        self.children
+            .iter_mut()
+            .zip(&mut tree.children)
+            .zip(layout.children())
+            .zip(shown)
+            .filter(|(_, shown)| *shown)
+            .map(|(((child, tree), layout), _)| {
+                child.as_widget_mut().on_event(
+                    tree,
+                    event.clone(),
+                    layout,
+                    cursor,
+                    renderer,
+                    clipboard,
+                    shell,
+                    viewport,
+                )
+            })
+            .fold(event::Status::Ignored, event::Status::merge)
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut iced::Renderer,
+        theme: &iced::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        for (index, ((child, tree), layout)) in self
+            .children
+            .iter()
+            .zip(&tree.children)
+            .zip(layout.children())
+            .enumerate()
+        {
+            if self.is_shown(index) {
+                child
+                    .as_widget()
+                    .draw(tree, renderer, theme, style, layout, cursor, viewport);
+            }
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &iced::Renderer,
+    ) -> mouse::Interaction {
+        self.children
+            .iter()
+            .zip(&tree.children)
+            .zip(layout.children())
+            .enumerate()
+            .filter(|(index, _)| self.is_shown(*index))
+            .map(|(_, ((child, tree), layout))| {
+                child
+                    .as_widget()
+                    .mouse_interaction(tree, layout, cursor, viewport, renderer)
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &iced::Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, iced::Theme, iced::Renderer>> {
+        let shown: Vec<bool> = (0..self.children.len()).map(|i| self.is_shown(i)).collect();
+        let overlays: Vec<_> = self
+            .children
+            .iter_mut()
+            .zip(&mut tree.children)
+            .zip(layout.children())
+            .zip(shown)
+            .filter(|(_, shown)| *shown)
+            .filter_map(|(((child, tree), layout), _)| {
+                child
+                    .as_widget_mut()
+                    .overlay(tree, layout, renderer, translation)
+            })
+            .collect();
+        (!overlays.is_empty()).then(|| overlay::Group::with_children(overlays).overlay())
+    }
+}
+
+impl<'a, Message: 'a> From<Breadcrumbs<'a, Message>> for Element<'a, Message> {
+    fn from(breadcrumbs: Breadcrumbs<'a, Message>) -> Self {
+        Element::new(breadcrumbs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::advanced::widget::operation::focusable;
+    use iced::advanced::widget::Id;
+    use iced::widget::{button, container, text};
+    use iced::{Font, Pixels};
+
+    fn renderer() -> iced::Renderer {
+        iced::Renderer::TinySkia(iced_tiny_skia::Renderer::new(
+            iced_tiny_skia::Backend::new(),
+            Font::default(),
+            Pixels(14.0),
+        ))
+    }
+
+    fn segment<'a>(label: &'a str) -> Element<'a, ()> {
+        container(button(text(label)).on_press(()).width(100)).into()
+    }
+
+    // Lays out five 100px wide segments in `width`, then walks the layout the
+    // way the runtime does on every update and draw
+    fn lay_out(width: f32) -> (Size, (usize, usize)) {
+        let renderer = renderer();
+        let collapsed = Rc::new(Cell::new((0, 0)));
+        let segments = ["/", "home", "user", "Documents", "notes"]
+            .into_iter()
+            .map(segment)
+            .collect();
+        let mut breadcrumbs: Element<()> =
+            Breadcrumbs::new(segments, segment("…"), collapsed.clone())
+                .spacing(10.0)
+                .into();
+        let mut tree = Tree::new(&breadcrumbs);
+        let limits = layout::Limits::new(Size::ZERO, Size::new(width, 30.0));
+        let node = breadcrumbs
+            .as_widget()
+            .layout(&mut tree, &renderer, &limits);
+
+        breadcrumbs.as_widget().operate(
+            &mut tree,
+            Layout::new(&node),
+            &renderer,
+            &mut focusable::focus(Id::unique()),
+        );
+        assert!(breadcrumbs
+            .as_widget_mut()
+            .overlay(&mut tree, Layout::new(&node), &renderer, Vector::ZERO)
+            .is_none());
+        (node.size(), collapsed.get())
+    }
+
+    #[test]
+    fn segments_that_fit_are_all_shown() {
+        let (size, collapsed) = lay_out(1000.0);
+        assert_eq!(collapsed, (0, 0));
+        assert_eq!(size.width, 5.0 * 100.0 + 4.0 * 10.0);
+    }
+
+    #[test]
+    fn segments_after_the_first_collapse_into_the_overflow() {
+        // The first, the overflow and the last two segments
+        let (size, collapsed) = lay_out(450.0);
+        assert_eq!(collapsed, (1, 3));
+        assert_eq!(size.width, 4.0 * 100.0 + 3.0 * 10.0);
+
+        // Never fewer than the first and the last segment
+        let (_, collapsed) = lay_out(50.0);
+        assert_eq!(collapsed, (1, 4));
+    }
+}
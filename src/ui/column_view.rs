@@ -0,0 +1,193 @@
+use crate::app::{FileManager, Message};
+use crate::constants::FORWARD_ICON_PATH;
+use crate::fs_utils::{self, DirEntry, PreviewContent};
+use crate::tab::Tab;
+use crate::ui::file_grid::{
+    build_rename_input, build_status_message, entry_icon_path, with_background_menu,
+    with_item_menu,
+};
+use crate::ui::styles::{RuleStyle, SelectedItemStyle, SECONDARY_TEXT_COLOR};
+use iced::widget::{
+    button, column, container, image, mouse_area, row, scrollable, text, Column, Row, Rule,
+};
+use iced::{theme, Alignment, ContentFit, Element, Length};
+use std::path::Path;
+
+const PADDING: f32 = 8.0;
+const SPACING: f32 = 10.0;
+const COLUMN_WIDTH: f32 = 220.0;
+const PREVIEW_WIDTH: f32 = 280.0;
+const ROW_ICON_SIZE: f32 = 18.0;
+const CHEVRON_SIZE: f32 = 12.0;
+const TEXT_SIZE: u16 = 14;
+// Longer text files are cut off in the preview
+const MAX_PREVIEW_CHARS: usize = 4000;
+
+fn create_column_row<'a>(
+    entry: &'a DirEntry,
+    tab: &'a Tab,
+    is_highlighted: bool,
+) -> Element<'a, Message> {
+    let name: Element<Message> = if tab.is_renaming(&entry.path) {
+        build_rename_input(tab)
+    } else {
+        text(&entry.display_name)
+            .size(TEXT_SIZE)
+            .width(Length::Fill)
+            .into()
+    };
+    let mut content = row![
+        image(entry_icon_path(entry))
+            .width(Length::Fixed(ROW_ICON_SIZE))
+            .height(Length::Fixed(ROW_ICON_SIZE)),
+        name,
+    ]
+    .spacing(SPACING / 2.0)
+    .align_items(Alignment::Center);
+    if entry.is_dir {
+        content = content.push(
+            image(FORWARD_ICON_PATH)
+                .width(Length::Fixed(CHEVRON_SIZE))
+                .height(Length::Fixed(CHEVRON_SIZE)),
+        );
+    }
+
+    let row_button = button(content)
+        .style(theme::Button::Text)
+        .padding([2.0, PADDING / 2.0])
+        .width(Length::Fill)
+        .on_press(Message::ItemClicked(entry.path.clone()));
+
+    // Middle-clicking a folder opens it in a new tab
+    let row_button: Element<'a, Message> = if entry.is_dir {
+        mouse_area(row_button)
+            .on_middle_press(Message::OpenInNewTab(entry.path.clone()))
+            .into()
+    } else {
+        row_button.into()
+    };
+
+    container(row_button)
+        .width(Length::Fill)
+        .style(if is_highlighted {
+            theme::Container::Custom(Box::new(SelectedItemStyle))
+        } else {
+            theme::Container::Transparent
+        })
+        .into()
+}
+
+// One folder's items; `is_highlighted` marks the opened folder or the
+// selection. Items get their right-click menu when `state` is given, which
+// only the current folder's column does.
+fn create_column<'a>(
+    state: Option<&'a FileManager>,
+    entries: impl Iterator<Item = &'a DirEntry>,
+    tab: &'a Tab,
+    is_highlighted: impl Fn(&Path) -> bool,
+) -> Element<'a, Message> {
+    let rows = entries.fold(Column::new().padding(PADDING / 2.0), |rows, entry| {
+            let row = create_column_row(entry, tab, is_highlighted(&entry.path));
+            match state {
+                Some(state) => rows.push(with_item_menu(state, tab, entry, row)),
+                None => rows.push(row),
+            }
+        });
+    scrollable(rows)
+        .width(Length::Fixed(COLUMN_WIDTH))
+        .height(Length::Fill)
+        .into()
+}
+
+// The selected file: its preview followed by its size and date
+fn build_preview_column<'a>(state: &'a FileManager, entry: &'a DirEntry) -> Element<'a, Message> {
+    let preview: Element<Message> = match &state.preview_content {
+        Some(PreviewContent::Image(handle)) => image(handle.clone())
+            .width(Length::Fill)
+            .content_fit(ContentFit::Contain)
+            .into(),
+        Some(PreviewContent::Text(content)) => {
+            text(content.chars().take(MAX_PREVIEW_CHARS).collect::<String>())
+                .size(12)
+                .into()
+        }
+        Some(PreviewContent::Error(_)) | None => image(entry_icon_path(entry))
+            .width(Length::Fixed(96.0))
+            .height(Length::Fixed(96.0))
+            .into(),
+    };
+
+    let details = column![
+        text(&entry.display_name).size(TEXT_SIZE),
+        text(format!("Size: {}", fs_utils::format_size(entry.size)))
+            .size(12)
+            .style(SECONDARY_TEXT_COLOR),
+        text(format!(
+            "Modified: {}",
+            fs_utils::format_modified(entry.modified)
+        ))
+        .size(12)
+        .style(SECONDARY_TEXT_COLOR),
+    ]
+    .spacing(4);
+
+    scrollable(
+        column![container(preview).width(Length::Fill).center_x(), details]
+            .spacing(SPACING)
+            .padding(PADDING),
+    )
+    .width(Length::Fixed(PREVIEW_WIDTH))
+    .height(Length::Fill)
+    .into()
+}
+
+// The column view: one column per folder from the column root down to the
+// current folder, then a preview of the selected file
+pub fn build_column_view<'a>(state: &'a FileManager, tab: &'a Tab) -> Element<'a, Message> {
+    let separator = || Rule::vertical(1).style(theme::Rule::Custom(Box::new(RuleStyle)));
+
+    let mut columns = tab
+        .parent_columns
+        .iter()
+        .fold(Row::new(), |columns, (_, entries)| {
+            // The folder opened from this column is on the way to the current one
+            columns
+                .push(create_column(None, entries.iter(), tab, |path| {
+                    tab.current_path.starts_with(path)
+                }))
+                .push(separator())
+        });
+
+    columns = columns.push(match build_status_message(tab) {
+        Some(status) => container(status)
+            .width(Length::Fixed(COLUMN_WIDTH))
+            .height(Length::Fill)
+            .into(),
+        None => create_column(Some(state), tab.shown_entries(), tab, |path| {
+            tab.selection.contains(path)
+        }),
+    });
+
+    // Only the tab being worked in has its preview loaded
+    let selected_file = tab
+        .selection
+        .single()
+        .and_then(|path| tab.entries.iter().find(|e| &e.path == path))
+        .filter(|entry| !entry.is_dir);
+    if let Some(entry) = selected_file.filter(|_| state.tab().id == tab.id) {
+        columns = columns
+            .push(separator())
+            .push(build_preview_column(state, entry));
+    }
+
+    let tab_id = tab.id;
+    let view = scrollable(columns.height(Length::Fill))
+        .id(tab.scrollable_id())
+        .on_scroll(move |viewport| Message::GridScrolled(tab_id, viewport.absolute_offset()))
+        .direction(scrollable::Direction::Horizontal(
+            scrollable::Properties::default(),
+        ))
+        .width(Length::Fill)
+        .height(Length::Fill);
+    with_background_menu(state, tab, view)
+}
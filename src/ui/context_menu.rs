@@ -0,0 +1,335 @@
+// A region that opens a menu at the cursor when right-clicked.
+//
+// The menu is built only while it is open. A right-click the content handles
+// itself (an item's menu inside the background's) does not open this one, so
+// items and empty space can have different menus. The menu closes when one of
+// its entries is clicked, on a click outside of it, on Escape and when the
+// window is resized. As a drop-down it opens below the content on a left click
+// instead.
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::renderer;
+use iced::advanced::widget::{tree, Operation, Tree, Widget};
+use iced::advanced::{overlay, Clipboard, Shell};
+use iced::keyboard::{self, key::Named};
+use iced::{event, mouse, window, Element, Event, Length, Point, Rectangle, Size, Vector};
+
+pub struct ContextMenu<'a, Message> {
+    content: Element<'a, Message>,
+    menu: Box<dyn Fn() -> Element<'a, Message> + 'a>,
+    on_open: Option<Message>,
+    is_dropdown: bool,
+}
+
+impl<'a, Message> ContextMenu<'a, Message> {
+    pub fn new(
+        content: impl Into<Element<'a, Message>>,
+        menu: impl Fn() -> Element<'a, Message> + 'a,
+    ) -> Self {
+        ContextMenu {
+            content: content.into(),
+            menu: Box::new(menu),
+            on_open: None,
+            is_dropdown: false,
+        }
+    }
+
+    // Opens on a left click, below the content rather than at the cursor
+    pub fn dropdown(mut self) -> Self {
+        self.is_dropdown = true;
+        self
+    }
+
+    // Published when the menu opens, before it is shown
+    pub fn on_open(mut self, message: Message) -> Self {
+        self.on_open = Some(message);
+        self
+    }
+}
+
+struct State {
+    // Where the menu was opened, while it is open
+    position: Option<Point>,
+    menu: Tree,
+}
+
+impl<'a, Message: Clone> Widget<Message, iced::Theme, iced::Renderer> for ContextMenu<'a, Message> {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State {
+            position: None,
+            menu: Tree::empty(),
+        })
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &iced::Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &iced::Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &iced::Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        let status = self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event.clone(),
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        );
+
+        let opening_button = if self.is_dropdown {
+            mouse::Button::Left
+        } else {
+            mouse::Button::Right
+        };
+        if let Event::Mouse(mouse::Event::ButtonPressed(button)) = event {
+            if button == opening_button && status == event::Status::Ignored {
+                let bounds = layout.bounds();
+                if let Some(position) = cursor.position_over(bounds) {
+                    let state = tree.state.downcast_mut::<State>();
+                    state.position = Some(if self.is_dropdown {
+                        Point::new(bounds.x, bounds.y + bounds.height)
+                    } else {
+                        position
+                    });
+                    if let Some(on_open) = &self.on_open {
+                        shell.publish(on_open.clone());
+                    }
+                    return event::Status::Captured;
+                }
+            }
+        }
+
+        status
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut iced::Renderer,
+        theme: &iced::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &iced::Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &iced::Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, iced::Theme, iced::Renderer>> {
+        let state = tree.state.downcast_mut::<State>();
+        let Some(position) = state.position else {
+            return self.content.as_widget_mut().overlay(
+                &mut tree.children[0],
+                layout,
+                renderer,
+                translation,
+            );
+        };
+
+        let menu = (self.menu)();
+        state.menu.diff(menu.as_widget());
+        Some(overlay::Element::new(Box::new(MenuOverlay {
+            position: position + translation,
+            menu,
+            state,
+        })))
+    }
+}
+
+impl<'a, Message: Clone + 'a> From<ContextMenu<'a, Message>> for Element<'a, Message> {
+    fn from(context_menu: ContextMenu<'a, Message>) -> Self {
+        Element::new(context_menu)
+    }
+}
+
+struct MenuOverlay<'a, Message> {
+    position: Point,
+    menu: Element<'a, Message>,
+    state: &'a mut State,
+}
+
+impl<'a, Message> overlay::Overlay<Message, iced::Theme, iced::Renderer>
+    for MenuOverlay<'a, Message>
+{
+    fn layout(&mut self, renderer: &iced::Renderer, bounds: Size) -> layout::Node {
+        let limits = layout::Limits::new(Size::ZERO, bounds);
+        let node = self
+            .menu
+            .as_widget()
+            .layout(&mut self.state.menu, renderer, &limits);
+
+        // Opens towards the inside of the window near its right and bottom edges
+        let size = node.size();
+        let mut position = self.position;
+        if position.x + size.width > bounds.width {
+            position.x = (position.x - size.width).max(0.0);
+        }
+        if position.y + size.height > bounds.height {
+            position.y = (position.y - size.height).max(0.0);
+        }
+        node.move_to(position)
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut iced::Renderer,
+        theme: &iced::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+    ) {
+        let bounds = layout.bounds();
+        self.menu.as_widget().draw(
+            &self.state.menu,
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            &bounds,
+        );
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &iced::Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        match &event {
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Named(Named::Escape),
+                ..
+            })
+            | Event::Window(_, window::Event::Resized { .. }) => {
+                self.state.position = None;
+                return event::Status::Captured;
+            }
+            // A click anywhere else only closes the menu
+            Event::Mouse(mouse::Event::ButtonPressed(_)) if !cursor.is_over(layout.bounds()) => {
+                self.state.position = None;
+                return event::Status::Captured;
+            }
+            _ => {}
+        }
+
+        let bounds = layout.bounds();
+        let status = self.menu.as_widget_mut().on_event(
+            &mut self.state.menu,
+            event.clone(),
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            &bounds,
+        );
+
+        // Entries act on release; clicking a section title leaves the menu open
+        if let Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) = event {
+            if status == event::Status::Captured {
+                self.state.position = None;
+            }
+        }
+        if cursor.is_over(bounds) {
+            event::Status::Captured
+        } else {
+            status
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &iced::Renderer,
+    ) -> mouse::Interaction {
+        self.menu.as_widget().mouse_interaction(
+            &self.state.menu,
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+}
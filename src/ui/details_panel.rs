@@ -1,8 +1,9 @@
 use crate::app::{FileManager, Message};
 use crate::constants::{THUMBNAIL_CACHE_DIR, THUMBNAIL_SIZE};
 use crate::fs_utils::{self};
+use crate::ui::trash_view;
 use iced::widget::{
-    button, column, container, image, scrollable, text,
+    button, column, container, image, row, scrollable, text, Space,
 };
 use iced::{Element, Font, Length, Theme, ContentFit, Renderer};
 use std::path::PathBuf;
@@ -32,9 +33,136 @@ async fn load_thumbnail_async(path: PathBuf) -> Option<image::Handle> {
         .flatten()
 }
 
+// Summary of the whole trash plus actions for the selected trashed item
+fn trash_details(state: &FileManager) -> Element<'_, Message, Theme, Renderer> {
+    let mut details_column = column![
+        text("Trash").size(20),
+        text(format!("Items: {}", state.trash_entries.len())),
+        text(format!(
+            "Total size: {}",
+            fs_utils::format_size(Some(state.trash_size()))
+        )),
+    ]
+    .spacing(5);
+
+    let selected_entry = state
+        .tab()
+        .selection
+        .single()
+        .and_then(|path| state.trash_entries.iter().find(|e| &e.trashed_path == path));
+    if let Some(entry) = selected_entry {
+        details_column = details_column
+            .push(Space::with_height(Length::Fixed(10.0)))
+            .push(text(&entry.name).size(16))
+            .push(text(format!(
+                "Original location: {}",
+                entry.original_path.display()
+            )))
+            .push(text(format!(
+                "Deleted: {}",
+                trash_view::format_deletion_date(entry)
+            )))
+            .push(text(format!(
+                "Size: {}",
+                fs_utils::format_size(Some(entry.size))
+            )))
+            .push(
+                row![
+                    action_button(
+                        "Restore",
+                        Some(Message::RestoreFromTrash(entry.trashed_path.clone()))
+                    ),
+                    action_button(
+                        "Delete permanently",
+                        Some(Message::DeleteFromTrash(entry.trashed_path.clone()))
+                    ),
+                ]
+                .spacing(5),
+            );
+    }
+
+    details_column = details_column.push(Space::with_height(Length::Fixed(10.0))).push(
+        action_button(
+            "Empty trash",
+            (!state.trash_entries.is_empty()).then_some(Message::EmptyTrash),
+        ),
+    );
+
+    container(scrollable(details_column))
+        .padding(10)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
+
+// Actions that apply to every selected item
+fn selection_actions(state: &FileManager) -> Element<'_, Message, Theme, Renderer> {
+    let has_selection = !state.tab().selection.is_empty();
+    column![
+        row![
+            action_button("Copy", has_selection.then_some(Message::CopySelection)),
+            action_button("Cut", has_selection.then_some(Message::CutSelection)),
+            action_button("Paste", state.clipboard.is_some().then_some(Message::Paste)),
+        ]
+        .spacing(5),
+        row![
+            action_button("Move to Trash", has_selection.then_some(Message::TrashSelection)),
+            action_button(
+                "Delete permanently",
+                has_selection.then_some(Message::DeleteSelectionPermanently)
+            ),
+        ]
+        .spacing(5),
+        row![
+            action_button("Select all", Some(Message::SelectAll)),
+            action_button("Invert selection", Some(Message::InvertSelection)),
+        ]
+        .spacing(5),
+    ]
+    .spacing(5)
+    .into()
+}
+
+// Summary shown when more than one item is selected
+fn multi_selection_details(state: &FileManager) -> Element<'_, Message, Theme, Renderer> {
+    let selected = state.tab().selected_entries();
+    let folder_count = selected.iter().filter(|e| e.is_dir).count();
+    let file_count = selected.len() - folder_count;
+    let files_size: u64 = selected.iter().filter_map(|e| e.size).sum();
+
+    let mut details_column = column![
+        text(format!("{} items selected", selected.len())).size(20),
+        text(format!("Folders: {}", folder_count)),
+        text(format!("Files: {}", file_count)),
+    ]
+    .spacing(5);
+    if file_count > 0 {
+        details_column = details_column.push(text(format!(
+            "Size of files: {}",
+            fs_utils::format_size(Some(files_size))
+        )));
+    }
+    details_column = details_column
+        .push(Space::with_height(Length::Fixed(10.0)))
+        .push(selection_actions(state));
+
+    container(scrollable(details_column))
+        .padding(10)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
+
 pub fn details_panel(state: &FileManager) -> Element<'_, Message, Theme, Renderer> {
-    let content = if let Some(path) = &state.selected_path {
-        if let Some(entry) = state.entries.iter().find(|e| e.path == *path) {
+    if state.tab().showing_trash {
+        return trash_details(state);
+    }
+    if state.tab().selection.len() > 1 {
+        return multi_selection_details(state);
+    }
+
+    let content = if let Some(path) = state.tab().selection.single() {
+        if let Some(entry) = state.tab().entries.iter().find(|e| e.path == *path) {
             let mut details_column = column![
                 text(&entry.display_name).size(20),
                 text(format!("Path: {}", entry.path.display())),
@@ -71,6 +199,10 @@ pub fn details_panel(state: &FileManager) -> Element<'_, Message, Theme, Rendere
                 }
             }
 
+            details_column = details_column
+                .push(Space::with_height(Length::Fixed(10.0)))
+                .push(selection_actions(state));
+
             container(scrollable(details_column)).padding(10)
         } else {
             container(text("No item selected or item not found."))
@@ -79,10 +211,16 @@ pub fn details_panel(state: &FileManager) -> Element<'_, Message, Theme, Rendere
                 .center_y()
         }
     } else {
-        container(text("Select an item to see details."))
-            .padding(10)
-            .center_x()
-            .center_y()
+        container(
+            column![
+                text("Select an item to see details."),
+                selection_actions(state),
+            ]
+            .spacing(10),
+        )
+        .padding(10)
+        .center_x()
+        .center_y()
     };
 
     content.width(Length::Fill).height(Length::Fill).into()
@@ -0,0 +1,233 @@
+// A region that tells a click apart from a horizontal drag.
+//
+// Used by the list view: dragging a column's edge resizes it (`on_drag`
+// reports the movement since the last event), dragging a header moves the
+// column (`on_drop` reports the whole offset on release) and clicking a header
+// sorts by it (`on_click`).
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::renderer;
+use iced::advanced::widget::{tree, Operation, Tree, Widget};
+use iced::advanced::{overlay, Clipboard, Shell};
+use iced::{event, mouse, Element, Event, Length, Rectangle, Size, Vector};
+
+// Movement below this distance is treated as a click rather than a drag
+const DRAG_THRESHOLD: f32 = 4.0;
+
+pub struct DragArea<'a, Message> {
+    content: Element<'a, Message>,
+    on_click: Option<Message>,
+    on_drag: Option<Box<dyn Fn(f32) -> Message + 'a>>,
+    on_drop: Option<Box<dyn Fn(f32) -> Message + 'a>>,
+    interaction: mouse::Interaction,
+}
+
+impl<'a, Message> DragArea<'a, Message> {
+    pub fn new(content: impl Into<Element<'a, Message>>) -> Self {
+        DragArea {
+            content: content.into(),
+            on_click: None,
+            on_drag: None,
+            on_drop: None,
+            interaction: mouse::Interaction::Pointer,
+        }
+    }
+
+    pub fn on_click(mut self, message: Message) -> Self {
+        self.on_click = Some(message);
+        self
+    }
+
+    pub fn on_drag(mut self, on_drag: impl Fn(f32) -> Message + 'a) -> Self {
+        self.on_drag = Some(Box::new(on_drag));
+        self
+    }
+
+    pub fn on_drop(mut self, on_drop: impl Fn(f32) -> Message + 'a) -> Self {
+        self.on_drop = Some(Box::new(on_drop));
+        self
+    }
+
+    pub fn interaction(mut self, interaction: mouse::Interaction) -> Self {
+        self.interaction = interaction;
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    origin_x: Option<f32>,
+    last_x: f32,
+    dragging: bool,
+}
+
+impl<'a, Message: Clone> Widget<Message, iced::Theme, iced::Renderer> for DragArea<'a, Message> {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &iced::Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &iced::Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &iced::Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        let status = self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event.clone(),
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        );
+
+        let state = tree.state.downcast_mut::<State>();
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                if status == event::Status::Ignored =>
+            {
+                if let Some(position) = cursor.position_over(layout.bounds()) {
+                    state.origin_x = Some(position.x);
+                    state.last_x = position.x;
+                    state.dragging = false;
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                if let Some(origin_x) = state.origin_x {
+                    if !state.dragging && (position.x - origin_x).abs() >= DRAG_THRESHOLD {
+                        state.dragging = true;
+                    }
+                    if state.dragging {
+                        if let Some(on_drag) = &self.on_drag {
+                            shell.publish(on_drag(position.x - state.last_x));
+                        }
+                        state.last_x = position.x;
+                    }
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                if let Some(origin_x) = state.origin_x.take() {
+                    if state.dragging {
+                        if let Some(on_drop) = &self.on_drop {
+                            shell.publish(on_drop(state.last_x - origin_x));
+                        }
+                    } else if let Some(on_click) = &self.on_click {
+                        shell.publish(on_click.clone());
+                    }
+                    state.dragging = false;
+                    return event::Status::Captured;
+                }
+            }
+            _ => {}
+        }
+
+        status
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut iced::Renderer,
+        theme: &iced::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &iced::Renderer,
+    ) -> mouse::Interaction {
+        let state = tree.state.downcast_ref::<State>();
+        if state.dragging || cursor.is_over(layout.bounds()) {
+            return self.interaction;
+        }
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &iced::Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, iced::Theme, iced::Renderer>> {
+        self.content
+            .as_widget_mut()
+            .overlay(&mut tree.children[0], layout, renderer, translation)
+    }
+}
+
+impl<'a, Message: Clone + 'a> From<DragArea<'a, Message>> for Element<'a, Message> {
+    fn from(drag_area: DragArea<'a, Message>) -> Self {
+        Element::new(drag_area)
+    }
+}
@@ -1,14 +1,20 @@
-use crate::app::{FileManager, GroupCriteria, Message};
+use crate::app::{group_of, FileManager, GroupCriteria, Message, SortCriteria, SortOrder};
 use crate::constants::*;
 use crate::constants::{FILE_ICON_PATH, FOLDER_ICON_PATH, THUMBNAIL_SIZE};
 use crate::fs_utils::DirEntry;
-use crate::ui::styles::{SelectedItemStyle, SECONDARY_TEXT_COLOR};
+use crate::tab::Tab;
+use crate::ui::context_menu::ContextMenu;
+use crate::ui::rubber_band::{self, RubberBand};
+use crate::ui::styles::{
+    MenuItemStyle, MenuStyle, RuleStyle, SelectedItemStyle, ACCENT_COLOR, SECONDARY_TEXT_COLOR,
+};
 use iced::alignment::{Horizontal, Vertical};
-use iced::widget::{button, column, container, image, row, scrollable, text, Column, Rule};
+use iced::widget::{
+    button, column, container, image, mouse_area, row, scrollable, text, text_input, Column, Rule,
+};
 use iced::{theme, Alignment, ContentFit, Element, Length, Renderer, Theme}; // Import ContentFit directly
 use iced_aw::Wrap;
-use std::collections::BTreeMap;
-use std::path::PathBuf; // Import THUMBNAIL_SIZE
+use std::collections::{BTreeMap, HashMap};
 
 const PADDING: f32 = 8.0;
 const SPACING: f32 = 10.0;
@@ -16,14 +22,227 @@ const ITEM_WIDTH: f32 = 100.0;
 const ICON_SIZE: f32 = 52.0;
 const MAX_FILENAME_LEN: usize = 15;
 const ELLIPSIS: &str = "...";
+const MENU_WIDTH: f32 = 220.0;
+// Long menus scroll beyond this height
+const MAX_MENU_HEIGHT: f32 = 400.0;
+// Apps listed under "Open With" at most
+const MAX_OPEN_WITH_APPS: usize = 8;
+
+// Folder icon, the resolved icon for apps, otherwise the generic file icon
+pub fn entry_icon_path(entry: &DirEntry) -> String {
+    if entry.is_dir {
+        FOLDER_ICON_PATH.to_string()
+    } else {
+        entry
+            .resolved_icon_path
+            .as_ref()
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_else(|| FILE_ICON_PATH.to_string())
+    }
+}
+
+pub fn rename_input_id() -> text_input::Id {
+    text_input::Id::new("rename-input")
+}
+
+// The text field that replaces an item's name while it is renamed
+pub fn build_rename_input(tab: &Tab) -> Element<'_, Message, Theme, Renderer> {
+    text_input("Name", &tab.rename_input_value)
+        .id(rename_input_id())
+        .on_input(Message::RenameInputChanged)
+        .on_submit(Message::ConfirmRename)
+        .size(14)
+        .padding(2)
+        .into()
+}
+
+// One entry of a right-click menu; without a message it is shown disabled
+pub fn menu_entry<'a>(label: impl ToString, message: Option<Message>) -> Element<'a, Message> {
+    button(text(label).size(14))
+        .style(theme::Button::Custom(Box::new(MenuItemStyle)))
+        .width(Length::Fill)
+        .padding([4, 12])
+        .on_press_maybe(message)
+        .into()
+}
+
+// A menu entry for one of several choices, the current one in the accent color
+pub fn menu_choice<'a>(label: &str, is_current: bool, message: Message) -> Element<'a, Message> {
+    let label = if is_current {
+        text(label).size(14).style(ACCENT_COLOR)
+    } else {
+        text(label).size(14)
+    };
+    button(label)
+        .style(theme::Button::Custom(Box::new(MenuItemStyle)))
+        .width(Length::Fill)
+        .padding([4, 12])
+        .on_press(message)
+        .into()
+}
+
+pub fn menu_title<'a>(title: &str) -> Element<'a, Message> {
+    container(text(title).size(12).style(SECONDARY_TEXT_COLOR))
+        .padding([4, 12, 2, 12])
+        .into()
+}
+
+fn menu_separator<'a>() -> Element<'a, Message> {
+    container(Rule::horizontal(1).style(theme::Rule::Custom(Box::new(RuleStyle))))
+        .padding([2, 0])
+        .into()
+}
+
+pub fn build_menu<'a>(entries: Vec<Element<'a, Message>>) -> Element<'a, Message> {
+    container(Column::with_children(entries).width(Length::Fixed(MENU_WIDTH)))
+        .padding(4)
+        .style(theme::Container::Custom(Box::new(MenuStyle)))
+        .into()
+}
+
+// A menu of any number of entries, such as the folders in a folder
+pub fn build_scrolling_menu<'a>(entries: Vec<Element<'a, Message>>) -> Element<'a, Message> {
+    container(scrollable(
+        Column::with_children(entries).width(Length::Fixed(MENU_WIDTH)),
+    ))
+    .max_height(MAX_MENU_HEIGHT)
+    .padding(4)
+    .style(theme::Container::Custom(Box::new(MenuStyle)))
+    .into()
+}
+
+// The menu of a right-clicked item. It acts on the selection, which the
+// item joins when the menu opens.
+fn build_item_menu<'a>(state: &'a FileManager, tab: &'a Tab, entry: &'a DirEntry) -> Element<'a, Message> {
+    let mut entries = vec![menu_entry("Open", Some(Message::OpenSelection))];
+
+    if !entry.is_dir {
+        entries.push(menu_title("Open With"));
+        match &state.open_with_apps {
+            Some((path, apps)) if *path == entry.path && apps.is_empty() => {
+                entries.push(menu_entry("No applications found", None));
+            }
+            Some((path, apps)) if *path == entry.path => {
+                entries.extend(apps.iter().take(MAX_OPEN_WITH_APPS).map(|app| {
+                    menu_entry(&app.name, Some(Message::OpenWith(app.desktop_file.clone())))
+                }));
+            }
+            _ => entries.push(menu_entry("Looking for applications...", None)),
+        }
+    }
+
+    // Pasting onto a folder puts the items inside it
+    let paste = if entry.is_dir {
+        Message::PasteIntoFolder(entry.path.clone())
+    } else {
+        Message::Paste
+    };
+    let has_clipboard = state.clipboard.is_some();
+    entries.extend([
+        menu_separator(),
+        menu_entry("Cut", Some(Message::CutSelection)),
+        menu_entry("Copy", Some(Message::CopySelection)),
+        menu_entry("Paste", Some(paste).filter(|_| has_clipboard)),
+        menu_separator(),
+        // Several items are renamed together in the batch rename dialog
+        menu_entry(
+            if tab.selection.len() > 1 { "Rename Items…" } else { "Rename" },
+            Some(Message::RenameSelection),
+        ),
+        menu_entry("Move to Trash", Some(Message::TrashSelection)),
+        menu_separator(),
+        menu_entry("Properties", Some(Message::ShowProperties)),
+        menu_entry("Copy Path", Some(Message::CopyPath)),
+    ]);
+    build_menu(entries)
+}
+
+// The menu of the empty space around the items, acting on the open folder
+fn build_background_menu<'a>(state: &'a FileManager, tab: &'a Tab) -> Element<'a, Message> {
+    let sort_choice = |label, criteria| {
+        menu_choice(
+            label,
+            tab.sort_criteria == criteria,
+            Message::SetSortCriteria(criteria),
+        )
+    };
+    let is_grouped = tab.group_criteria != GroupCriteria::None;
+
+    let mut entries = vec![
+        menu_entry("New Folder", Some(Message::NewFolder)),
+        menu_entry("New File", Some(Message::NewFile)),
+        menu_title("New Document"),
+    ];
+    if state.templates.is_empty() {
+        entries.push(menu_entry("No templates", None));
+    }
+    // Listed by name without the extension, like "Text Document"
+    entries.extend(state.templates.iter().map(|template| {
+        let name = template.file_stem().unwrap_or_default().to_string_lossy();
+        menu_entry(name, Some(Message::NewDocument(template.clone())))
+    }));
+
+    entries.extend([
+        menu_separator(),
+        menu_entry("Paste", Some(Message::Paste).filter(|_| state.clipboard.is_some())),
+        menu_entry("Select All", Some(Message::SelectAll)),
+        menu_separator(),
+        menu_title("Sort by"),
+        sort_choice("Name", SortCriteria::Name),
+        sort_choice("Size", SortCriteria::Size),
+        sort_choice("Modified", SortCriteria::ModifiedDate),
+        sort_choice("Type", SortCriteria::Type),
+        menu_choice(
+            "Descending",
+            tab.sort_order == SortOrder::Descending,
+            Message::ToggleSortOrder,
+        ),
+        menu_choice(
+            "Group by Type",
+            is_grouped,
+            Message::SetGroupCriteria(if is_grouped {
+                GroupCriteria::None
+            } else {
+                GroupCriteria::MimeType
+            }),
+        ),
+        menu_separator(),
+        menu_entry("Open Terminal Here", Some(Message::OpenTerminalHere)),
+    ]);
+    build_menu(entries)
+}
+
+// Gives an item of any view its right-click menu
+pub fn with_item_menu<'a>(
+    state: &'a FileManager,
+    tab: &'a Tab,
+    entry: &'a DirEntry,
+    content: impl Into<Element<'a, Message>>,
+) -> Element<'a, Message> {
+    ContextMenu::new(content, move || build_item_menu(state, tab, entry))
+        .on_open(Message::ItemContextMenu(entry.path.clone()))
+        .into()
+}
+
+// Gives a view its right-click menu for the space not covered by items
+pub fn with_background_menu<'a>(
+    state: &'a FileManager,
+    tab: &'a Tab,
+    content: impl Into<Element<'a, Message>>,
+) -> Element<'a, Message> {
+    ContextMenu::new(content, move || build_background_menu(state, tab))
+        .on_open(Message::BackgroundContextMenu)
+        .into()
+}
 
 // Helper function to create a single item widget
 fn create_item_widget<'a>(
+    state: &'a FileManager,
     entry: &'a DirEntry,
-    selected_path: &'a Option<PathBuf>,
+    tab: &'a Tab,
 ) -> Element<'a, Message, Theme, Renderer> {
     let path = entry.path.clone();
-    let is_selected = selected_path.as_ref() == Some(&path);
+    let is_selected = tab.selection.contains(&path);
 
     // Use entry.display_name directly
     let display_name_full = &entry.display_name;
@@ -50,28 +269,25 @@ fn create_item_widget<'a>(
             .content_fit(ContentFit::Contain) // Use imported ContentFit
     } else {
         // Use icon if no thumbnail
-        let icon_path_string = if entry.is_dir {
-            FOLDER_ICON_PATH.to_string() // Convert to String
-        } else {
-            // Use resolved icon for apps, otherwise generic file icon
-            entry
-                .resolved_icon_path
-                .as_ref()
-                .map(|p| p.to_string_lossy().into_owned())
-                .unwrap_or_else(|| FILE_ICON_PATH.to_string())
-        };
-        image(icon_path_string) // Pass String to image()
+        image(entry_icon_path(entry)) // Pass String to image()
             .width(Length::Fixed(48.0)) // Keep icon size consistent
             .height(Length::Fixed(48.0))
             .content_fit(ContentFit::Contain) // Use imported ContentFit
     };
 
+    let name: Element<'a, Message, Theme, Renderer> = if tab.is_renaming(&path) {
+        build_rename_input(tab)
+    } else {
+        text(display_name) // Use the potentially truncated display_name
+            .size(14)
+            .horizontal_alignment(Horizontal::Center)
+            .into()
+    };
+
     let item_button = button(
         column![
             item_content, // Use the determined content (thumbnail or icon)
-            text(display_name) // Use the potentially truncated display_name
-                .size(14)
-                .horizontal_alignment(Horizontal::Center)
+            name,
         ]
         .spacing(5)
         .align_items(Alignment::Center)
@@ -80,7 +296,17 @@ fn create_item_widget<'a>(
     .style(theme::Button::Text)
     .on_press(Message::ItemClicked(path.clone()));
 
+    // Middle-clicking a folder opens it in a new tab
+    let item_button: Element<'a, Message, Theme, Renderer> = if entry.is_dir {
+        mouse_area(item_button)
+            .on_middle_press(Message::OpenInNewTab(path.clone()))
+            .into()
+    } else {
+        item_button.into()
+    };
+
     let item_container = container(item_button)
+        .id(rubber_band::item_id(&path))
         .width(Length::Fixed(ITEM_WIDTH + PADDING))
         .height(Length::Shrink)
         .padding(PADDING / 2.0)
@@ -92,17 +318,18 @@ fn create_item_widget<'a>(
             theme::Container::Transparent
         });
 
-    item_container.into()
+    with_item_menu(state, tab, entry, item_container)
 }
 
 // Helper function to create a Wrap container for a list of entries
 fn create_wrap_for_entries<'a>(
+    state: &'a FileManager,
     entries: impl Iterator<Item = &'a DirEntry>,
-    selected_path: &'a Option<PathBuf>,
+    tab: &'a Tab,
 ) -> Element<'a, Message, Theme, Renderer> {
     entries
         .fold(Wrap::new(), |wrap_builder, entry| {
-            wrap_builder.push(create_item_widget(entry, selected_path))
+            wrap_builder.push(create_item_widget(state, entry, tab))
         })
         .spacing(SPACING)
         .line_spacing(SPACING)
@@ -110,7 +337,7 @@ fn create_wrap_for_entries<'a>(
 }
 
 // Helper function to create a group header
-fn create_group_header<'a>(
+pub fn create_group_header<'a>(
     group_name: &str,
     item_count: usize,
     is_collapsed: bool,
@@ -146,138 +373,74 @@ fn create_group_header<'a>(
     .into()
 }
 
-pub fn build_file_grid(state: &FileManager) -> Element<Message, Theme, Renderer> {
-    if let Some(error) = &state.error {
-        container(text(error).style(theme::Text::Color(iced::Color::from_rgb8(200, 0, 0))))
-            .padding(PADDING * 2.0)
-            .center_x()
-            .center_y()
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .into()
-    } else if state.entries.is_empty() {
-        container(text("Directory is empty").style(SECONDARY_TEXT_COLOR))
+// Entries by group as (group id, title, entries): folders first, then the
+// remaining groups by title
+pub fn grouped_entries(tab: &Tab) -> Vec<(String, String, Vec<&DirEntry>)> {
+    let mut groups: BTreeMap<(bool, String), (String, Vec<&DirEntry>)> = BTreeMap::new();
+    for entry in tab.shown_entries() {
+        if let Some((group_id, title)) = group_of(entry, tab.group_criteria) {
+            groups
+                .entry((!entry.is_dir, title))
+                .or_insert_with(|| (group_id, Vec::new()))
+                .1
+                .push(entry);
+        }
+    }
+    groups
+        .into_iter()
+        .map(|((_, title), (group_id, entries))| (group_id, title, entries))
+        .collect()
+}
+
+// The error or "empty" notice shown instead of the items, if any
+pub fn build_status_message(tab: &Tab) -> Option<Element<'_, Message, Theme, Renderer>> {
+    let message = if let Some(error) = &tab.error {
+        text(error).style(theme::Text::Color(iced::Color::from_rgb8(200, 0, 0)))
+    } else if tab.entries.is_empty() {
+        text("Directory is empty").style(SECONDARY_TEXT_COLOR)
+    } else if tab.shown_entries().next().is_none() {
+        text("No items match the filter").style(SECONDARY_TEXT_COLOR)
+    } else {
+        return None;
+    };
+    Some(
+        container(message)
             .padding(PADDING * 2.0)
             .center_x()
             .center_y()
             .width(Length::Fill)
             .height(Length::Fill)
-            .into()
+            .into(),
+    )
+}
+
+pub fn build_file_grid<'a>(state: &'a FileManager, tab: &'a Tab) -> Element<'a, Message, Theme, Renderer> {
+    let grid = if let Some(status) = build_status_message(tab) {
+        status
     } else {
-        let content: Element<Message, Theme, Renderer> = match state.group_criteria {
+        let content: Element<Message, Theme, Renderer> = match tab.group_criteria {
             GroupCriteria::None => {
-                let wrap_element =
-                    create_wrap_for_entries(state.entries.iter(), &state.selected_path);
+                let wrap_element = create_wrap_for_entries(state, tab.shown_entries(), tab);
                 container(wrap_element)
                     .width(Length::Fill)
                     .padding(PADDING)
                     .into()
             }
-            GroupCriteria::Type => {
-                let (folders, files): (Vec<_>, Vec<_>) =
-                    state.entries.iter().partition(|e| e.is_dir);
-
+            GroupCriteria::Type | GroupCriteria::MimeType => {
                 let mut main_column = Column::new().spacing(SPACING).padding(PADDING);
 
-                if !folders.is_empty() {
-                    let group_id = "folders".to_string();
-                    let is_collapsed = state.collapsed_groups.contains(&group_id);
-                    main_column = main_column.push(create_group_header(
-                        "Folders",
-                        folders.len(),
-                        is_collapsed,
-                        group_id.clone(),
-                    ));
-
-                    if !is_collapsed {
-                        let folder_element = create_wrap_for_entries(
-                            folders.iter().map(|&e| e),
-                            &state.selected_path,
-                        );
-                        main_column = main_column.push(
-                            container(folder_element)
-                                .width(Length::Fill)
-                                .padding([0.0, 0.0, 0.0, 20.0]),
-                        );
-                    }
-                    main_column = main_column.push(Rule::horizontal(1).style(theme::Rule::Default));
-                }
-
-                if !files.is_empty() {
-                    let group_id = "files".to_string();
-                    let is_collapsed = state.collapsed_groups.contains(&group_id);
+                for (group_id, title, entries) in grouped_entries(tab) {
+                    let is_collapsed = tab.collapsed_groups.contains(&group_id);
                     main_column = main_column.push(create_group_header(
-                        "Files",
-                        files.len(),
-                        is_collapsed,
-                        group_id.clone(),
-                    ));
-
-                    if !is_collapsed {
-                        let file_element =
-                            create_wrap_for_entries(files.iter().map(|&e| e), &state.selected_path);
-                        main_column = main_column.push(
-                            container(file_element)
-                                .width(Length::Fill)
-                                .padding([0.0, 0.0, 0.0, 20.0]),
-                        );
-                    }
-                    main_column = main_column.push(Rule::horizontal(1).style(theme::Rule::Default));
-                }
-
-                container(main_column).width(Length::Fill).into()
-            }
-            GroupCriteria::MimeType => {
-                let mut groups: BTreeMap<String, Vec<&DirEntry>> = BTreeMap::new();
-                for entry in &state.entries {
-                    let group_key = if entry.is_dir {
-                        "Folders".to_string()
-                    } else {
-                        entry
-                            .mime_group
-                            .clone()
-                            .unwrap_or_else(|| "Other".to_string())
-                    };
-                    groups.entry(group_key).or_default().push(entry);
-                }
-
-                let mut main_column = Column::new().spacing(SPACING).padding(PADDING);
-
-                if let Some(folders) = groups.remove("Folders") {
-                    let group_id = "folders".to_string();
-                    let is_collapsed = state.collapsed_groups.contains(&group_id);
-                    main_column = main_column.push(create_group_header(
-                        "Folders",
-                        folders.len(),
-                        is_collapsed,
-                        group_id.clone(),
-                    ));
-
-                    if !is_collapsed {
-                        let folder_element =
-                            create_wrap_for_entries(folders.into_iter(), &state.selected_path);
-                        main_column = main_column.push(
-                            container(folder_element)
-                                .width(Length::Fill)
-                                .padding([0.0, 0.0, 0.0, 20.0]),
-                        );
-                    }
-                    main_column = main_column.push(Rule::horizontal(1).style(theme::Rule::Default));
-                }
-
-                for (group_name, entries) in groups {
-                    let group_id = group_name.clone();
-                    let is_collapsed = state.collapsed_groups.contains(&group_id);
-                    main_column = main_column.push(create_group_header(
-                        &group_name,
+                        &title,
                         entries.len(),
                         is_collapsed,
-                        group_id.clone(),
+                        group_id,
                     ));
 
                     if !is_collapsed {
                         let group_element =
-                            create_wrap_for_entries(entries.into_iter(), &state.selected_path);
+                            create_wrap_for_entries(state, entries.into_iter(), tab);
                         main_column = main_column.push(
                             container(group_element)
                                 .width(Length::Fill)
@@ -290,9 +453,23 @@ pub fn build_file_grid(state: &FileManager) -> Element<Message, Theme, Renderer>
             }
         };
 
-        scrollable(content)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .into()
-    }
+        // Lets a drag on empty space select every item it touches
+        let items: HashMap<_, _> = tab
+            .shown_entries()
+            .map(|entry| (rubber_band::item_id(&entry.path).into(), entry.path.clone()))
+            .collect();
+
+        let tab_id = tab.id;
+        RubberBand::new(
+            scrollable(content)
+                .id(tab.scrollable_id())
+                .on_scroll(move |viewport| Message::GridScrolled(tab_id, viewport.absolute_offset()))
+                .width(Length::Fill)
+                .height(Length::Fill),
+            items,
+            Message::RubberBandSelect,
+        )
+        .into()
+    };
+    with_background_menu(state, tab, grid)
 }
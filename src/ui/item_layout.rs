@@ -0,0 +1,137 @@
+// Where the items of a file view are on screen.
+//
+// Moving the selection up or down in the wrapped grid depends on how many
+// items fit in a row, which only the layout knows. `locate_items` runs a
+// widget operation over a tab's scrollable and reports the bounds of every
+// registered item together with the scrollable's viewport.
+use iced::advanced::widget::{self, operation, Operation};
+use iced::widget::scrollable::{self, AbsoluteOffset};
+use iced::{Command, Rectangle, Vector};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Default)]
+pub struct ItemLayout {
+    viewport: Rectangle,
+    offset: Vector,
+    items: Vec<(PathBuf, Rectangle)>,
+}
+
+impl ItemLayout {
+    pub fn bounds(&self, path: &PathBuf) -> Option<Rectangle> {
+        self.items
+            .iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, bounds)| *bounds)
+    }
+
+    // The item in the next row above or below `from` that is closest to it
+    // horizontally
+    pub fn vertical_neighbour(&self, from: Rectangle, down: bool) -> Option<&PathBuf> {
+        let from_y = from.center_y();
+        let candidates: Vec<&(PathBuf, Rectangle)> = self
+            .items
+            .iter()
+            .filter(|(_, bounds)| {
+                if down {
+                    bounds.center_y() > from_y + 1.0
+                } else {
+                    bounds.center_y() < from_y - 1.0
+                }
+            })
+            .collect();
+        let row_y = candidates
+            .iter()
+            .map(|(_, bounds)| bounds.center_y())
+            .reduce(|a, b| if down { a.min(b) } else { a.max(b) })?;
+        candidates
+            .into_iter()
+            .filter(|(_, bounds)| (bounds.center_y() - row_y).abs() < 1.0)
+            .min_by(|(_, a), (_, b)| {
+                let distance = |bounds: &Rectangle| (bounds.center_x() - from.center_x()).abs();
+                distance(a).total_cmp(&distance(b))
+            })
+            .map(|(path, _)| path)
+    }
+
+    // The scroll position that brings `bounds` into view, if it is not already
+    pub fn scroll_into_view(&self, bounds: Rectangle) -> Option<AbsoluteOffset> {
+        let top = bounds.y - self.viewport.y;
+        let bottom = top + bounds.height;
+        let y = if top < self.offset.y {
+            top
+        } else if bottom > self.offset.y + self.viewport.height {
+            bottom - self.viewport.height
+        } else {
+            return None;
+        };
+        Some(AbsoluteOffset {
+            x: self.offset.x,
+            y: y.max(0.0),
+        })
+    }
+}
+
+struct LocateItems<Message> {
+    target: widget::Id,
+    inside_target: bool,
+    items: HashMap<widget::Id, PathBuf>,
+    layout: ItemLayout,
+    on_found: Box<dyn Fn(ItemLayout) -> Message>,
+}
+
+impl<Message> Operation<Message> for LocateItems<Message> {
+    fn container(
+        &mut self,
+        id: Option<&widget::Id>,
+        bounds: Rectangle,
+        operate_on_children: &mut dyn FnMut(&mut dyn Operation<Message>),
+    ) {
+        if id == Some(&self.target) {
+            self.inside_target = true;
+            operate_on_children(self);
+            self.inside_target = false;
+            return;
+        }
+        if self.inside_target {
+            if let Some(path) = id.and_then(|id| self.items.get(id)) {
+                self.layout.items.push((path.clone(), bounds));
+            }
+        }
+        operate_on_children(self);
+    }
+
+    fn scrollable(
+        &mut self,
+        _state: &mut dyn operation::Scrollable,
+        id: Option<&widget::Id>,
+        bounds: Rectangle,
+        translation: Vector,
+    ) {
+        if id == Some(&self.target) {
+            self.layout.viewport = bounds;
+            self.layout.offset = translation;
+        }
+    }
+
+    fn finish(&self) -> operation::Outcome<Message> {
+        operation::Outcome::Some((self.on_found)(self.layout.clone()))
+    }
+}
+
+// Finds the items in `items` inside the scrollable `scrollable_id`. Only that
+// scrollable is searched, so the same folder open in the other pane is not
+// mixed in.
+pub fn locate_items<Message: 'static>(
+    scrollable_id: scrollable::Id,
+    items: HashMap<widget::Id, PathBuf>,
+    on_found: impl Fn(ItemLayout) -> Message + 'static,
+) -> Command<Message> {
+    Command::widget(LocateItems {
+        target: scrollable_id.into(),
+        inside_target: false,
+        items,
+        layout: ItemLayout::default(),
+        on_found: Box::new(on_found),
+    })
+}
@@ -0,0 +1,158 @@
+use crate::app::{FileManager, Message};
+use crate::fs_utils;
+use crate::jobs::{Job, JobStatus};
+use crate::ui::styles::SECONDARY_TEXT_COLOR;
+use iced::widget::{button, column, container, progress_bar, row, scrollable, text, Column};
+use iced::{theme, Alignment, Element, Length};
+use std::time::Duration;
+
+const PADDING: f32 = 8.0;
+const SPACING: f32 = 10.0;
+const PANEL_MAX_HEIGHT: f32 = 220.0;
+const PROGRESS_BAR_HEIGHT: f32 = 6.0;
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 3600 {
+        format!("{}h {:02}m", seconds / 3600, (seconds % 3600) / 60)
+    } else if seconds >= 60 {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+// "1.2 GB of 40.0 GB, 120 of 3000 files, 85.3 MB/s, 3m 05s left"
+fn progress_summary(job: &Job) -> String {
+    let progress = &job.progress;
+    let mut parts = vec![
+        format!(
+            "{} of {}",
+            fs_utils::format_size(Some(progress.bytes_done)),
+            fs_utils::format_size(Some(progress.bytes_total))
+        ),
+        format!("{} of {} files", progress.files_done, progress.files_total),
+    ];
+    if let Some(rate) = job.throughput() {
+        parts.push(format!("{}/s", fs_utils::format_size(Some(rate as u64))));
+    }
+    if let Some(eta) = job.eta() {
+        parts.push(format!("{} left", format_duration(eta)));
+    }
+    parts.join(", ")
+}
+
+fn status_text(job: &Job) -> String {
+    match &job.status {
+        JobStatus::Queued => "Queued".to_string(),
+        JobStatus::Running => progress_summary(job),
+        JobStatus::Paused => format!("Paused, {}", progress_summary(job)),
+        JobStatus::WaitingForAnswer => match &job.pending_conflict {
+            Some(conflict) => format!(
+                "Waiting for a decision about '{}'",
+                conflict
+                    .destination
+                    .path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+            ),
+            None => "Waiting for a decision".to_string(),
+        },
+        JobStatus::Completed => format!(
+            "Done, {} in {} files",
+            fs_utils::format_size(Some(job.progress.bytes_done)),
+            job.progress.files_done
+        ),
+        JobStatus::Failed(e) => format!("Failed: {}", e),
+        JobStatus::Cancelled => "Cancelled".to_string(),
+    }
+}
+
+fn job_row(job: &Job) -> Element<'_, Message> {
+    let controls = match job.status {
+        JobStatus::Running => row![
+            button(text("Pause"))
+                .on_press(Message::PauseJob(job.id))
+                .style(theme::Button::Secondary),
+            button(text("Cancel"))
+                .on_press(Message::CancelJob(job.id))
+                .style(theme::Button::Destructive),
+        ],
+        JobStatus::Paused => row![
+            button(text("Resume"))
+                .on_press(Message::ResumeJob(job.id))
+                .style(theme::Button::Secondary),
+            button(text("Cancel"))
+                .on_press(Message::CancelJob(job.id))
+                .style(theme::Button::Destructive),
+        ],
+        JobStatus::Queued | JobStatus::WaitingForAnswer => row![button(text("Cancel"))
+            .on_press(Message::CancelJob(job.id))
+            .style(theme::Button::Destructive)],
+        _ => row![],
+    }
+    .spacing(SPACING / 2.0)
+    .align_items(Alignment::Center);
+
+    let current = job
+        .current
+        .as_ref()
+        .map(|path| path.display().to_string())
+        .unwrap_or_default();
+
+    let mut details = column![
+        text(job.title()),
+        text(status_text(job)).size(12).style(SECONDARY_TEXT_COLOR),
+    ]
+    .spacing(4)
+    .width(Length::Fill);
+    if !job.is_finished() {
+        details = details.push(
+            progress_bar(0.0..=1.0, job.progress.fraction())
+                .height(Length::Fixed(PROGRESS_BAR_HEIGHT)),
+        );
+    }
+    if !current.is_empty() {
+        details = details.push(text(current).size(12).style(SECONDARY_TEXT_COLOR));
+    }
+
+    row![details, controls]
+        .spacing(SPACING)
+        .align_items(Alignment::Center)
+        .into()
+}
+
+// Running, queued and finished copy/move jobs, newest last
+pub fn build_jobs_panel(state: &FileManager) -> Element<'_, Message> {
+    let jobs = state.jobs.jobs();
+    let has_finished = jobs.iter().any(|job| job.is_finished());
+
+    let header = row![
+        text(format!("Jobs ({} active)", state.jobs.active_count())).width(Length::Fill),
+        button(text("Clear finished"))
+            .on_press_maybe(has_finished.then_some(Message::ClearFinishedJobs))
+            .style(theme::Button::Secondary),
+        button(text("Hide"))
+            .on_press(Message::ToggleJobsPanel)
+            .style(theme::Button::Secondary),
+    ]
+    .spacing(SPACING)
+    .align_items(Alignment::Center);
+
+    let list = jobs
+        .iter()
+        .fold(Column::new().spacing(SPACING), |list, job| list.push(job_row(job)));
+
+    container(
+        column![
+            header,
+            scrollable(list).height(Length::Shrink)
+        ]
+        .spacing(SPACING),
+    )
+    .padding(PADDING)
+    .width(Length::Fill)
+    .max_height(PANEL_MAX_HEIGHT)
+    .into()
+}
@@ -0,0 +1,236 @@
+use crate::app::{FileManager, GroupCriteria, Message, SortOrder};
+use crate::columns::{ColumnLayout, ListColumn};
+use crate::constants::{COLLAPSED_ICON_PATH, EXPANDED_ICON_PATH};
+use crate::fs_utils::{self, DirEntry};
+use crate::tab::Tab;
+use crate::ui::drag_area::DragArea;
+use crate::ui::file_grid::{
+    build_rename_input, build_status_message, create_group_header, entry_icon_path, grouped_entries,
+    with_background_menu, with_item_menu,
+};
+use crate::ui::rubber_band::{self, RubberBand};
+use crate::ui::styles::{RuleStyle, SelectedItemStyle, SECONDARY_TEXT_COLOR};
+use iced::widget::{
+    button, container, image, mouse_area, row, scrollable, text, Column, Row, Rule,
+};
+use iced::{mouse, theme, Alignment, Element, Length};
+use std::collections::HashMap;
+
+const PADDING: f32 = 8.0;
+const SPACING: f32 = 10.0;
+const ROW_ICON_SIZE: f32 = 20.0;
+const SORT_ICON_SIZE: f32 = 12.0;
+const HANDLE_WIDTH: f32 = 6.0;
+const TEXT_SIZE: u16 = 14;
+
+// What the Type column shows: "Folder", the extension or "File"
+fn type_label(entry: &DirEntry) -> String {
+    if entry.is_dir {
+        "Folder".to_string()
+    } else {
+        entry
+            .path
+            .extension()
+            .map(|ext| format!("{} file", ext.to_string_lossy().to_uppercase()))
+            .unwrap_or_else(|| "File".to_string())
+    }
+}
+
+fn cell_text(entry: &DirEntry, column: ListColumn) -> String {
+    match column {
+        ListColumn::Name => entry.display_name.clone(),
+        ListColumn::Size if entry.is_dir => "-".to_string(),
+        ListColumn::Size => fs_utils::format_size(entry.size),
+        ListColumn::Modified => fs_utils::format_modified(entry.modified),
+        ListColumn::Type => type_label(entry),
+        ListColumn::Permissions => fs_utils::format_permissions(entry.permissions),
+        ListColumn::Owner => fs_utils::format_owner(entry.owner),
+    }
+}
+
+// Clicking a header sorts by it, dragging moves it and dragging its right edge
+// resizes it
+fn build_header<'a>(tab: &Tab, columns: &ColumnLayout) -> Element<'a, Message> {
+    let header = columns.iter().fold(Row::new(), |header, (column, width)| {
+        let sort_criteria = column.sort_criteria();
+        let mut title = row![text(column.title())
+            .size(TEXT_SIZE)
+            .style(SECONDARY_TEXT_COLOR)]
+        .spacing(4)
+        .align_items(Alignment::Center);
+        if sort_criteria == Some(tab.sort_criteria) {
+            let icon_path = match tab.sort_order {
+                SortOrder::Ascending => EXPANDED_ICON_PATH,
+                SortOrder::Descending => COLLAPSED_ICON_PATH,
+            };
+            title = title.push(
+                image(icon_path)
+                    .width(Length::Fixed(SORT_ICON_SIZE))
+                    .height(Length::Fixed(SORT_ICON_SIZE)),
+            );
+        }
+
+        let mut title_area = DragArea::new(
+            container(title)
+                .padding([PADDING / 2.0, PADDING / 2.0])
+                .width(Length::Fill)
+                .clip(true),
+        )
+        .on_drop(move |offset| Message::MoveColumn(column, offset));
+        if let Some(criteria) = sort_criteria {
+            title_area = title_area.on_click(Message::SetSortCriteria(criteria));
+        }
+
+        let resize_handle = DragArea::new(
+            container(Rule::vertical(1).style(theme::Rule::Custom(Box::new(RuleStyle))))
+                .width(Length::Fixed(HANDLE_WIDTH))
+                .height(Length::Fill)
+                .center_x(),
+        )
+        .on_drag(move |delta| Message::ResizeColumn(column, delta))
+        .interaction(mouse::Interaction::ResizingHorizontally);
+
+        header.push(
+            row![title_area, resize_handle]
+                .width(Length::Fixed(width))
+                .height(Length::Fixed(28.0))
+                .align_items(Alignment::Center),
+        )
+    });
+
+    container(header).padding([0.0, PADDING]).into()
+}
+
+fn create_list_row<'a>(
+    state: &'a FileManager,
+    entry: &'a DirEntry,
+    tab: &'a Tab,
+    columns: &ColumnLayout,
+) -> Element<'a, Message> {
+    let path = entry.path.clone();
+    let is_selected = tab.selection.contains(&path);
+
+    let cells = columns.iter().fold(Row::new(), |cells, (column, width)| {
+        let label = text(cell_text(entry, column)).size(TEXT_SIZE);
+        let cell: Element<Message> = if column == ListColumn::Name {
+            let name: Element<Message> = if tab.is_renaming(&path) {
+                build_rename_input(tab)
+            } else {
+                label.into()
+            };
+            row![
+                image(entry_icon_path(entry))
+                    .width(Length::Fixed(ROW_ICON_SIZE))
+                    .height(Length::Fixed(ROW_ICON_SIZE)),
+                name,
+            ]
+            .spacing(SPACING / 2.0)
+            .align_items(Alignment::Center)
+            .into()
+        } else {
+            label.style(SECONDARY_TEXT_COLOR).into()
+        };
+        cells.push(
+            container(cell)
+                .width(Length::Fixed(width))
+                .padding([0.0, PADDING / 2.0])
+                .clip(true),
+        )
+    });
+
+    let row_button = button(cells.align_items(Alignment::Center))
+        .style(theme::Button::Text)
+        .padding([2.0, 0.0])
+        .on_press(Message::ItemClicked(path.clone()));
+
+    // Middle-clicking a folder opens it in a new tab
+    let row_button: Element<'a, Message> = if entry.is_dir {
+        mouse_area(row_button)
+            .on_middle_press(Message::OpenInNewTab(path.clone()))
+            .into()
+    } else {
+        row_button.into()
+    };
+
+    let row_container = container(row_button)
+        .id(rubber_band::item_id(&path))
+        .width(Length::Fixed(columns.total_width()))
+        .style(if is_selected {
+            theme::Container::Custom(Box::new(SelectedItemStyle))
+        } else {
+            theme::Container::Transparent
+        });
+    with_item_menu(state, tab, entry, row_container)
+}
+
+fn create_rows<'a>(
+    state: &'a FileManager,
+    entries: impl Iterator<Item = &'a DirEntry>,
+    tab: &'a Tab,
+    columns: &ColumnLayout,
+) -> Column<'a, Message> {
+    entries.fold(Column::new(), |rows, entry| {
+        rows.push(create_list_row(state, entry, tab, columns))
+    })
+}
+
+// The details view: one row per item with the list view's columns
+pub fn build_list_view<'a>(state: &'a FileManager, tab: &'a Tab) -> Element<'a, Message> {
+    let columns = &state.list_columns;
+    let header = build_header(tab, columns);
+    let body = if let Some(status) = build_status_message(tab) {
+        status
+    } else {
+        let content: Element<Message> = match tab.group_criteria {
+            GroupCriteria::None => create_rows(state, tab.shown_entries(), tab, columns)
+                .padding(PADDING)
+                .into(),
+            GroupCriteria::Type | GroupCriteria::MimeType => {
+                let mut main_column = Column::new().spacing(SPACING / 2.0).padding(PADDING);
+                for (group_id, title, entries) in grouped_entries(tab) {
+                    let is_collapsed = tab.collapsed_groups.contains(&group_id);
+                    main_column = main_column.push(create_group_header(
+                        &title,
+                        entries.len(),
+                        is_collapsed,
+                        group_id,
+                    ));
+                    if !is_collapsed {
+                        main_column =
+                            main_column.push(create_rows(state, entries.into_iter(), tab, columns));
+                    }
+                    main_column = main_column.push(Rule::horizontal(1).style(theme::Rule::Default));
+                }
+                main_column.into()
+            }
+        };
+
+        // Lets a drag on empty space select every row it touches
+        let items: HashMap<_, _> = tab
+            .shown_entries()
+            .map(|entry| (rubber_band::item_id(&entry.path).into(), entry.path.clone()))
+            .collect();
+
+        let tab_id = tab.id;
+        RubberBand::new(
+            scrollable(container(content).width(Length::Fill))
+                .id(tab.scrollable_id())
+                .on_scroll(move |viewport| {
+                    Message::GridScrolled(tab_id, viewport.absolute_offset())
+                })
+                .width(Length::Fill)
+                .height(Length::Fill),
+            items,
+            Message::RubberBandSelect,
+        )
+        .into()
+    };
+
+    Column::new()
+        .push(header)
+        .push(Rule::horizontal(1).style(theme::Rule::Custom(Box::new(RuleStyle))))
+        .push(with_background_menu(state, tab, body))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
@@ -1,6 +1,18 @@
+pub mod breadcrumbs;
+pub mod column_view;
+pub mod context_menu;
 pub mod details_panel;
+pub mod drag_area;
 pub mod file_grid;
+pub mod item_layout;
+pub mod jobs_panel;
+pub mod list_view;
+pub mod modal;
+pub mod rubber_band;
+pub mod search_results;
 pub mod sidebar;
 pub mod styles;
+pub mod tab_bar;
 pub mod top_bar;
+pub mod trash_view;
 pub mod view; // Make the main view function public
@@ -0,0 +1,269 @@
+use crate::app::{FileManager, Message};
+use crate::batch_rename::{CaseChange, RuleEdit};
+use crate::dialog::{BatchRenameDialog, ConfirmDialog, ConflictDialog, Dialog};
+use crate::fs_utils::{self, ConflictChoice};
+use crate::jobs::ItemInfo;
+use crate::ui::styles::SECONDARY_TEXT_COLOR;
+use iced::widget::{
+    button, checkbox, column, container, pick_list, row, scrollable, text, text_input, Column,
+    Space,
+};
+use iced::{theme, Alignment, Element, Length};
+use iced_aw::Card;
+
+const DIALOG_WIDTH: f32 = 480.0;
+const CONFLICT_DIALOG_WIDTH: f32 = 600.0;
+const BATCH_RENAME_DIALOG_WIDTH: f32 = 680.0;
+const ITEM_LIST_HEIGHT: f32 = 180.0;
+const PREVIEW_HEIGHT: f32 = 240.0;
+const FIELD_LABEL_WIDTH: f32 = 90.0;
+const SPACING: f32 = 10.0;
+
+fn confirm_dialog(dialog: &ConfirmDialog) -> Element<'_, Message> {
+    let item_list = dialog.items.iter().fold(
+        Column::new().spacing(4).width(Length::Fill),
+        |list, item| {
+            list.push(
+                row![
+                    text(&item.label).width(Length::Fill),
+                    text(fs_utils::format_size(Some(item.size))).style(SECONDARY_TEXT_COLOR),
+                ]
+                .spacing(SPACING),
+            )
+        },
+    );
+
+    let summary = format!(
+        "{} item{}, {} in total",
+        dialog.items.len(),
+        if dialog.items.len() == 1 { "" } else { "s" },
+        fs_utils::format_size(Some(dialog.total_size()))
+    );
+
+    let body = column![
+        text(&dialog.message),
+        container(scrollable(item_list)).max_height(ITEM_LIST_HEIGHT),
+        text(summary).style(SECONDARY_TEXT_COLOR),
+    ]
+    .spacing(SPACING);
+
+    let footer = row![
+        Space::with_width(Length::Fill),
+        button(text("Cancel"))
+            .on_press(Message::DialogCancelled)
+            .style(theme::Button::Secondary),
+        button(text(&dialog.confirm_label))
+            .on_press(Message::DialogConfirmed)
+            .style(theme::Button::Destructive),
+    ]
+    .spacing(SPACING)
+    .align_items(Alignment::Center);
+
+    Card::new(text(&dialog.title).size(16), body)
+        .foot(footer)
+        .max_width(DIALOG_WIDTH)
+        .on_close(Message::DialogCancelled)
+        .into()
+}
+
+// One side of a conflict: where the item is, how big and how old it is
+fn item_info<'a>(heading: &str, item: &'a ItemInfo) -> Element<'a, Message> {
+    let location = item
+        .path
+        .parent()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default();
+    column![
+        text(heading).style(SECONDARY_TEXT_COLOR),
+        text(location),
+        text(format!(
+            "{}: {}",
+            if item.is_dir { "Folder" } else { "File" },
+            fs_utils::format_size(Some(item.size))
+        )),
+        text(format!(
+            "Modified: {}",
+            fs_utils::format_modified(item.modified)
+        )),
+    ]
+    .spacing(4)
+    .width(Length::Fill)
+    .into()
+}
+
+fn conflict_dialog(dialog: &ConflictDialog) -> Element<'_, Message> {
+    let conflict = &dialog.conflict;
+    let name = conflict
+        .destination
+        .path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+
+    let body = column![
+        text(if conflict.can_merge() {
+            "A folder with the same name already exists. Merging combines both folders; conflicts inside are asked again."
+        } else {
+            "An item with the same name already exists in the destination."
+        }),
+        row![
+            item_info("Source", &conflict.source),
+            item_info("Destination", &conflict.destination),
+        ]
+        .spacing(SPACING * 2.0),
+        checkbox("Apply to all remaining conflicts", dialog.apply_to_all)
+            .on_toggle(Message::ConflictApplyToAllToggled),
+    ]
+    .spacing(SPACING);
+
+    let mut footer = row![
+        button(text("Cancel job"))
+            .on_press(Message::CancelJob(dialog.job_id))
+            .style(theme::Button::Secondary),
+        Space::with_width(Length::Fill),
+        button(text("Skip"))
+            .on_press(Message::ResolveConflict(ConflictChoice::Skip))
+            .style(theme::Button::Secondary),
+        button(text("Keep both"))
+            .on_press(Message::ResolveConflict(ConflictChoice::KeepBoth))
+            .style(theme::Button::Secondary),
+    ]
+    .spacing(SPACING)
+    .align_items(Alignment::Center);
+    if conflict.can_merge() {
+        footer = footer.push(
+            button(text("Merge"))
+                .on_press(Message::ResolveConflict(ConflictChoice::Merge))
+                .style(theme::Button::Primary),
+        );
+    }
+    footer = footer.push(
+        button(text("Replace"))
+            .on_press(Message::ResolveConflict(ConflictChoice::Replace))
+            .style(theme::Button::Destructive),
+    );
+
+    Card::new(text(format!("'{}' already exists", name)).size(16), body)
+        .foot(footer)
+        .max_width(CONFLICT_DIALOG_WIDTH)
+        .on_close(Message::DialogCancelled)
+        .into()
+}
+
+fn labeled<'a>(label: &str, field: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    row![text(label).width(FIELD_LABEL_WIDTH), field.into()]
+        .spacing(SPACING)
+        .align_items(Alignment::Center)
+        .width(Length::Fill)
+        .into()
+}
+
+fn rule_input<'a>(
+    placeholder: &str,
+    value: &str,
+    edit: impl Fn(String) -> RuleEdit + 'a,
+) -> Element<'a, Message> {
+    text_input(placeholder, value)
+        .on_input(move |value| Message::BatchRenameEdited(edit(value)))
+        .padding(5)
+        .into()
+}
+
+fn batch_rename_dialog(dialog: &BatchRenameDialog) -> Element<'_, Message> {
+    let rules = &dialog.rules;
+    let error_color = theme::Text::Color(iced::Color::from_rgb8(200, 0, 0));
+
+    let fields = column![
+        row![
+            labeled("Find", rule_input("Text to find", &rules.find, RuleEdit::Find)),
+            labeled(
+                "Replace with",
+                rule_input("Replacement", &rules.replace, RuleEdit::Replace)
+            ),
+        ]
+        .spacing(SPACING * 2.0),
+        checkbox("Regular expression ($1 inserts a group)", rules.use_regex)
+            .on_toggle(|on| Message::BatchRenameEdited(RuleEdit::UseRegex(on))),
+        row![
+            labeled("Prefix", rule_input("Before the name", &rules.prefix, RuleEdit::Prefix)),
+            labeled("Suffix", rule_input("After the name", &rules.suffix, RuleEdit::Suffix)),
+        ]
+        .spacing(SPACING * 2.0),
+        row![
+            labeled(
+                "Start {n} at",
+                rule_input("1", &rules.start.to_string(), RuleEdit::Start)
+            ),
+            labeled(
+                "Digits",
+                rule_input("2", &rules.padding.to_string(), RuleEdit::Padding)
+            ),
+            labeled(
+                "Case",
+                pick_list(&CaseChange::ALL[..], Some(rules.case), |case| {
+                    Message::BatchRenameEdited(RuleEdit::Case(case))
+                })
+                .width(Length::Fill)
+            ),
+        ]
+        .spacing(SPACING * 2.0),
+        text("Tokens: {n} number, {name} old name, {ext} extension, {date} date modified, {exif} date taken")
+            .size(12)
+            .style(SECONDARY_TEXT_COLOR),
+    ]
+    .spacing(SPACING);
+
+    let preview: Element<Message> = match &dialog.preview {
+        Ok(planned) => {
+            let list = planned.iter().fold(
+                Column::new().spacing(4).width(Length::Fill),
+                |list, name| {
+                    let old_name = name.source.file_name().unwrap_or_default().to_string_lossy();
+                    let mut line = row![
+                        text(old_name).width(Length::FillPortion(2)),
+                        text("→").style(SECONDARY_TEXT_COLOR),
+                        text(&name.new_name).width(Length::FillPortion(2)),
+                    ]
+                    .spacing(SPACING);
+                    if let Some(problem) = &name.problem {
+                        line = line.push(text(problem).style(error_color).width(Length::FillPortion(1)));
+                    }
+                    list.push(line)
+                },
+            );
+            container(scrollable(list)).max_height(PREVIEW_HEIGHT).into()
+        }
+        Err(e) => text(e).style(error_color).into(),
+    };
+
+    let body = column![fields, text("Preview").style(SECONDARY_TEXT_COLOR), preview]
+        .spacing(SPACING);
+
+    let footer = row![
+        Space::with_width(Length::Fill),
+        button(text("Cancel"))
+            .on_press(Message::DialogCancelled)
+            .style(theme::Button::Secondary),
+        button(text("Rename"))
+            .on_press_maybe(dialog.ready().map(|_| Message::DialogConfirmed))
+            .style(theme::Button::Primary),
+    ]
+    .spacing(SPACING)
+    .align_items(Alignment::Center);
+
+    let title = format!("Rename {} items", dialog.items.len());
+    Card::new(text(title).size(16), body)
+        .foot(footer)
+        .max_width(BATCH_RENAME_DIALOG_WIDTH)
+        .on_close(Message::DialogCancelled)
+        .into()
+}
+
+// The content of the modal layer, if a dialog is open
+pub fn build_dialog(state: &FileManager) -> Option<Element<'_, Message>> {
+    state.dialog.as_ref().map(|dialog| match dialog {
+        Dialog::Confirm(confirm) => confirm_dialog(confirm),
+        Dialog::Conflict(conflict) => conflict_dialog(conflict),
+        Dialog::BatchRename(batch_rename) => batch_rename_dialog(batch_rename),
+    })
+}
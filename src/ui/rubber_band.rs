@@ -0,0 +1,291 @@
+// Rubber-band (marquee) selection around the file grid.
+//
+// Dragging on empty space draws a selection rectangle. On release, every item
+// container whose id is registered in `items` and which intersects the
+// rectangle is reported through `on_select`. A plain click on empty space
+// reports an empty selection.
+use crate::ui::styles::{ACCENT_COLOR, SELECTED_BG_COLOR};
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::renderer::{self, Quad, Renderer as _};
+use iced::advanced::widget::{self, tree, Operation, Tree, Widget};
+use iced::advanced::{overlay, Clipboard, Shell};
+use iced::{event, mouse, Border, Element, Event, Length, Point, Rectangle, Size, Vector};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+// Movement below this distance is treated as a click rather than a drag
+const DRAG_THRESHOLD: f32 = 4.0;
+
+pub struct RubberBand<'a, Message> {
+    content: Element<'a, Message>,
+    items: HashMap<widget::Id, PathBuf>,
+    on_select: Box<dyn Fn(Vec<PathBuf>) -> Message + 'a>,
+}
+
+impl<'a, Message> RubberBand<'a, Message> {
+    pub fn new(
+        content: impl Into<Element<'a, Message>>,
+        items: HashMap<widget::Id, PathBuf>,
+        on_select: impl Fn(Vec<PathBuf>) -> Message + 'a,
+    ) -> Self {
+        RubberBand {
+            content: content.into(),
+            items,
+            on_select: Box::new(on_select),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    origin: Option<Point>,
+    current: Option<Point>,
+}
+
+impl State {
+    fn rectangle(&self) -> Option<Rectangle> {
+        let (origin, current) = (self.origin?, self.current?);
+        let top_left = Point::new(origin.x.min(current.x), origin.y.min(current.y));
+        Some(Rectangle::new(
+            top_left,
+            Size::new((origin.x - current.x).abs(), (origin.y - current.y).abs()),
+        ))
+    }
+}
+
+// Collects the registered items intersecting `area`, undoing the scroll
+// translation of any scrollable on the way down
+struct CollectItems<'b> {
+    items: &'b HashMap<widget::Id, PathBuf>,
+    area: Rectangle,
+    translation: Vector,
+    found: Vec<PathBuf>,
+}
+
+impl<'b, T> Operation<T> for CollectItems<'b> {
+    fn container(
+        &mut self,
+        id: Option<&widget::Id>,
+        bounds: Rectangle,
+        operate_on_children: &mut dyn FnMut(&mut dyn Operation<T>),
+    ) {
+        if let Some(path) = id.and_then(|id| self.items.get(id)) {
+            if (bounds - self.translation).intersects(&self.area) {
+                self.found.push(path.clone());
+            }
+        }
+        operate_on_children(self);
+    }
+
+    fn scrollable(
+        &mut self,
+        _state: &mut dyn widget::operation::Scrollable,
+        _id: Option<&widget::Id>,
+        _bounds: Rectangle,
+        translation: Vector,
+    ) {
+        self.translation = self.translation + translation;
+    }
+}
+
+impl<'a, Message> Widget<Message, iced::Theme, iced::Renderer> for RubberBand<'a, Message> {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &iced::Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        let content = self
+            .content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits);
+        layout::Node::with_children(content.size(), vec![content])
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &iced::Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        self.content.as_widget().operate(
+            &mut tree.children[0],
+            layout.children().next().unwrap(),
+            renderer,
+            operation,
+        );
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &iced::Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        let status = self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event.clone(),
+            layout.children().next().unwrap(),
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        );
+
+        let state = tree.state.downcast_mut::<State>();
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                if status == event::Status::Ignored =>
+            {
+                if let Some(position) = cursor.position_over(layout.bounds()) {
+                    state.origin = Some(position);
+                    state.current = Some(position);
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) if state.origin.is_some() => {
+                state.current = Some(position);
+                return event::Status::Captured;
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                if state.origin.is_some() =>
+            {
+                let area = state.rectangle().unwrap_or_default();
+                state.origin = None;
+                state.current = None;
+
+                let mut collect = CollectItems {
+                    items: &self.items,
+                    area,
+                    translation: Vector::ZERO,
+                    found: Vec::new(),
+                };
+                if area.width.max(area.height) >= DRAG_THRESHOLD {
+                    self.content.as_widget().operate(
+                        &mut tree.children[0],
+                        layout.children().next().unwrap(),
+                        renderer,
+                        &mut collect,
+                    );
+                }
+                shell.publish((self.on_select)(collect.found));
+                return event::Status::Captured;
+            }
+            _ => {}
+        }
+
+        status
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut iced::Renderer,
+        theme: &iced::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout.children().next().unwrap(),
+            cursor,
+            viewport,
+        );
+
+        let state = tree.state.downcast_ref::<State>();
+        if let Some(area) = state.rectangle() {
+            let bounds = layout.bounds();
+            if let Some(visible) = area.intersection(&bounds) {
+                renderer.with_layer(bounds, |renderer| {
+                    renderer.fill_quad(
+                        Quad {
+                            bounds: visible,
+                            border: Border {
+                                color: ACCENT_COLOR,
+                                width: 1.0,
+                                radius: 0.0.into(),
+                            },
+                            ..Quad::default()
+                        },
+                        SELECTED_BG_COLOR,
+                    );
+                });
+            }
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &iced::Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout.children().next().unwrap(),
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &iced::Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, iced::Theme, iced::Renderer>> {
+        self.content.as_widget_mut().overlay(
+            &mut tree.children[0],
+            layout.children().next().unwrap(),
+            renderer,
+            translation,
+        )
+    }
+}
+
+impl<'a, Message: 'a> From<RubberBand<'a, Message>> for Element<'a, Message> {
+    fn from(rubber_band: RubberBand<'a, Message>) -> Self {
+        Element::new(rubber_band)
+    }
+}
+
+// The container id used to find an item of the grid during a rubber-band drag
+pub fn item_id(path: &std::path::Path) -> iced::widget::container::Id {
+    iced::widget::container::Id::new(path.to_string_lossy().into_owned())
+}
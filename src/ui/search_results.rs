@@ -0,0 +1,281 @@
+use crate::app::Message;
+use crate::constants::{FILE_ICON_PATH, FOLDER_ICON_PATH};
+use crate::search::{LineMatch, Search, SearchHit, TextPreview, MAX_HITS};
+use crate::ui::styles::{RuleStyle, SelectedItemStyle, ACCENT_COLOR, SECONDARY_TEXT_COLOR};
+use iced::widget::scrollable::AbsoluteOffset;
+use iced::widget::text::LineHeight;
+use iced::widget::{
+    button, column, container, image, row, scrollable, text, Column, Row, Rule, Space,
+};
+use iced::{theme, Alignment, Element, Font, Length, Pixels};
+
+const PADDING: f32 = 8.0;
+const SPACING: f32 = 10.0;
+const ROW_ICON_SIZE: f32 = 24.0;
+const PREVIEW_TEXT_SIZE: f32 = 13.0;
+// Every preview line is this tall, so a line number gives its scroll offset
+const PREVIEW_LINE_HEIGHT: f32 = 18.0;
+const PREVIEW_NUMBER_WIDTH: f32 = 56.0;
+// Lines left visible above the match when the preview scrolls to it
+const PREVIEW_LINES_ABOVE: usize = 5;
+
+fn error_text<'a>(message: &str) -> iced::widget::Text<'a> {
+    text(message).style(theme::Text::Color(iced::Color::from_rgb8(200, 0, 0)))
+}
+
+// Where the item is, relative to the folder that was searched
+fn location(hit: &SearchHit) -> String {
+    match hit.relative.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.display().to_string(),
+        _ => ".".to_string(),
+    }
+}
+
+fn create_result_row(hit: &SearchHit) -> Element<'_, Message> {
+    let icon_path = if hit.is_dir {
+        FOLDER_ICON_PATH
+    } else {
+        FILE_ICON_PATH
+    };
+    let name = hit.path.file_name().unwrap_or_default().to_string_lossy();
+
+    let info = button(
+        row![
+            image(icon_path)
+                .width(Length::Fixed(ROW_ICON_SIZE))
+                .height(Length::Fixed(ROW_ICON_SIZE)),
+            column![text(name), text(location(hit)).style(SECONDARY_TEXT_COLOR)]
+                .spacing(2)
+                .width(Length::Fill),
+        ]
+        .spacing(SPACING)
+        .align_items(Alignment::Center),
+    )
+    .on_press(Message::OpenSearchResult(hit.path.clone()))
+    .style(theme::Button::Text)
+    .width(Length::Fill);
+
+    row![
+        info,
+        button(text("Show in folder"))
+            .on_press(Message::RevealItem(hit.path.clone()))
+            .style(theme::Button::Secondary),
+    ]
+    .spacing(SPACING)
+    .padding(PADDING / 2.0)
+    .align_items(Alignment::Center)
+    .into()
+}
+
+// The snippet with the matched part in the accent colour
+fn highlighted_snippet(line_match: &LineMatch) -> Element<'_, Message> {
+    let snippet = &line_match.snippet;
+    let range = line_match.highlight.clone();
+    row![
+        text(&snippet[..range.start]),
+        text(&snippet[range.clone()]).style(ACCENT_COLOR),
+        text(&snippet[range.end..]),
+    ]
+    .into()
+}
+
+fn create_line_match_row<'a>(
+    hit: &'a SearchHit,
+    line_match: &'a LineMatch,
+    is_previewed: bool,
+) -> Element<'a, Message> {
+    let name = hit.path.file_name().unwrap_or_default().to_string_lossy();
+    let info = button(
+        column![
+            row![
+                text(name),
+                text(format!("{}:{}", location(hit), line_match.line)).style(SECONDARY_TEXT_COLOR),
+            ]
+            .spacing(SPACING),
+            highlighted_snippet(line_match),
+        ]
+        .spacing(2),
+    )
+    .on_press(Message::OpenLineMatch(hit.path.clone(), line_match.line))
+    .style(theme::Button::Text)
+    .width(Length::Fill);
+
+    container(info)
+        .padding(PADDING / 2.0)
+        .width(Length::Fill)
+        .style(if is_previewed {
+            theme::Container::Custom(Box::new(SelectedItemStyle))
+        } else {
+            theme::Container::Transparent
+        })
+        .into()
+}
+
+// Scroll position showing the matched line a few lines below the top
+pub fn preview_offset(preview: &TextPreview) -> AbsoluteOffset {
+    let above = (preview.line - preview.first_line).saturating_sub(PREVIEW_LINES_ABOVE);
+    AbsoluteOffset {
+        x: 0.0,
+        y: above as f32 * PREVIEW_LINE_HEIGHT,
+    }
+}
+
+fn preview_line<'a>(number: usize, line: &'a str, is_match: bool) -> Element<'a, Message> {
+    let line_height = LineHeight::Absolute(Pixels(PREVIEW_LINE_HEIGHT));
+    let line_row = row![
+        text(number)
+            .size(PREVIEW_TEXT_SIZE)
+            .line_height(line_height)
+            .font(Font::MONOSPACE)
+            .style(SECONDARY_TEXT_COLOR)
+            .width(Length::Fixed(PREVIEW_NUMBER_WIDTH)),
+        text(line)
+            .size(PREVIEW_TEXT_SIZE)
+            .line_height(line_height)
+            .font(Font::MONOSPACE),
+    ]
+    .height(Length::Fixed(PREVIEW_LINE_HEIGHT));
+    container(line_row)
+        .style(if is_match {
+            theme::Container::Custom(Box::new(SelectedItemStyle))
+        } else {
+            theme::Container::Transparent
+        })
+        .into()
+}
+
+// The file of the clicked match, scrolled to the matching line
+fn build_text_preview(search: &Search) -> Element<'_, Message> {
+    let preview = match &search.preview {
+        Some(Ok(preview)) => preview,
+        Some(Err(e)) => return container(error_text(e)).padding(PADDING).into(),
+        None => return Space::with_width(Length::Shrink).into(),
+    };
+
+    let name = preview
+        .path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let header = row![
+        text(format!("{}:{}", name, preview.line)).width(Length::Fill),
+        button(text("Open"))
+            .on_press(Message::OpenSearchResult(preview.path.clone()))
+            .style(theme::Button::Secondary),
+    ]
+    .spacing(SPACING)
+    .align_items(Alignment::Center)
+    .padding(PADDING);
+
+    let lines = preview.lines.iter().enumerate().fold(
+        Column::new().padding(PADDING),
+        |lines, (index, line)| {
+            let number = preview.first_line + index;
+            lines.push(preview_line(number, line, number == preview.line))
+        },
+    );
+    // Long lines scroll sideways instead of wrapping, which would throw off
+    // the offset of the matched line
+    let lines = scrollable(lines)
+        .id(search.preview_id())
+        .direction(scrollable::Direction::Both {
+            vertical: scrollable::Properties::default(),
+            horizontal: scrollable::Properties::default(),
+        })
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+    column![
+        header,
+        Rule::horizontal(1).style(theme::Rule::Custom(Box::new(RuleStyle))),
+        lines
+    ]
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .into()
+}
+
+// Results of the tab's search, growing while the search runs. Text matches
+// open in a preview next to the results.
+pub fn build_search_results(search: &Search) -> Element<'_, Message> {
+    let source = if search.from_index { " (index)" } else { "" };
+    let status = if search.running {
+        format!("Searching… {} found", search.hits.len())
+    } else if search.truncated {
+        format!("First {} results", MAX_HITS)
+    } else {
+        format!(
+            "{} result{}",
+            search.hits.len(),
+            if search.hits.len() == 1 { "" } else { "s" }
+        )
+    };
+    let header = row![
+        text(format!("Search for '{}'", search.query)).size(20),
+        text(format!("{} in {}{}", status, search.root.display(), source))
+            .style(SECONDARY_TEXT_COLOR)
+            .width(Length::Fill),
+        button(text("Close"))
+            .on_press(Message::ClearSearch)
+            .style(theme::Button::Secondary),
+    ]
+    .spacing(SPACING)
+    .align_items(Alignment::Center)
+    .padding(PADDING);
+
+    let notice = if let Some(error) = &search.error {
+        Some(error_text(error))
+    } else if search.hits.is_empty() && !search.running {
+        Some(text("No matches").style(SECONDARY_TEXT_COLOR))
+    } else {
+        None
+    };
+    let previewed = match &search.preview {
+        Some(Ok(preview)) => Some((&preview.path, preview.line)),
+        _ => None,
+    };
+    let body: Element<Message> = match notice {
+        Some(notice) => container(notice)
+            .padding(PADDING * 2.0)
+            .center_x()
+            .center_y()
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into(),
+        None => {
+            let rows = search.hits.iter().fold(
+                Column::new().spacing(SPACING / 2.0).padding(PADDING),
+                |rows, hit| {
+                    rows.push(match &hit.line_match {
+                        Some(line_match) => create_line_match_row(
+                            hit,
+                            line_match,
+                            previewed == Some((&hit.path, line_match.line)),
+                        ),
+                        None => create_result_row(hit),
+                    })
+                },
+            );
+            let results = scrollable(rows).width(Length::Fill).height(Length::Fill);
+            if search.preview.is_some() {
+                Row::new()
+                    .push(container(results).width(Length::FillPortion(1)))
+                    .push(Rule::vertical(1).style(theme::Rule::Custom(Box::new(RuleStyle))))
+                    .push(container(build_text_preview(search)).width(Length::FillPortion(1)))
+                    .height(Length::Fill)
+                    .into()
+            } else {
+                results.into()
+            }
+        }
+    };
+
+    column![
+        header,
+        Rule::horizontal(1).style(theme::Rule::Custom(Box::new(RuleStyle))),
+        body
+    ]
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .into()
+}
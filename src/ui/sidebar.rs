@@ -22,7 +22,7 @@ fn sidebar_button_content(icon_path: &str, label: &str) -> Element<'static, Mess
     .into()
 }
 
-pub fn build_sidebar(_state: &FileManager) -> Element<Message> {
+pub fn build_sidebar(state: &FileManager) -> Element<'_, Message> {
     let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
     let applications_path = home_dir.join("Applications");
 
@@ -75,6 +75,21 @@ pub fn build_sidebar(_state: &FileManager) -> Element<Message> {
             );
         }
     }
+
+    sidebar_content = sidebar_content.push(
+        Rule::horizontal(1).style(theme::Rule::Custom(Box::new(RuleStyle))),
+    );
+    sidebar_content = sidebar_content.push(
+        button(sidebar_button_content(TRASH_ICON_PATH, "Trash"))
+            .on_press(Message::ShowTrash)
+            .style(if state.tab().showing_trash {
+                theme::Button::Secondary
+            } else {
+                theme::Button::Text
+            })
+            .width(Length::Fill)
+            .padding(PADDING),
+    );
     sidebar_content = sidebar_content.push(Space::with_height(Length::Fill));
 
     container(sidebar_content)
@@ -52,6 +52,76 @@ impl iced::widget::container::StyleSheet for SelectedItemStyle {
     }
 }
 
+// Outline of the pane keyboard and toolbar actions apply to in the split view
+pub struct ActivePaneStyle;
+impl iced::widget::container::StyleSheet for ActivePaneStyle {
+    type Style = Theme;
+    fn appearance(&self, _style: &Self::Style) -> iced::widget::container::Appearance {
+        iced::widget::container::Appearance {
+            text_color: Some(TEXT_COLOR),
+            background: None,
+            border: Border {
+                radius: 0.0.into(),
+                width: 1.0,
+                color: ACCENT_COLOR,
+            },
+            shadow: iced::Shadow::default(),
+        }
+    }
+}
+
+// Right-click menus: a white card with a shadow
+pub struct MenuStyle;
+impl iced::widget::container::StyleSheet for MenuStyle {
+    type Style = Theme;
+    fn appearance(&self, _style: &Self::Style) -> iced::widget::container::Appearance {
+        iced::widget::container::Appearance {
+            text_color: Some(TEXT_COLOR),
+            background: Some(Background::Color(Color::WHITE)),
+            border: Border {
+                radius: 4.0.into(),
+                width: 1.0,
+                color: BORDER_COLOR,
+            },
+            shadow: iced::Shadow {
+                color: Color::from_rgba(0.0, 0.0, 0.0, 0.2),
+                offset: Vector::new(0.0, 2.0),
+                blur_radius: 8.0,
+            },
+        }
+    }
+}
+
+// Entries of a right-click menu, highlighted under the cursor
+pub struct MenuItemStyle;
+impl iced::widget::button::StyleSheet for MenuItemStyle {
+    type Style = Theme;
+    fn active(&self, _style: &Self::Style) -> iced::widget::button::Appearance {
+        iced::widget::button::Appearance {
+            text_color: TEXT_COLOR,
+            background: None,
+            border: Border {
+                radius: 3.0.into(),
+                ..Border::default()
+            },
+            shadow: iced::Shadow::default(),
+            shadow_offset: Vector::default(),
+        }
+    }
+    fn hovered(&self, style: &Self::Style) -> iced::widget::button::Appearance {
+        iced::widget::button::Appearance {
+            background: Some(Background::Color(SELECTED_BG_COLOR)),
+            ..self.active(style)
+        }
+    }
+    fn disabled(&self, style: &Self::Style) -> iced::widget::button::Appearance {
+        iced::widget::button::Appearance {
+            text_color: SECONDARY_TEXT_COLOR,
+            ..self.active(style)
+        }
+    }
+}
+
 // Custom Container Style for the main background
 pub struct BackgroundStyle;
 impl iced::widget::container::StyleSheet for BackgroundStyle {
@@ -0,0 +1,75 @@
+use crate::app::{FileManager, Message};
+use crate::pane::Pane;
+use crate::tab::Tab;
+use iced::widget::{button, row, scrollable, text, tooltip, Row};
+use iced::{theme, Alignment, Element, Length};
+
+const PADDING: f32 = 4.0;
+const SPACING: f32 = 4.0;
+const TAB_MAX_WIDTH: f32 = 200.0;
+
+fn tab_button<'a>(tab: &'a Tab, is_active: bool, is_paste_target: bool) -> Element<'a, Message> {
+    let mut content = row![text(tab.title()).size(14).width(Length::Fill)]
+        .spacing(SPACING)
+        .align_items(Alignment::Center);
+
+    // Lets the clipboard be pasted into another tab's folder without switching
+    if is_paste_target && !tab.showing_trash {
+        content = content.push(tooltip(
+            button(text("Paste").size(12))
+                .on_press(Message::PasteIntoTab(tab.id))
+                .style(theme::Button::Text)
+                .padding(2),
+            text(format!("Paste into {}", tab.current_path.display())),
+            tooltip::Position::Bottom,
+        ));
+    }
+    content = content.push(
+        button(text("×").size(14))
+            .on_press(Message::CloseTab(tab.id))
+            .style(theme::Button::Text)
+            .padding([0, 4]),
+    );
+
+    button(content)
+        .on_press(Message::SelectTab(tab.id))
+        .style(if is_active {
+            theme::Button::Primary
+        } else {
+            theme::Button::Secondary
+        })
+        .padding([PADDING, PADDING * 2.0])
+        .width(Length::Fixed(TAB_MAX_WIDTH))
+        .into()
+}
+
+// One button per open tab of a pane plus "+" for a new one; only shown with
+// several tabs
+pub fn build_tab_bar<'a>(state: &'a FileManager, pane: &'a Pane) -> Element<'a, Message> {
+    let current_tab = state.tab().id;
+    let can_paste = state.clipboard.is_some();
+    let tabs = pane
+        .tabs
+        .iter()
+        .enumerate()
+        .fold(Row::new().spacing(SPACING), |tabs, (index, tab)| {
+            // The folder being worked in is not a paste target of its own
+            let is_paste_target = can_paste && tab.id != current_tab;
+            tabs.push(tab_button(tab, index == pane.active_tab, is_paste_target))
+        })
+        .push(
+            button(text("+"))
+                .on_press(Message::NewTab)
+                .style(theme::Button::Secondary)
+                .padding([PADDING, PADDING * 2.0]),
+        )
+        .align_items(Alignment::Center)
+        .padding(PADDING);
+
+    scrollable(tabs)
+        .direction(scrollable::Direction::Horizontal(
+            scrollable::Properties::new().width(2).scroller_width(2),
+        ))
+        .width(Length::Fill)
+        .into()
+}
@@ -1,13 +1,24 @@
-use crate::app::{FileManager, GroupCriteria, Message, SortCriteria, SortOrder};
+use crate::app::{FileManager, GroupCriteria, Message, SortCriteria, SortOrder, ViewMode};
 use crate::constants::*;
+use crate::index::IndexStatus;
+use crate::location::PathInput;
+use crate::ui::breadcrumbs::Breadcrumbs;
+use crate::ui::context_menu::ContextMenu;
+use crate::ui::file_grid::{build_menu, build_scrolling_menu, menu_choice, menu_entry};
+use crate::search::MatchMode;
+use crate::tab::Tab;
 use crate::ui::styles::{
     BreadcrumbEndSegmentStyle, BreadcrumbMiddleSegmentStyle, BreadcrumbSegmentStyle,
     BreadcrumbStartSegmentStyle, LinkButtonStyle, NavBackButtonStartStyle, NavButtonEndStyle,
-    NavButtonMiddleStyle,
+    NavButtonMiddleStyle, SECONDARY_TEXT_COLOR,
+};
+use iced::widget::{
+    button, checkbox, column, container, image, mouse_area, pick_list, row, text, text_input, Space,
 };
-use iced::widget::{button, checkbox, container, image, row, text, Space};
 use iced::{theme, Alignment, Element, Length, Theme};
-use std::path::{Component, PathBuf};
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 const PADDING: f32 = 8.0;
 const SPACING: f32 = 10.0;
@@ -16,16 +27,34 @@ const NAV_ICON_SIZE: f32 = 16.0; // Size for nav icons
 const SORT_ICON_SIZE: f32 = 16.0; // Size for sort icons
 const SORT_BUTTON_PADDING: f32 = 6.0; // Padding for sort icon buttons
 const BREADCRUMB_TEXT_SIZE: u16 = 14; // Keep text size for breadcrumbs
+const DROPDOWN_ICON_SIZE: f32 = 10.0;
 const TOGGLE_PANEL_ICON_SIZE: f32 = 16.0; // Size for the new toggle icon
+const SEARCH_INPUT_WIDTH: f32 = 200.0;
+const FILTER_INPUT_WIDTH: f32 = 220.0;
+
+pub fn path_input_id() -> text_input::Id {
+    text_input::Id::new("path-input")
+}
+
+pub fn search_input_id() -> text_input::Id {
+    text_input::Id::new("search-input")
+}
 
-pub fn build_top_bar(state: &FileManager) -> Element<Message> {
+// Back/forward/up buttons and the breadcrumbs of one tab. Each pane of the
+// split view has its own. `path_input` is the location being typed (Ctrl+L or
+// a click next to the breadcrumbs), shown as a text field instead of them.
+pub fn build_location_bar<'a>(
+    state: &'a FileManager,
+    tab: &'a Tab,
+    path_input: Option<&'a PathInput>,
+) -> Element<'a, Message> {
     // --- Navigation Buttons ---
     let back_button_inner = button(
         image(BACK_ICON_PATH)
             .width(Length::Fixed(NAV_ICON_SIZE))
             .height(Length::Fixed(NAV_ICON_SIZE)),
     )
-    .on_press_maybe(state.can_go_back().then_some(Message::GoBack))
+    .on_press_maybe(tab.can_go_back().then_some(Message::GoBack))
     .style(theme::Button::Secondary);
     let back_button = container(back_button_inner)
         .width(Length::Fixed(BUTTON_HEIGHT))
@@ -39,7 +68,7 @@ pub fn build_top_bar(state: &FileManager) -> Element<Message> {
             .width(Length::Fixed(NAV_ICON_SIZE))
             .height(Length::Fixed(NAV_ICON_SIZE)),
     )
-    .on_press_maybe(state.can_go_forward().then_some(Message::GoForward))
+    .on_press_maybe(tab.can_go_forward().then_some(Message::GoForward))
     .style(theme::Button::Secondary);
     let forward_button = container(forward_button_inner)
         .width(Length::Fixed(BUTTON_HEIGHT))
@@ -67,82 +96,250 @@ pub fn build_top_bar(state: &FileManager) -> Element<Message> {
         .align_items(Alignment::Center);
 
     // --- Breadcrumbs ---
-    let mut breadcrumbs = row![].align_items(Alignment::Center).spacing(-1.0); // Negative spacing
-    let mut current_breadcrumb_path = PathBuf::new();
+    let breadcrumbs: Element<Message> = if tab.showing_trash {
+        container(
+            button(text("Trash"))
+                .on_press(Message::ShowTrash)
+                .style(theme::Button::Custom(Box::new(LinkButtonStyle)))
+                .padding([PADDING / 2.0, PADDING, PADDING / 2.0, PADDING]),
+        )
+        .width(Length::Shrink)
+        .height(Length::Fixed(BUTTON_HEIGHT))
+        .center_y()
+        .style(theme::Container::Custom(Box::new(BreadcrumbSegmentStyle)))
+        .into()
+    } else {
+        build_breadcrumbs(state, tab)
+    };
 
-    let normal_components: Vec<_> = state
-        .current_path
-        .components()
-        .filter_map(|c| {
-            if let Component::Normal(name) = c {
-                Some(name)
-            } else {
-                None
+    let location: Element<Message> = match path_input {
+        Some(input) => {
+            let mut field = column![text_input("Location (Tab completes folder names)", &input.value)
+                .id(path_input_id())
+                .on_input(Message::PathInputChanged)
+                .on_submit(Message::SubmitPathInput)
+                .width(Length::Fill)]
+            .spacing(2);
+            if let Some(error) = &input.error {
+                field = field.push(
+                    text(error)
+                        .size(12)
+                        .style(theme::Text::Color(iced::Color::from_rgb8(200, 0, 0))),
+                );
+            } else if !input.candidates.is_empty() {
+                field = field.push(
+                    text(input.candidates.join("   "))
+                        .size(12)
+                        .style(SECONDARY_TEXT_COLOR),
+                );
             }
+            field.width(Length::Fill).into()
+        }
+        // A click on the space after the breadcrumbs edits the location
+        None if tab.showing_trash => breadcrumbs,
+        None => mouse_area(
+            container(breadcrumbs)
+                .width(Length::Fill)
+                .height(Length::Fixed(BUTTON_HEIGHT))
+                .center_y(),
+        )
+        .on_press(Message::FocusPathBar)
+        .into(),
+    };
+
+    row![
+        navigation_buttons,
+        Space::with_width(Length::Fixed(SPACING / 2.0)),
+        location,
+    ]
+    .spacing(SPACING)
+    .align_items(Alignment::Center)
+    .width(Length::Fill)
+    .into()
+}
+
+// The folders in `path`, once loaded, each opening that folder. `current` is
+// the one on the way to the open folder.
+fn build_subfolder_menu<'a>(
+    state: &'a FileManager,
+    path: &Path,
+    current: Option<&Path>,
+) -> Element<'a, Message> {
+    let entries = match &state.subfolders {
+        Some((folder, subfolders)) if folder == path && subfolders.is_empty() => {
+            vec![menu_entry("No folders", None)]
+        }
+        Some((folder, subfolders)) if folder == path => subfolders
+            .iter()
+            .map(|subfolder| {
+                let name = subfolder.file_name().unwrap_or_default().to_string_lossy();
+                menu_choice(
+                    &name,
+                    current == Some(subfolder.as_path()),
+                    Message::Navigate(subfolder.clone()),
+                )
+            })
+            .collect(),
+        _ => vec![menu_entry("Loading...", None)],
+    };
+    build_scrolling_menu(entries)
+}
+
+// One segment per folder from the root down to the open one, each with a
+// drop-down of the folders inside it. Segments that do not fit are collapsed
+// into a "…" menu.
+fn build_breadcrumbs<'a>(state: &'a FileManager, tab: &'a Tab) -> Element<'a, Message> {
+    let mut crumbs: Vec<(String, PathBuf)> = tab
+        .current_path
+        .ancestors()
+        .filter(|path| !path.as_os_str().is_empty())
+        .map(|path| {
+            let label = match path.file_name() {
+                Some(name) => name.to_string_lossy().into_owned(),
+                None => "Root".to_string(),
+            };
+            (label, path.to_path_buf())
         })
         .collect();
-    let has_root = state.current_path.has_root();
-    let total_segments = if has_root { 1 } else { 0 } + normal_components.len();
-
-    let mut current_segment_index = 0;
+    crumbs.reverse();
+    let count = crumbs.len();
 
-    if has_root {
-        let root_path = PathBuf::from("/");
-        let root_button = button(text("Root"))
-            .on_press(Message::Navigate(root_path))
-            .style(theme::Button::Custom(Box::new(LinkButtonStyle)))
-            .padding([PADDING / 2.0, PADDING, PADDING / 2.0, PADDING]);
+    let segments = crumbs
+        .iter()
+        .enumerate()
+        .map(|(index, (label, path))| {
+            let style: Box<dyn container::StyleSheet<Style = Theme>> = if count == 1 {
+                Box::new(BreadcrumbSegmentStyle)
+            } else if index == 0 {
+                Box::new(BreadcrumbStartSegmentStyle)
+            } else if index == count - 1 {
+                Box::new(BreadcrumbEndSegmentStyle)
+            } else {
+                Box::new(BreadcrumbMiddleSegmentStyle)
+            };
+            let segment_button = button(text(label))
+                .on_press(Message::Navigate(path.clone()))
+                .style(theme::Button::Custom(Box::new(LinkButtonStyle)))
+                .padding([PADDING / 2.0, PADDING / 2.0, PADDING / 2.0, PADDING]);
 
-        let style: Box<dyn container::StyleSheet<Style = Theme>> = if total_segments == 1 {
-            Box::new(BreadcrumbSegmentStyle)
-        } else {
-            Box::new(BreadcrumbStartSegmentStyle)
-        };
+            let current = crumbs.get(index + 1).map(|(_, next)| next.clone());
+            let menu_path = path.clone();
+            let dropdown = ContextMenu::new(
+                container(
+                    image(EXPANDED_ICON_PATH)
+                        .width(Length::Fixed(DROPDOWN_ICON_SIZE))
+                        .height(Length::Fixed(DROPDOWN_ICON_SIZE)),
+                )
+                .height(Length::Fixed(BUTTON_HEIGHT))
+                .padding([0.0, PADDING / 2.0, 0.0, 0.0])
+                .center_y(),
+                move || build_subfolder_menu(state, &menu_path, current.as_deref()),
+            )
+            .dropdown()
+            .on_open(Message::SubfoldersMenu(path.clone()));
 
-        breadcrumbs = breadcrumbs.push(
-            container(root_button)
-                .width(Length::Shrink)
+            container(row![segment_button, dropdown].align_items(Alignment::Center))
                 .height(Length::Fixed(BUTTON_HEIGHT))
                 .center_y()
-                .style(theme::Container::Custom(style)),
-        );
-        current_breadcrumb_path.push("/");
-        current_segment_index += 1;
-    }
+                .style(theme::Container::Custom(style))
+                .into()
+        })
+        .collect();
 
-    for name in normal_components.iter() {
-        let name_str = name.to_string_lossy();
-        current_breadcrumb_path.push(name);
-        let path_for_button = current_breadcrumb_path.clone();
-
-        let segment_button = button(text(name_str))
-            .on_press(Message::Navigate(path_for_button))
-            .style(theme::Button::Custom(Box::new(LinkButtonStyle)))
-            .padding([PADDING / 2.0, PADDING, PADDING / 2.0, PADDING]);
-
-        let style: Box<dyn container::StyleSheet<Style = Theme>> = if total_segments == 1 {
-            Box::new(BreadcrumbSegmentStyle)
-        } else if current_segment_index == 0 && has_root {
-            // If it's the first *after* root
-            Box::new(BreadcrumbMiddleSegmentStyle)
-        } else if current_segment_index == 0 && !has_root {
-            // If it's the very first segment (no root)
-            Box::new(BreadcrumbStartSegmentStyle)
-        } else if current_segment_index == total_segments - 1 {
-            Box::new(BreadcrumbEndSegmentStyle)
-        } else {
-            Box::new(BreadcrumbMiddleSegmentStyle)
-        };
+    // Lists the segments the row had no room for
+    let collapsed = Rc::new(Cell::new((0, 0)));
+    let overflow_collapsed = collapsed.clone();
+    let overflow = container(
+        ContextMenu::new(
+            container(text("…")).padding([PADDING / 2.0, PADDING]),
+            move || {
+                let (start, end) = overflow_collapsed.get();
+                build_menu(
+                    crumbs[start..end]
+                        .iter()
+                        .map(|(label, path)| menu_entry(label, Some(Message::Navigate(path.clone()))))
+                        .collect(),
+                )
+            },
+        )
+        .dropdown(),
+    )
+    .height(Length::Fixed(BUTTON_HEIGHT))
+    .center_y()
+    .style(theme::Container::Custom(Box::new(BreadcrumbMiddleSegmentStyle)));
 
-        breadcrumbs = breadcrumbs.push(
-            container(segment_button)
-                .width(Length::Shrink)
-                .height(Length::Fixed(BUTTON_HEIGHT))
-                .center_y()
-                .style(theme::Container::Custom(style)),
-        );
-        current_segment_index += 1;
+    Breadcrumbs::new(segments, overflow, collapsed)
+        .spacing(-1.0) // Negative spacing to make borders overlap
+        .into()
+}
+
+pub fn build_top_bar(state: &FileManager) -> Element<'_, Message> {
+    // With the split view every pane shows its own location instead
+    let location: Element<Message> = if state.panes.len() == 1 {
+        build_location_bar(state, state.tab(), state.path_input.as_ref())
+    } else {
+        Space::with_width(Length::Fill).into()
+    };
+
+    // Searches below the active tab's folder as you type (Ctrl+F)
+    let tab = state.tab();
+    let search_query = tab.search.as_ref().map_or("", |search| search.query.as_str());
+    // Name searches below the home folder use the index while it is on
+    let index_label = match state.search_index.as_ref().map(|index| &index.status) {
+        Some(IndexStatus::Loading) => "Index (loading)".to_string(),
+        Some(IndexStatus::Building(count)) => format!("Indexing ({})", count),
+        Some(IndexStatus::Failed(_)) => "Index (failed)".to_string(),
+        Some(IndexStatus::Ready(_)) | None => "Index".to_string(),
+    };
+    let mut search_input = text_input("Search", search_query)
+        .id(search_input_id())
+        .width(Length::Fixed(SEARCH_INPUT_WIDTH));
+    if !tab.showing_trash {
+        search_input = search_input.on_input(Message::SearchInputChanged);
+    }
+    let search_controls = row![
+        search_input,
+        pick_list(&MatchMode::ALL[..], Some(state.search_mode), Message::SetSearchMode),
+        checkbox(index_label, state.search_index.is_some())
+            .on_toggle(Message::ToggleSearchIndex)
+            .spacing(SPACING / 2.0),
+    ]
+    .spacing(SPACING / 2.0)
+    .align_items(Alignment::Center);
+
+    // Narrows the listed items with a query such as `type:image size:>5M`
+    let mut filter_input = text_input("Filter (type:image size:>5M)", &tab.filter_query)
+        .width(Length::Fixed(FILTER_INPUT_WIDTH));
+    if !tab.showing_trash {
+        filter_input = filter_input.on_input(Message::FilterInputChanged);
     }
+    let filter_status: Element<Message> = if let Some(error) = &tab.filter_error {
+        text(error)
+            .style(theme::Text::Color(iced::Color::from_rgb8(200, 0, 0)))
+            .into()
+    } else if tab.filter.is_some() {
+        text(format!(
+            "{} of {}",
+            tab.shown_entries().count(),
+            tab.entries.len()
+        ))
+        .into()
+    } else {
+        Space::with_width(Length::Shrink).into()
+    };
+    let filter_controls = row![filter_input, filter_status]
+        .spacing(SPACING / 2.0)
+        .align_items(Alignment::Center);
+
+    // Typing in the folder view filters the items instead of jumping to one
+    let type_to_filter_checkbox = checkbox("Type to filter", state.type_to_filter)
+        .on_toggle(Message::ToggleTypeToFilter)
+        .spacing(SPACING / 2.0);
+    let typed_filter: Element<Message> = if tab.type_ahead.is_filtering() {
+        text(format!("Filtering '{}'", tab.type_ahead.text)).into()
+    } else {
+        Space::with_width(Length::Shrink).into()
+    };
 
     // --- Toggle Hidden Files Checkbox ---
     let toggle_hidden_checkbox = checkbox(".file", state.show_hidden_files)
@@ -150,7 +347,7 @@ pub fn build_top_bar(state: &FileManager) -> Element<Message> {
         .spacing(SPACING / 2.0);
 
     // --- Sorting Buttons ---
-    let sort_name_icon = match state.sort_order {
+    let sort_name_icon = match state.tab().sort_order {
         SortOrder::Ascending => SORT_NAME_ASC_ICON_PATH,
         SortOrder::Descending => SORT_NAME_DESC_ICON_PATH,
     };
@@ -160,7 +357,7 @@ pub fn build_top_bar(state: &FileManager) -> Element<Message> {
             .height(Length::Fixed(SORT_ICON_SIZE)),
     )
     .on_press(Message::SetSortCriteria(SortCriteria::Name))
-    .style(if state.sort_criteria == SortCriteria::Name {
+    .style(if state.tab().sort_criteria == SortCriteria::Name {
         theme::Button::Primary // Highlight active sort
     } else {
         theme::Button::Secondary
@@ -173,7 +370,7 @@ pub fn build_top_bar(state: &FileManager) -> Element<Message> {
         .center_y()
         .style(theme::Container::Custom(Box::new(NavBackButtonStartStyle))); // Start style
 
-    let sort_size_icon = match state.sort_order {
+    let sort_size_icon = match state.tab().sort_order {
         SortOrder::Ascending => SORT_SIZE_ASC_ICON_PATH,
         SortOrder::Descending => SORT_SIZE_DESC_ICON_PATH,
     };
@@ -183,7 +380,7 @@ pub fn build_top_bar(state: &FileManager) -> Element<Message> {
             .height(Length::Fixed(SORT_ICON_SIZE)),
     )
     .on_press(Message::SetSortCriteria(SortCriteria::Size))
-    .style(if state.sort_criteria == SortCriteria::Size {
+    .style(if state.tab().sort_criteria == SortCriteria::Size {
         theme::Button::Primary
     } else {
         theme::Button::Secondary
@@ -196,7 +393,7 @@ pub fn build_top_bar(state: &FileManager) -> Element<Message> {
         .center_y()
         .style(theme::Container::Custom(Box::new(NavButtonMiddleStyle))); // Middle style
 
-    let sort_date_icon = match state.sort_order {
+    let sort_date_icon = match state.tab().sort_order {
         SortOrder::Ascending => SORT_DATE_ASC_ICON_PATH,
         SortOrder::Descending => SORT_DATE_DESC_ICON_PATH,
     };
@@ -206,7 +403,7 @@ pub fn build_top_bar(state: &FileManager) -> Element<Message> {
             .height(Length::Fixed(SORT_ICON_SIZE)),
     )
     .on_press(Message::SetSortCriteria(SortCriteria::ModifiedDate))
-    .style(if state.sort_criteria == SortCriteria::ModifiedDate {
+    .style(if state.tab().sort_criteria == SortCriteria::ModifiedDate {
         theme::Button::Primary
     } else {
         theme::Button::Secondary
@@ -219,7 +416,7 @@ pub fn build_top_bar(state: &FileManager) -> Element<Message> {
         .center_y()
         .style(theme::Container::Custom(Box::new(NavButtonMiddleStyle))); // Middle style
 
-    let sort_type_icon = match state.sort_order {
+    let sort_type_icon = match state.tab().sort_order {
         SortOrder::Ascending => SORT_TYPE_ASC_ICON_PATH,
         SortOrder::Descending => SORT_TYPE_DESC_ICON_PATH,
     };
@@ -229,7 +426,7 @@ pub fn build_top_bar(state: &FileManager) -> Element<Message> {
             .height(Length::Fixed(SORT_ICON_SIZE)),
     )
     .on_press(Message::SetSortCriteria(SortCriteria::Type))
-    .style(if state.sort_criteria == SortCriteria::Type {
+    .style(if state.tab().sort_criteria == SortCriteria::Type {
         theme::Button::Primary
     } else {
         theme::Button::Secondary
@@ -253,7 +450,7 @@ pub fn build_top_bar(state: &FileManager) -> Element<Message> {
     // --- End Sorting Buttons ---
 
     // --- Grouping Controls ---
-    let is_grouped_by_category = state.group_criteria == GroupCriteria::MimeType;
+    let is_grouped_by_category = state.tab().group_criteria == GroupCriteria::MimeType;
     let group_by_category_checkbox = checkbox("Group", is_grouped_by_category)
         .on_toggle(|is_checked| {
             if is_checked {
@@ -293,17 +490,61 @@ pub fn build_top_bar(state: &FileManager) -> Element<Message> {
         .style(theme::Container::Custom(Box::new(NavButtonEndStyle)));
     // --- End Toggle Details Panel Button ---
 
+    // Only shown once a copy or move has been started
+    let jobs_button: Element<Message> = if state.jobs.jobs().is_empty() {
+        Space::with_width(Length::Shrink).into()
+    } else {
+        button(text(format!("Jobs ({})", state.jobs.active_count())))
+            .on_press(Message::ToggleJobsPanel)
+            .style(if state.show_jobs_panel {
+                theme::Button::Primary
+            } else {
+                theme::Button::Secondary
+            })
+            .into()
+    };
+
+    // Switches the shown tab between icons and the details list
+    let view_mode_button = |label, view_mode| {
+        button(text(label))
+            .on_press(Message::SetViewMode(view_mode))
+            .style(if state.tab().view_mode == view_mode {
+                theme::Button::Primary
+            } else {
+                theme::Button::Secondary
+            })
+    };
+    let view_mode_controls = row![
+        view_mode_button("Grid", ViewMode::Grid),
+        view_mode_button("List", ViewMode::List),
+        view_mode_button("Columns", ViewMode::Columns),
+    ]
+    .spacing(-1.0)
+    .align_items(Alignment::Center);
+
+    let split_view_button = button(text("Split"))
+        .on_press(Message::ToggleSplitView)
+        .style(if state.panes.len() > 1 {
+            theme::Button::Primary
+        } else {
+            theme::Button::Secondary
+        });
+
     row![
-        navigation_buttons,
-        Space::with_width(Length::Fixed(SPACING / 2.0)),
-        breadcrumbs,
-        Space::with_width(Length::Fill), // Push controls to the right
+        location, // Fills the space left of the controls
+        search_controls,
+        filter_controls,
+        type_to_filter_checkbox,
+        typed_filter,
         toggle_hidden_checkbox,          // Use the checkbox here
         Space::with_width(Length::Fixed(SPACING / 2.0)), // Add spacing
         grouping_controls,               // Add grouping controls
         Space::with_width(Length::Fixed(SPACING / 2.0)), // Add spacing
         sorting_controls,                // Add sorting controls
         Space::with_width(Length::Fixed(SPACING / 2.0)), // Add spacing
+        view_mode_controls,
+        jobs_button,
+        split_view_button,
         toggle_panel_button,             // Add the new toggle button
     ]
     .padding(PADDING)
@@ -0,0 +1,138 @@
+use crate::app::{FileManager, Message};
+use crate::constants::{FILE_ICON_PATH, FOLDER_ICON_PATH};
+use crate::fs_utils;
+use crate::tab::Tab;
+use crate::trash::TrashEntry;
+use crate::ui::styles::{RuleStyle, SelectedItemStyle, SECONDARY_TEXT_COLOR};
+use iced::widget::{button, column, container, image, row, scrollable, text, Column, Rule};
+use iced::{theme, Alignment, Element, Length};
+
+const PADDING: f32 = 8.0;
+const SPACING: f32 = 10.0;
+const ROW_ICON_SIZE: f32 = 24.0;
+const DATE_COLUMN_WIDTH: f32 = 130.0;
+const SIZE_COLUMN_WIDTH: f32 = 80.0;
+
+pub fn format_deletion_date(entry: &TrashEntry) -> String {
+    entry
+        .deletion_date
+        .map(|date| date.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn create_trash_row<'a>(entry: &'a TrashEntry, is_selected: bool) -> Element<'a, Message> {
+    let icon_path = if entry.is_dir {
+        FOLDER_ICON_PATH
+    } else {
+        FILE_ICON_PATH
+    };
+    let original_location = entry
+        .original_path
+        .parent()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default();
+
+    let info = button(
+        row![
+            image(icon_path)
+                .width(Length::Fixed(ROW_ICON_SIZE))
+                .height(Length::Fixed(ROW_ICON_SIZE)),
+            column![
+                text(&entry.name),
+                text(original_location).style(SECONDARY_TEXT_COLOR),
+            ]
+            .spacing(2)
+            .width(Length::Fill),
+            text(format_deletion_date(entry)).width(Length::Fixed(DATE_COLUMN_WIDTH)),
+            text(fs_utils::format_size(Some(entry.size))).width(Length::Fixed(SIZE_COLUMN_WIDTH)),
+        ]
+        .spacing(SPACING)
+        .align_items(Alignment::Center),
+    )
+    .on_press(Message::ItemClicked(entry.trashed_path.clone()))
+    .style(theme::Button::Text)
+    .width(Length::Fill);
+
+    let actions = row![
+        button(text("Restore"))
+            .on_press(Message::RestoreFromTrash(entry.trashed_path.clone()))
+            .style(theme::Button::Secondary),
+        button(text("Delete permanently"))
+            .on_press(Message::DeleteFromTrash(entry.trashed_path.clone()))
+            .style(theme::Button::Destructive),
+    ]
+    .spacing(SPACING / 2.0)
+    .align_items(Alignment::Center);
+
+    container(
+        row![info, actions]
+            .spacing(SPACING)
+            .align_items(Alignment::Center),
+    )
+    .padding(PADDING / 2.0)
+    .width(Length::Fill)
+    .style(if is_selected {
+        theme::Container::Custom(Box::new(SelectedItemStyle))
+    } else {
+        theme::Container::Transparent
+    })
+    .into()
+}
+
+pub fn build_trash_view<'a>(state: &'a FileManager, tab: &'a Tab) -> Element<'a, Message> {
+    let header = row![
+        text("Trash").size(20),
+        text(format!(
+            "{} items, {}",
+            state.trash_entries.len(),
+            fs_utils::format_size(Some(state.trash_size()))
+        ))
+        .style(SECONDARY_TEXT_COLOR)
+        .width(Length::Fill),
+        button(text("Empty trash"))
+            .on_press_maybe((!state.trash_entries.is_empty()).then_some(Message::EmptyTrash))
+            .style(theme::Button::Destructive),
+    ]
+    .spacing(SPACING)
+    .align_items(Alignment::Center)
+    .padding(PADDING);
+
+    let body: Element<Message> = if let Some(error) = &tab.error {
+        container(text(error).style(theme::Text::Color(iced::Color::from_rgb8(200, 0, 0))))
+            .padding(PADDING * 2.0)
+            .center_x()
+            .center_y()
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    } else if state.trash_entries.is_empty() {
+        container(text("Trash is empty").style(SECONDARY_TEXT_COLOR))
+            .padding(PADDING * 2.0)
+            .center_x()
+            .center_y()
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    } else {
+        let rows = state.trash_entries.iter().fold(
+            Column::new().spacing(SPACING / 2.0).padding(PADDING),
+            |rows, entry| {
+                let is_selected = tab.selection.contains(&entry.trashed_path);
+                rows.push(create_trash_row(entry, is_selected))
+            },
+        );
+        scrollable(rows)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    };
+
+    column![
+        header,
+        Rule::horizontal(1).style(theme::Rule::Custom(Box::new(RuleStyle))),
+        body
+    ]
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .into()
+}
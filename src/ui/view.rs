@@ -1,28 +1,102 @@
-use crate::app::{FileManager, Message};
+use crate::app::{FileManager, Message, ViewMode};
+use crate::dialog::Dialog;
+use crate::fs_utils::ConflictChoice;
+use crate::ui::column_view;
 use crate::ui::details_panel; // Import module
 use crate::ui::file_grid; // Import module
+use crate::ui::jobs_panel;
+use crate::ui::list_view;
+use crate::ui::modal;
+use crate::ui::search_results;
 use crate::ui::sidebar; // Import module
-use crate::ui::styles::{BackgroundStyle, RuleStyle};
+use crate::ui::styles::{ActivePaneStyle, BackgroundStyle, RuleStyle};
+use crate::ui::tab_bar;
 use crate::ui::top_bar;
+use crate::ui::trash_view;
 
 // Import module
 use iced::widget::{column, container, row, Rule}; // Removed Space import
 use iced::{theme, Element, Length};
+use iced_aw::modal as modal_layer;
+
+// One pane: its tab bar, its location (in the split view) and the shown tab.
+// Messages from inside are tagged with the pane so they act on it.
+fn build_pane(state: &FileManager, index: usize) -> Element<'_, Message> {
+    let pane = &state.panes[index];
+    let tab = pane.tab();
+    let is_split = state.panes.len() > 1;
+
+    let mut content = column![].spacing(0);
+    if pane.tabs.len() > 1 {
+        content = content
+            .push(tab_bar::build_tab_bar(state, pane))
+            .push(Rule::horizontal(1).style(theme::Rule::Custom(Box::new(RuleStyle))));
+    }
+    if is_split {
+        content = content
+            .push(
+                container(top_bar::build_location_bar(
+                    state,
+                    tab,
+                    state.path_input.as_ref().filter(|_| index == state.active_pane),
+                ))
+                .padding(8),
+            )
+            .push(Rule::horizontal(1).style(theme::Rule::Custom(Box::new(RuleStyle))));
+    }
+    content = content.push(if tab.showing_trash {
+        trash_view::build_trash_view(state, tab)
+    } else if let Some(search) = &tab.search {
+        search_results::build_search_results(search)
+    } else {
+        match tab.view_mode {
+            ViewMode::Grid => file_grid::build_file_grid(state, tab),
+            ViewMode::List => list_view::build_list_view(state, tab),
+            ViewMode::Columns => column_view::build_column_view(state, tab),
+        }
+    });
+
+    let pane_element: Element<Message> = container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(if is_split && index == state.active_pane {
+            theme::Container::Custom(Box::new(ActivePaneStyle))
+        } else {
+            theme::Container::Transparent
+        })
+        .into();
+    pane_element.map(move |message| Message::InPane(index, Box::new(message)))
+}
 
 // The main view function, taking the application state as input
-pub fn view(state: &FileManager) -> Element<Message> {
+pub fn view(state: &FileManager) -> Element<'_, Message> {
     let sidebar = sidebar::build_sidebar(state); // Use module::function
     let top_bar = top_bar::build_top_bar(state); // Use module::function
-    let file_grid = file_grid::build_file_grid(state); // Use module::function
     let details_panel_content = details_panel::details_panel(state); // Corrected function name
 
-    let main_content_area = column![
+    // Side by side in the split view, otherwise a single pane
+    let panes = (0..state.panes.len()).fold(row![].spacing(0), |panes, index| {
+        let panes = if index > 0 {
+            panes.push(Rule::vertical(1).style(theme::Rule::Custom(Box::new(RuleStyle))))
+        } else {
+            panes
+        };
+        panes.push(build_pane(state, index))
+    });
+
+    let mut main_content_area = column![
         top_bar,
         Rule::horizontal(1).style(theme::Rule::Custom(Box::new(RuleStyle))), // Changed Rule::Custom to theme::Rule::Custom
-        file_grid
+        panes.height(Length::Fill)
     ]
     .spacing(0);
 
+    if state.show_jobs_panel && !state.jobs.jobs().is_empty() {
+        main_content_area = main_content_area
+            .push(Rule::horizontal(1).style(theme::Rule::Custom(Box::new(RuleStyle))))
+            .push(jobs_panel::build_jobs_panel(state));
+    }
+
     // --- Final Layout ---
     // Conditionally create the layout based on the show_details_panel flag
     let main_layout = if state.show_details_panel {
@@ -52,9 +126,19 @@ pub fn view(state: &FileManager) -> Element<Message> {
         .spacing(0)
     };
 
-    container(main_layout)
+    let underlay = container(main_layout)
         .width(Length::Fill)
         .height(Length::Fill)
-        .style(theme::Container::Custom(Box::new(BackgroundStyle))) // Added theme:: prefix
-        .into()
+        .style(theme::Container::Custom(Box::new(BackgroundStyle))); // Added theme:: prefix
+
+    // Dialogs are drawn on a modal layer above everything else
+    let layer = modal_layer(underlay, modal::build_dialog(state));
+    match state.dialog {
+        // A stray click must not cancel the job asking; Escape skips the item
+        Some(Dialog::Conflict(_)) => layer.on_esc(Message::ResolveConflict(ConflictChoice::Skip)),
+        _ => layer
+            .backdrop(Message::DialogCancelled)
+            .on_esc(Message::DialogCancelled),
+    }
+    .into()
 }
@@ -0,0 +1,145 @@
+// Watches the open folder with inotify so changes made by other programs show
+// up without a full reload.
+//
+// Events are collected until the folder has been quiet for DEBOUNCE (or
+// MAX_DELAY has passed since the first one), then reported as one batch of
+// changed item paths.
+use crate::app::Message;
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, Stream, StreamExt};
+use iced::{subscription, Subscription};
+use inotify::{EventMask, Inotify, WatchMask};
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::time::{timeout, Instant};
+
+const DEBOUNCE: Duration = Duration::from_millis(200);
+const MAX_DELAY: Duration = Duration::from_secs(1);
+const EVENT_BUFFER_SIZE: usize = 4096;
+
+#[derive(Debug, Clone)]
+pub enum DirectoryChange {
+    // These items were created, removed, renamed or modified
+    Items(Vec<PathBuf>),
+    // Too many events were dropped or the folder itself went away
+    Everything,
+}
+
+#[derive(Default)]
+struct PendingChanges {
+    names: BTreeSet<OsString>,
+    everything: bool,
+    // The folder itself was removed or moved, so its watch is gone
+    gone: bool,
+}
+
+impl PendingChanges {
+    fn record(&mut self, mask: EventMask, name: Option<OsString>) {
+        if mask.intersects(EventMask::DELETE_SELF | EventMask::MOVE_SELF | EventMask::IGNORED) {
+            self.everything = true;
+            self.gone = true;
+        } else if mask.contains(EventMask::Q_OVERFLOW) {
+            // Events were dropped, but the watch itself is still in place
+            self.everything = true;
+        } else if let Some(name) = name {
+            self.names.insert(name);
+        }
+    }
+
+    fn into_change(self, dir: &Path) -> DirectoryChange {
+        if self.everything {
+            DirectoryChange::Everything
+        } else {
+            DirectoryChange::Items(self.names.into_iter().map(|name| dir.join(name)).collect())
+        }
+    }
+}
+
+fn event_stream(
+    path: &Path,
+) -> io::Result<impl Stream<Item = io::Result<inotify::EventOwned>> + Unpin> {
+    let inotify = Inotify::init()?;
+    inotify.watches().add(
+        path,
+        WatchMask::CREATE
+            | WatchMask::DELETE
+            | WatchMask::MOVED_FROM
+            | WatchMask::MOVED_TO
+            | WatchMask::MODIFY
+            | WatchMask::CLOSE_WRITE
+            | WatchMask::ATTRIB
+            | WatchMask::DELETE_SELF
+            | WatchMask::MOVE_SELF,
+    )?;
+    inotify.into_event_stream([0u8; EVENT_BUFFER_SIZE])
+}
+
+async fn report(output: &mut mpsc::Sender<Message>, dir: &Path, changes: PendingChanges) {
+    let _ = output
+        .send(Message::DirectoryChanged(
+            dir.to_path_buf(),
+            changes.into_change(dir),
+        ))
+        .await;
+}
+
+// Reports changes inside `path` as `Message::DirectoryChanged`. The watch is
+// dropped as soon as the subscription is, i.e. when another folder is opened.
+pub fn watch(path: PathBuf) -> Subscription<Message> {
+    subscription::channel(
+        ("directory-watcher", path.clone()),
+        100,
+        move |mut output| async move {
+            let mut events = match event_stream(&path) {
+                Ok(events) => Some(events),
+                Err(e) => {
+                    eprintln!("Failed to watch {}: {}", path.display(), e);
+                    None
+                }
+            };
+
+            while let Some(stream) = events.as_mut() {
+                let first = match stream.next().await {
+                    Some(Ok(event)) => event,
+                    Some(Err(e)) => {
+                        eprintln!("Failed to read events for {}: {}", path.display(), e);
+                        break;
+                    }
+                    None => break,
+                };
+
+                let mut changes = PendingChanges::default();
+                changes.record(first.mask, first.name);
+                let deadline = Instant::now() + MAX_DELAY;
+                let mut ended = false;
+
+                // Keep collecting until the burst is over
+                loop {
+                    let wait = DEBOUNCE.min(deadline.saturating_duration_since(Instant::now()));
+                    match timeout(wait, stream.next()).await {
+                        Ok(Some(Ok(event))) => changes.record(event.mask, event.name),
+                        Ok(Some(Err(_))) | Ok(None) => {
+                            ended = true;
+                            break;
+                        }
+                        Err(_) => break,
+                    }
+                }
+
+                if changes.gone {
+                    ended = true;
+                }
+                report(&mut output, &path, changes).await;
+                if ended {
+                    break;
+                }
+            }
+
+            // Nothing left to watch; stay idle until the subscription is dropped
+            std::future::pending().await
+        },
+    )
+}